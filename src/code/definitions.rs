@@ -54,6 +54,56 @@ lazy_static! {
         map.insert(Opcode::Closure, Definition::new("OpClosure", &[2, 1]));
         map.insert(Opcode::GetFree, Definition::new("OpGetFree", &[1]));
         map.insert(Opcode::CurrClosure, Definition::new("OpCurrClosure", &[]));
+        map.insert(Opcode::Iter, Definition::new("OpIter", &[]));
+        // 'OpIterNext' has two operands - a 2-byte jump address and #loop-variables
+        map.insert(Opcode::IterNext, Definition::new("OpIterNext", &[2, 1]));
+        map.insert(Opcode::SetFree, Definition::new("OpSetFree", &[1]));
+        // 'OpCaptureLocal' and 'OpCaptureFree' push the cell of a variable
+        // rather than its value so that closures capture by reference
+        map.insert(Opcode::CaptureLocal, Definition::new("OpCaptureLocal", &[1]));
+        map.insert(Opcode::CaptureFree, Definition::new("OpCaptureFree", &[1]));
+        map.insert(Opcode::SetIndex, Definition::new("OpSetIndex", &[]));
+        map.insert(Opcode::JumpIfTrue, Definition::new("OpJumpIfTrue", &[2]));
+        // 'OpConcat' joins the given number of objects on the stack into a string
+        map.insert(Opcode::Concat, Definition::new("OpConcat", &[2]));
+        map.insert(Opcode::Mod, Definition::new("OpMod", &[]));
+        map.insert(Opcode::FloorDiv, Definition::new("OpFloorDiv", &[]));
+        map.insert(Opcode::Pow, Definition::new("OpPow", &[]));
+        map.insert(Opcode::BitAnd, Definition::new("OpBitAnd", &[]));
+        map.insert(Opcode::BitOr, Definition::new("OpBitOr", &[]));
+        map.insert(Opcode::BitXor, Definition::new("OpBitXor", &[]));
+        map.insert(Opcode::BitNot, Definition::new("OpBitNot", &[]));
+        map.insert(Opcode::ShiftLeft, Definition::new("OpShiftLeft", &[]));
+        map.insert(Opcode::ShiftRight, Definition::new("OpShiftRight", &[]));
+        // 'OpUnpackArray' has two operands - #names and a flag for a rest name
+        map.insert(Opcode::UnpackArray, Definition::new("OpUnpackArray", &[2, 1]));
+        map.insert(Opcode::UnpackMap, Definition::new("OpUnpackMap", &[2]));
+        map.insert(Opcode::Spread, Definition::new("OpSpread", &[]));
+        map.insert(Opcode::CallSpread, Definition::new("OpCallSpread", &[]));
+        map.insert(Opcode::Slice, Definition::new("OpSlice", &[]));
+        // Operand of 'OpGetProperty' is the index of the property name
+        map.insert(Opcode::GetProperty, Definition::new("OpGetProperty", &[2]));
+        // 'OpInvoke' has three operands - index of the method name, #args and
+        // whether the caller's binding of the name is on top of the stack
+        map.insert(Opcode::Invoke, Definition::new("OpInvoke", &[2, 1, 1]));
+        map.insert(
+            Opcode::InvokeSpread,
+            Definition::new("OpInvokeSpread", &[2, 1]),
+        );
+        // Unlike the other conditional jumps, these leave the value on the stack
+        map.insert(Opcode::JumpIfNil, Definition::new("OpJumpIfNil", &[2]));
+        map.insert(Opcode::JumpIfNotNil, Definition::new("OpJumpIfNotNil", &[2]));
+        // The shape tests of match patterns have the operands of the unpack opcodes
+        map.insert(Opcode::MatchArray, Definition::new("OpMatchArray", &[2, 1]));
+        map.insert(Opcode::MatchMap, Definition::new("OpMatchMap", &[2]));
+        map.insert(Opcode::NoMatch, Definition::new("OpNoMatch", &[]));
+        // The operand is the position of the exception handler
+        map.insert(Opcode::SetupTry, Definition::new("OpSetupTry", &[2]));
+        map.insert(Opcode::PopTry, Definition::new("OpPopTry", &[]));
+        map.insert(Opcode::Throw, Definition::new("OpThrow", &[]));
+        // The operand is the position after the deferred expression
+        map.insert(Opcode::Defer, Definition::new("OpDefer", &[2]));
+        map.insert(Opcode::EndDefer, Definition::new("OpEndDefer", &[]));
         map
     };
 }
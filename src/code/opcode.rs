@@ -31,6 +31,41 @@ pub enum Opcode {
     Closure,
     GetFree,
     CurrClosure,
+    Iter,
+    IterNext,
+    SetFree,
+    CaptureLocal,
+    CaptureFree,
+    SetIndex,
+    JumpIfTrue,
+    Concat,
+    Mod,
+    FloorDiv,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
+    UnpackArray,
+    UnpackMap,
+    Spread,
+    CallSpread,
+    Slice,
+    GetProperty,
+    Invoke,
+    InvokeSpread,
+    JumpIfNil,
+    JumpIfNotNil,
+    MatchArray,
+    MatchMap,
+    NoMatch,
+    SetupTry,
+    PopTry,
+    Throw,
+    Defer,
+    EndDefer,
     #[default]
     Invalid,
 }
@@ -68,6 +103,41 @@ impl From<u8> for Opcode {
             27 => Opcode::Closure,
             28 => Opcode::GetFree,
             29 => Opcode::CurrClosure,
+            30 => Opcode::Iter,
+            31 => Opcode::IterNext,
+            32 => Opcode::SetFree,
+            33 => Opcode::CaptureLocal,
+            34 => Opcode::CaptureFree,
+            35 => Opcode::SetIndex,
+            36 => Opcode::JumpIfTrue,
+            37 => Opcode::Concat,
+            38 => Opcode::Mod,
+            39 => Opcode::FloorDiv,
+            40 => Opcode::Pow,
+            41 => Opcode::BitAnd,
+            42 => Opcode::BitOr,
+            43 => Opcode::BitXor,
+            44 => Opcode::BitNot,
+            45 => Opcode::ShiftLeft,
+            46 => Opcode::ShiftRight,
+            47 => Opcode::UnpackArray,
+            48 => Opcode::UnpackMap,
+            49 => Opcode::Spread,
+            50 => Opcode::CallSpread,
+            51 => Opcode::Slice,
+            52 => Opcode::GetProperty,
+            53 => Opcode::Invoke,
+            54 => Opcode::InvokeSpread,
+            55 => Opcode::JumpIfNil,
+            56 => Opcode::JumpIfNotNil,
+            57 => Opcode::MatchArray,
+            58 => Opcode::MatchMap,
+            59 => Opcode::NoMatch,
+            60 => Opcode::SetupTry,
+            61 => Opcode::PopTry,
+            62 => Opcode::Throw,
+            63 => Opcode::Defer,
+            64 => Opcode::EndDefer,
             _ => Opcode::Invalid,
         }
     }
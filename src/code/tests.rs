@@ -91,7 +91,7 @@ fn test_read_operands() {
 
     for (op, operands, bytes_read) in tests {
         let instruction = definitions::make(op, &operands, 1);
-        let def = definitions::lookup(instruction.code[0] as u8).unwrap();
+        let def = definitions::lookup(instruction.code[0]).unwrap();
         let (operands_read, n) = definitions::read_operands(def, &instruction.code[1..]);
 
         assert_eq!(n, bytes_read, "n is wrong");
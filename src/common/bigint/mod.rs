@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+mod tests;
+
+// Each limb holds nine decimal digits, which keeps conversion to and from
+// decimal strings simple and the product of two limbs within a u64.
+const BASE: u64 = 1_000_000_000;
+
+// An arbitrary precision integer stored as a sign and a magnitude. The
+// magnitude is a vector of limbs with the least significant limb first.
+// It never has leading zero limbs, and zero is never negative, so equal
+// values always have the same representation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        let negative = negative && !limbs.is_empty();
+        Self { negative, limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // Parse digits in the given radix with an optional leading sign
+    pub fn from_str_radix(s: &str, radix: u32) -> Option<Self> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        let mut limbs = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix)?;
+            mul_add_small(&mut limbs, radix, digit);
+        }
+        Some(Self::new(negative, limbs))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        let mut value: i128 = 0;
+        for &limb in self.limbs.iter().rev() {
+            value = value.checked_mul(BASE as i128)? + limb as i128;
+            if value > i64::MAX as i128 + 1 {
+                return None;
+            }
+        }
+        let value = if self.negative { -value } else { value };
+        i64::try_from(value).ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let value = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0f64, |acc, &limb| acc * BASE as f64 + limb as f64);
+        if self.negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::new(!self.negative, self.limbs.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_mag(&self.limbs, &other.limbs));
+        }
+        match cmp_mag(&self.limbs, &other.limbs) {
+            Ordering::Less => Self::new(other.negative, sub_mag(&other.limbs, &self.limbs)),
+            _ => Self::new(self.negative, sub_mag(&self.limbs, &other.limbs)),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(
+            self.negative != other.negative,
+            mul_mag(&self.limbs, &other.limbs),
+        )
+    }
+
+    // Floor division and the matching remainder, which takes the sign of
+    // the divisor. Returns None if the divisor is zero.
+    pub fn div_rem_floor(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (q, r) = div_rem_mag(&self.limbs, &other.limbs);
+        let mut quotient = Self::new(self.negative != other.negative, q);
+        let mut remainder = Self::new(self.negative, r);
+        if !remainder.is_zero() && remainder.negative != other.negative {
+            quotient = quotient.sub(&Self::from(1));
+            remainder = remainder.add(other);
+        }
+        Some((quotient, remainder))
+    }
+
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut result = Self::from(1);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let negative = value < 0;
+        let mut magnitude = value.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+        Self::new(negative, limbs)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.limbs, &other.limbs),
+            (true, true) => cmp_mag(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some((last, rest)) = self.limbs.split_last() else {
+            return write!(f, "0");
+        };
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", last)?;
+        for limb in rest.iter().rev() {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+// Operations on magnitudes. The results may have leading zero limbs which
+// are removed by BigInt::new().
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    let a = trim(a);
+    let b = trim(b);
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn trim(mut a: &[u32]) -> &[u32] {
+    while let Some((0, rest)) = a.split_last() {
+        a = rest;
+    }
+    a
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// Requires a >= b
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let mut diff = limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += BASE as i64;
+            borrow = 1;
+        }
+        result.push(diff as u32);
+    }
+    result
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut result = vec![0u64; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = result[i + j] + x as u64 * y as u64 + carry;
+            result[i + j] = cur % BASE;
+            carry = cur / BASE;
+        }
+        result[i + b.len()] += carry;
+    }
+    result.into_iter().map(|limb| limb as u32).collect()
+}
+
+// a = a * m + d
+fn mul_add_small(a: &mut Vec<u32>, m: u32, d: u32) {
+    let mut carry = d as u64;
+    for limb in a.iter_mut() {
+        let cur = *limb as u64 * m as u64 + carry;
+        *limb = (cur % BASE) as u32;
+        carry = cur / BASE;
+    }
+    while carry > 0 {
+        a.push((carry % BASE) as u32);
+        carry /= BASE;
+    }
+}
+
+// Long division one limb at a time. Each limb of the quotient is found by
+// a binary search for the largest q such that b * q <= the remainder.
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for i in (0..a.len()).rev() {
+        remainder.insert(0, a[i]);
+        if cmp_mag(&remainder, b) == Ordering::Less {
+            continue;
+        }
+        let (mut lo, mut hi) = (1u64, BASE - 1);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if cmp_mag(&mul_mag(b, &[mid as u32]), &remainder) == Ordering::Greater {
+                hi = mid - 1;
+            } else {
+                lo = mid;
+            }
+        }
+        remainder = sub_mag(&remainder, &mul_mag(b, &[lo as u32]));
+        remainder.truncate(trim(&remainder).len());
+        quotient[i] = lo as u32;
+    }
+    (quotient, remainder)
+}
@@ -0,0 +1,115 @@
+#![allow(unused_imports)]
+use super::BigInt;
+
+#[cfg(test)]
+fn big(s: &str) -> BigInt {
+    BigInt::from_str_radix(s, 10).expect("invalid test number")
+}
+
+#[test]
+fn test_bigint_parse_and_display() {
+    let tests = [
+        ("0", "0"),
+        ("-0", "0"),
+        ("+42", "42"),
+        ("000123", "123"),
+        ("-1000000000", "-1000000000"),
+        (
+            "123456789012345678901234567890",
+            "123456789012345678901234567890",
+        ),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(big(input).to_string(), expected);
+    }
+    assert_eq!(
+        BigInt::from_str_radix("ffffffffffffffffffff", 16)
+            .unwrap()
+            .to_string(),
+        "1208925819614629174706175"
+    );
+    for input in ["", "-", "12a", "1.5"] {
+        assert!(BigInt::from_str_radix(input, 10).is_none(), "{}", input);
+    }
+}
+
+#[test]
+fn test_bigint_conversions() {
+    for value in [0, 1, -1, 999_999_999, 1_000_000_000, i64::MAX, i64::MIN] {
+        let b = BigInt::from(value);
+        assert_eq!(b.to_string(), value.to_string());
+        assert_eq!(b.to_i64(), Some(value));
+    }
+    assert_eq!(big("9223372036854775808").to_i64(), None);
+    assert_eq!(big("-9223372036854775809").to_i64(), None);
+    assert_eq!(big("-12345678901").to_f64(), -12345678901.);
+}
+
+#[test]
+fn test_bigint_arithmetic() {
+    let a = big("123456789012345678901234567890");
+    let b = big("987654321098765432109876543210");
+    assert_eq!(a.add(&b), big("1111111110111111111011111111100"));
+    assert_eq!(a.sub(&b), big("-864197532086419753208641975320"));
+    assert_eq!(b.sub(&a), big("864197532086419753208641975320"));
+    assert_eq!(a.sub(&a), BigInt::from(0));
+    assert_eq!(
+        a.mul(&b),
+        big("121932631137021795226185032733622923332237463801111263526900")
+    );
+    assert_eq!(a.neg().mul(&b), a.mul(&b).neg());
+    assert_eq!(
+        BigInt::from(2).pow(100),
+        big("1267650600228229401496703205376")
+    );
+    assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+    assert_eq!(BigInt::from(7).pow(0), BigInt::from(1));
+}
+
+#[test]
+fn test_bigint_division() {
+    let a = big("1267650600228229401496703205376");
+    let (q, r) = a.div_rem_floor(&big("1000000007")).unwrap();
+    assert_eq!(q, big("1267650591354675262013"));
+    assert_eq!(r, big("976371285"));
+    assert_eq!(q.mul(&big("1000000007")).add(&r), a);
+
+    // Floor division rounds towards negative infinity and the remainder
+    // takes the sign of the divisor
+    let tests = [
+        (7, 2, 3, 1),
+        (-7, 2, -4, 1),
+        (7, -2, -4, -1),
+        (-7, -2, 3, -1),
+        (6, 3, 2, 0),
+    ];
+    for (a, b, q, r) in tests {
+        let (quotient, remainder) = BigInt::from(a).div_rem_floor(&BigInt::from(b)).unwrap();
+        assert_eq!(quotient, BigInt::from(q), "{} ~/ {}", a, b);
+        assert_eq!(remainder, BigInt::from(r), "{} % {}", a, b);
+    }
+    assert!(a.div_rem_floor(&BigInt::from(0)).is_none());
+}
+
+#[test]
+fn test_bigint_ordering() {
+    let mut values = [
+        big("100000000000000000000"),
+        big("-5"),
+        big("0"),
+        big("-100000000000000000000"),
+        big("5"),
+    ];
+    values.sort();
+    let sorted: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        sorted,
+        [
+            "-100000000000000000000",
+            "-5",
+            "0",
+            "5",
+            "100000000000000000000"
+        ]
+    );
+}
@@ -7,6 +7,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub mod print;
 mod tests;
 
+use crate::common::bigint::BigInt;
 use crate::common::object::*;
 use print::format_buf;
 
@@ -29,6 +30,9 @@ lazy_static! {
             BuiltinFunction::new("println".into(), builtin_println),
             BuiltinFunction::new("eprint".into(), builtin_eprint),
             BuiltinFunction::new("eprintln".into(), builtin_eprintln),
+            BuiltinFunction::new("bigint".into(), builtin_bigint),
+            BuiltinFunction::new("help".into(), builtin_help),
+            BuiltinFunction::new("split".into(), builtin_split),
         ]
     };
 }
@@ -38,8 +42,9 @@ fn builtin_len(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         return Err(format!("takes one argument. got={}", args.len()));
     }
     match args[0].as_ref() {
-        Object::Str(s) => Ok(Rc::new(Object::Number(s.len() as f64))),
-        Object::Arr(a) => Ok(Rc::new(Object::Number(a.elements.len() as f64))),
+        // Strings are indexed by characters, so they are counted the same way
+        Object::Str(s) => Ok(Rc::new(Object::Integer(s.chars().count() as i64))),
+        Object::Arr(a) => Ok(Rc::new(Object::Integer(a.elements.borrow().len() as i64))),
         _ => Err(String::from("unsupported argument")),
     }
 }
@@ -69,7 +74,7 @@ fn builtin_first(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     }
     match args[0].as_ref() {
         Object::Arr(a) => {
-            if let Some(first_element) = a.elements.get(0) {
+            if let Some(first_element) = a.elements.borrow().first() {
                 Ok(Rc::clone(first_element))
             } else {
                 Ok(Rc::new(Object::Nil))
@@ -85,7 +90,7 @@ fn builtin_last(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     }
     match args[0].as_ref() {
         Object::Arr(a) => {
-            if let Some(last_element) = a.elements.last() {
+            if let Some(last_element) = a.elements.borrow().last() {
                 Ok(Rc::clone(last_element))
             } else {
                 Ok(Rc::new(Object::Nil))
@@ -101,12 +106,12 @@ fn builtin_rest(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     }
     match args[0].as_ref() {
         Object::Arr(a) => {
-            if a.elements.is_empty() {
+            if a.elements.borrow().is_empty() {
                 Ok(Rc::new(Object::Nil))
             } else {
-                Ok(Rc::new(Object::Arr(Rc::new(Array {
-                    elements: a.elements[1..].to_vec(),
-                }))))
+                Ok(Rc::new(Object::Arr(Rc::new(Array::new(
+                    a.elements.borrow()[1..].to_vec(),
+                )))))
             }
         }
         _ => Err(String::from("unsupported argument")),
@@ -119,11 +124,10 @@ fn builtin_push(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     }
     match args[0].as_ref() {
         Object::Arr(a) => {
-            let mut new_array = a.clone();
-            // Use interior mutability of the Array
-            let new_array_mut = Rc::make_mut(&mut new_array);
-            new_array_mut.elements.push(args[1].clone());
-            Ok(Rc::new(Object::Arr(new_array)))
+            // 'push' returns a new array and leaves the original untouched
+            let mut elements = a.elements.borrow().clone();
+            elements.push(args[1].clone());
+            Ok(Rc::new(Object::Arr(Rc::new(Array::new(elements)))))
         }
         _ => Err(String::from("unsupported argument")),
     }
@@ -139,6 +143,8 @@ fn builtin_str(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         obj,
         Object::Nil
             | Object::Str(_)
+            | Object::Integer(_)
+            | Object::BigInt(_)
             | Object::Number(_)
             | Object::Bool(_)
             | Object::Arr(_)
@@ -158,7 +164,7 @@ fn builtin_time(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
     let seconds = duration.as_secs();
-    Ok(Rc::new(Object::Number(seconds as f64)))
+    Ok(Rc::new(Object::Integer(seconds as i64)))
 }
 
 #[allow(unreachable_code)]
@@ -167,6 +173,9 @@ fn builtin_exit(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         return Err(format!("takes one argument. got={}", args.len()));
     }
     match args[0].as_ref() {
+        Object::Integer(code) => {
+            process::exit(*code as i32);
+        }
         Object::Number(code) => {
             process::exit(*code as i32);
         }
@@ -214,7 +223,7 @@ fn builtin_print(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         print!("{}", s);
         len += s.len();
     }
-    Ok(Rc::new(Object::Number(len as f64)))
+    Ok(Rc::new(Object::Integer(len as i64)))
 }
 
 fn builtin_println(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
@@ -231,7 +240,7 @@ fn builtin_println(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     // Newline at the end
     println!();
     len += 1;
-    Ok(Rc::new(Object::Number(len as f64)))
+    Ok(Rc::new(Object::Integer(len as i64)))
 }
 
 fn builtin_eprint(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
@@ -245,7 +254,7 @@ fn builtin_eprint(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
         eprint!("{}", s);
         len += s.len();
     }
-    Ok(Rc::new(Object::Number(len as f64)))
+    Ok(Rc::new(Object::Integer(len as i64)))
 }
 
 fn builtin_eprintln(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
@@ -262,5 +271,89 @@ fn builtin_eprintln(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
     // Newline at the end
     eprintln!();
     len += 1;
-    Ok(Rc::new(Object::Number(len as f64)))
+    Ok(Rc::new(Object::Integer(len as i64)))
+}
+
+// Convert an integer or a string of decimal digits with an optional sign
+// to a big integer
+fn builtin_bigint(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
+    if args.len() != 1 {
+        return Err(format!("takes one argument. got={}", args.len()));
+    }
+    match args[0].as_ref() {
+        Object::BigInt(_) => Ok(args[0].clone()),
+        Object::Integer(n) => Ok(Rc::new(Object::BigInt(BigInt::from(*n)))),
+        Object::Str(s) => match BigInt::from_str_radix(s.trim(), 10) {
+            Some(n) => Ok(Rc::new(Object::BigInt(n))),
+            None => Err(format!("invalid integer '{}'", s)),
+        },
+        _ => Err(String::from("unsupported argument")),
+    }
+}
+
+// Return the doc comment of a function defined with a documented 'let'
+// statement, or nil if the function has none
+fn builtin_help(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
+    if args.len() != 1 {
+        return Err(format!("takes one argument. got={}", args.len()));
+    }
+    let doc = match args[0].as_ref() {
+        Object::Func(f) => f.doc.clone(),
+        Object::CompiledFunc(f) => f.doc.clone(),
+        Object::Clos(c) => c.func.doc.clone(),
+        Object::Builtin(_) => None,
+        _ => return Err(String::from("unsupported argument")),
+    };
+    Ok(Rc::new(doc.map_or(Object::Nil, Object::Str)))
+}
+
+// Split a string into an array of strings at each occurrence of the
+// separator. An empty separator splits the string into characters.
+fn builtin_split(args: Vec<Rc<Object>>) -> Result<Rc<Object>, String> {
+    if args.len() != 2 {
+        return Err(format!("takes two arguments. got={}", args.len()));
+    }
+    let (Object::Str(s), Object::Str(sep)) = (args[0].as_ref(), args[1].as_ref()) else {
+        return Err(String::from("unsupported argument"));
+    };
+    let parts: Vec<Rc<Object>> = if sep.is_empty() {
+        s.chars()
+            .map(|c| Rc::new(Object::Str(c.to_string())))
+            .collect()
+    } else {
+        s.split(sep.as_str())
+            .map(|part| Rc::new(Object::Str(part.to_string())))
+            .collect()
+    };
+    Ok(Rc::new(Object::Arr(Rc::new(Array::new(parts)))))
+}
+
+// Resolve the function called by the method call 'receiver.name(...)'.
+// A map that has a function stored under 'name' calls it with just the
+// arguments. Otherwise the function bound to 'name' by the caller, if any,
+// or else the builtin 'name' is called with the receiver as the first
+// argument, which is indicated by the returned flag.
+pub fn lookup_method(
+    receiver: &Object,
+    name: &str,
+    binding: Option<Rc<Object>>,
+) -> Result<(Rc<Object>, bool), String> {
+    if let Object::Map(_) = receiver {
+        let member = receiver.get_property(name)?;
+        if !matches!(member.as_ref(), Object::Nil) {
+            return Ok((member, false));
+        }
+    }
+    if let Some(binding) = binding {
+        if matches!(
+            binding.as_ref(),
+            Object::Func(_) | Object::Builtin(_) | Object::Clos(_)
+        ) {
+            return Ok((binding, true));
+        }
+    }
+    match BUILTINS.iter().find(|b| b.name == name) {
+        Some(builtin) => Ok((Rc::new(Object::Builtin(Box::new(builtin.clone()))), true)),
+        None => Err(format!("undefined method '{}'", name)),
+    }
 }
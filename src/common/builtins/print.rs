@@ -47,31 +47,31 @@ fn format_obj(
     // Format based on NumberFormat
     let formatted = match num_fmt {
         NumberFormat::Boolean => {
-            if let Object::Number(num) = obj {
-                format!("{:b}", *num as usize)
+            if let Some(num) = obj.as_integer() {
+                format!("{:b}", num)
             } else {
-                Err(String::from("Can't format non-number as binary"))?
+                Err(String::from("Can't format non-integer as binary"))?
             }
         }
         NumberFormat::Octal => {
-            if let Object::Number(num) = obj {
-                format!("{:o}", *num as usize)
+            if let Some(num) = obj.as_integer() {
+                format!("{:o}", num)
             } else {
-                Err(String::from("Can't format non-number as octal"))?
+                Err(String::from("Can't format non-integer as octal"))?
             }
         }
         NumberFormat::Hex => {
-            if let Object::Number(num) = obj {
-                format!("{:x}", *num as usize)
+            if let Some(num) = obj.as_integer() {
+                format!("{:x}", num)
             } else {
-                Err(String::from("Can't format non-number as hex"))?
+                Err(String::from("Can't format non-integer as hex"))?
             }
         }
         NumberFormat::HexaDecimal => {
-            if let Object::Number(num) = obj {
-                format!("{:X}", *num as usize)
+            if let Some(num) = obj.as_integer() {
+                format!("{:X}", num)
             } else {
-                Err(String::from("Can't format non-number as hex"))?
+                Err(String::from("Can't format non-integer as hex"))?
             }
         }
         NumberFormat::None => {
@@ -86,7 +86,7 @@ fn format_obj(
     // Use default justification as right for number and left for everything else
     let justify = match justify {
         SpecJustify::Default => {
-            if let Object::Number(_) = obj {
+            if obj.is_number() {
                 SpecJustify::Right
             } else {
                 SpecJustify::Left
@@ -98,7 +98,7 @@ fn format_obj(
     // Handle justification and padding
     let justify = match justify {
         SpecJustify::Default => {
-            if let Object::Number(_) = obj {
+            if obj.is_number() {
                 SpecJustify::Right
             } else {
                 SpecJustify::Left
@@ -142,7 +142,7 @@ fn test_builtin_function_format_failures() {
         args: Vec<Rc<Object>>,
         expected: &'static str,
     }
-    let format_tests = vec![
+    let format_tests = [
         FormatTest {
             args: vec![
                 Rc::new(Object::Number(69420.)),
@@ -1,20 +1,37 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
 
+use crate::common::error::RTError;
 use crate::common::object::*;
 use crate::scanner::token::*;
 
 #[derive(Clone, Debug, Default)]
 pub struct Environment {
     env: HashMap<String, Rc<Object>>,
+    // names of the 'const' bindings
+    consts: HashSet<String>,
+    // 'const' bindings declared by blocks that have ended. These may be
+    // defined again but still not assigned to.
+    closed: HashSet<String>,
+    // 'const' bindings of the enclosing environments that were declared when
+    // the function whose call created this environment was defined. As in
+    // the compiler, a constant declared later does not affect the function.
+    outer_consts: HashSet<String>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
-    pub fn new_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
+    pub fn new_enclosing(
+        enclosing: Rc<RefCell<Environment>>,
+        outer_consts: HashSet<String>,
+    ) -> Environment {
         Environment {
             env: HashMap::new(),
+            consts: HashSet::new(),
+            closed: HashSet::new(),
+            outer_consts,
             enclosing: Some(enclosing),
         }
     }
@@ -35,7 +52,71 @@ impl Environment {
         }
     }
 
-    pub fn set(&mut self, token: &Token, value: Rc<Object>) {
-        self.env.insert(token.literal.clone(), value);
+    pub fn set(&mut self, token: &Token, value: Rc<Object>) -> Result<(), RTError> {
+        self.define(token, value, false)
+    }
+
+    pub fn set_const(&mut self, token: &Token, value: Rc<Object>) -> Result<(), RTError> {
+        self.define(token, value, true)
+    }
+
+    /// A 'const' binding of this environment, or of an enclosing one when
+    /// the function was defined, may not be redefined or shadowed.
+    fn define(&mut self, token: &Token, value: Rc<Object>, is_const: bool) -> Result<(), RTError> {
+        let name = &token.literal;
+        if self.is_const(name) {
+            return Err(RTError::new(
+                &format!("cannot redefine constant {}", name),
+                token.line,
+            ));
+        }
+        if is_const {
+            self.consts.insert(name.clone());
+        } else {
+            self.consts.remove(name);
+        }
+        self.closed.remove(name);
+        self.env.insert(name.clone(), value);
+        Ok(())
+    }
+
+    pub fn is_const(&self, name: &str) -> bool {
+        self.visible_consts().contains(name)
+    }
+
+    /// The constants that a binding of this environment, or of a function
+    /// defined in it, may not redefine or shadow
+    pub fn visible_consts(&self) -> HashSet<String> {
+        &self.consts() | &self.outer_consts
+    }
+
+    /// Update an existing binding in the inner most environment that
+    /// defines the identifier. Return an error if none of the environments
+    /// define it or if it is a 'const' binding, in which case nothing is
+    /// updated.
+    pub fn assign(&mut self, name: &str, value: Rc<Object>) -> Result<(), String> {
+        if let Some(obj) = self.env.get_mut(name) {
+            if self.consts.contains(name) {
+                return Err(format!("cannot assign to constant {}", name));
+            }
+            *obj = value;
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
+        } else {
+            Err(format!("Undefined identifier: '{}'", name))
+        }
+    }
+
+    pub fn consts(&self) -> HashSet<String> {
+        &self.consts - &self.closed
+    }
+
+    /// Let the constants declared since 'consts' was taken be defined again,
+    /// e.g. by the other branch of an 'if' or the next iteration of a loop,
+    /// once the block that declared them has ended
+    pub fn close_consts(&mut self, consts: &HashSet<String>) {
+        let declared = &self.consts() - consts;
+        self.closed.extend(declared);
     }
 }
@@ -1,4 +1,8 @@
 use std::fmt;
+use std::rc::Rc;
+
+use crate::common::object::ErrorObject;
+use crate::common::object::Object;
 
 // Compile error
 #[derive(Debug)]
@@ -23,10 +27,11 @@ impl CompileError {
 }
 
 // Runtime error
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RTError {
     pub msg: String,
     pub line: usize,
+    pub value: Option<Rc<Object>>, // the value of a 'throw'
 }
 
 impl fmt::Display for RTError {
@@ -40,6 +45,31 @@ impl RTError {
         Self {
             msg: msg.to_string(),
             line,
+            value: None,
         }
     }
+
+    // A thrown error object keeps the message and the line it was created
+    // with, so that rethrowing a caught error reports the original error
+    pub fn thrown(value: Rc<Object>, line: usize) -> Self {
+        let (msg, line) = match value.as_ref() {
+            Object::Error(err) => (err.msg.clone(), err.line),
+            value => (value.to_string(), line),
+        };
+        Self {
+            msg,
+            line,
+            value: Some(value),
+        }
+    }
+
+    // The value that a 'catch' receives
+    pub fn into_object(self) -> Rc<Object> {
+        self.value.unwrap_or_else(|| {
+            Rc::new(Object::Error(Rc::new(ErrorObject {
+                msg: self.msg,
+                line: self.line,
+            })))
+        })
+    }
 }
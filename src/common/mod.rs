@@ -1,4 +1,6 @@
+pub mod bigint;
 pub mod builtins;
 pub mod environment;
 pub mod error;
+pub mod module;
 pub mod object;
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::parser::ast::stmt::Pattern;
+use crate::parser::ast::stmt::Statement;
+use crate::parser::ast::Program;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+
+// Modules are the files loaded by 'import' statements. The compiler and the
+// evaluator both keep the files that are being loaded, innermost last, to
+// resolve the imports of a file relative to it and to detect import cycles.
+
+// Absolute path of the module imported as 'path' by the last of 'files'.
+// Without an importing file (e.g. in the REPL) the path is relative to the
+// current directory. Importing one of 'files' again is a cycle.
+pub fn resolve(path: &str, files: &[PathBuf]) -> Result<PathBuf, String> {
+    let dir = files
+        .last()
+        .and_then(|file| file.parent())
+        .unwrap_or(Path::new("."));
+    let resolved = dir
+        .join(path)
+        .canonicalize()
+        .map_err(|_| format!("module '{}' not found", path))?;
+    if let Some(pos) = files.iter().position(|file| *file == resolved) {
+        let cycle: Vec<String> = files[pos..]
+            .iter()
+            .chain([&resolved])
+            .map(|file| file_name(file))
+            .collect();
+        return Err(format!("import cycle: {}", cycle.join(" -> ")));
+    }
+    Ok(resolved)
+}
+
+// Read and parse a module. Only the first parse error is reported.
+pub fn load(path: &Path) -> Result<Program, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|_| format!("failed to read module '{}'", file_name(path)))?;
+    let mut parser = Parser::new(Scanner::new(&source));
+    let program = parser.parse_program();
+    match parser.parse_errors().first() {
+        Some(err) => Err(format!("module '{}': {}", file_name(path), err)),
+        None => Ok(program),
+    }
+}
+
+// Names of the bindings exported by a module. These are the names bound by
+// the top level 'let' statements, except the ones that start with an
+// underscore which are private to the module.
+pub fn exports(program: &Program) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for stmt in &program.statements {
+        let idents: Vec<_> = match stmt {
+            Statement::Let(stmt) => vec![&stmt.name],
+            Statement::Destructure(stmt) => match &stmt.pattern {
+                Pattern::Array(idents, rest) => idents.iter().chain(rest).collect(),
+                Pattern::Map(idents) => idents.iter().collect(),
+            },
+            _ => continue,
+        };
+        for ident in idents {
+            if !ident.value.starts_with('_') && !names.contains(&ident.value) {
+                names.push(ident.value.clone());
+            }
+        }
+    }
+    names
+}
+
+fn file_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
@@ -1,12 +1,13 @@
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::ops;
 use std::rc::Rc;
 
 use crate::code::definitions::Instructions;
+use crate::common::bigint::BigInt;
 use crate::common::environment::Environment;
 use crate::parser::ast::expr::*;
 use crate::parser::ast::stmt::*;
@@ -16,6 +17,8 @@ use crate::parser::ast::stmt::*;
 pub enum Object {
     Nil,
     Str(String),
+    Integer(i64),
+    BigInt(BigInt),
     Number(f64),
     Bool(bool),
     Return(Rc<Object>),
@@ -25,20 +28,38 @@ pub enum Object {
     Arr(Rc<Array>),
     Map(Rc<HMap>),
     Clos(Rc<Closure>),
+    Iter(Rc<Iter>),
+    Cell(FreeCell),
+    Error(Rc<ErrorObject>),
 }
 
+// A shared, mutable slot holding a variable captured by a closure. Both the
+// enclosing function and the closure refer to the same cell so that an
+// assignment made by either one is seen by the other.
+pub type FreeCell = Rc<RefCell<Rc<Object>>>;
+
 impl PartialEq for Object {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Object::Nil, Object::Nil) => true,
             (Object::Str(a), Object::Str(b)) => a.eq(b),
+            (Object::Integer(a), Object::Integer(b)) => a.eq(b),
             (Object::Number(a), Object::Number(b)) => a.eq(b),
+            // An integer equals a float that holds exactly the same value
+            (Object::Integer(a), Object::Number(b)) | (Object::Number(b), Object::Integer(a)) => {
+                float_to_integer(*b) == Some(*a)
+            }
+            (Object::BigInt(a), Object::BigInt(b)) => a.eq(b),
+            (Object::BigInt(a), n) | (n, Object::BigInt(a)) if n.is_number() => {
+                n.as_integer().is_some_and(|n| a.to_i64() == Some(n))
+            }
             (Object::Bool(a), Object::Bool(b)) => a.eq(b),
             (Object::Arr(a), Object::Arr(b)) => a.eq(b),
             (Object::Map(a), Object::Map(b)) => a.eq(b),
             (Object::Builtin(a), Object::Builtin(b)) => a.eq(b),
             (Object::CompiledFunc(a), Object::CompiledFunc(b)) => a.eq(b),
             (Object::Clos(a), Object::Clos(b)) => a.eq(b),
+            (Object::Error(a), Object::Error(b)) => a.eq(b),
             _ => false,
         }
     }
@@ -51,7 +72,21 @@ impl PartialOrd for Object {
         match (self, other) {
             (Object::Nil, Object::Nil) => None,
             (Object::Str(a), Object::Str(b)) => a.partial_cmp(b),
+            (Object::Integer(a), Object::Integer(b)) => a.partial_cmp(b),
             (Object::Number(a), Object::Number(b)) => a.partial_cmp(b),
+            (Object::Integer(a), Object::Number(b)) => match float_to_integer(*b) {
+                Some(b) => a.partial_cmp(&b),
+                None => (*a as f64).partial_cmp(b),
+            },
+            (Object::Number(a), Object::Integer(b)) => match float_to_integer(*a) {
+                Some(a) => a.partial_cmp(b),
+                None => a.partial_cmp(&(*b as f64)),
+            },
+            (Object::BigInt(a), Object::BigInt(b)) => a.partial_cmp(b),
+            (Object::BigInt(a), Object::Integer(b)) => a.partial_cmp(&BigInt::from(*b)),
+            (Object::Integer(a), Object::BigInt(b)) => BigInt::from(*a).partial_cmp(b),
+            (Object::BigInt(a), Object::Number(b)) => a.to_f64().partial_cmp(b),
+            (Object::Number(a), Object::BigInt(b)) => a.partial_cmp(&b.to_f64()),
             (Object::Bool(a), Object::Bool(b)) => a.partial_cmp(b),
             _ => None,
         }
@@ -63,6 +98,8 @@ impl Clone for Object {
         match self {
             Object::Nil => Object::Nil,
             Object::Str(s) => Object::Str(s.clone()),
+            Object::Integer(n) => Object::Integer(*n),
+            Object::BigInt(n) => Object::BigInt(n.clone()),
             Object::Number(n) => Object::Number(*n),
             Object::Bool(b) => Object::Bool(*b),
             Object::Return(r) => Object::Return(r.clone()),
@@ -72,6 +109,9 @@ impl Clone for Object {
             Object::Map(m) => Object::Map(m.clone()),
             Object::CompiledFunc(f) => Object::CompiledFunc(f.clone()),
             Object::Clos(f) => Object::Clos(f.clone()),
+            Object::Iter(i) => Object::Iter(i.clone()),
+            Object::Cell(c) => Object::Cell(c.clone()),
+            Object::Error(e) => Object::Error(e.clone()),
         }
     }
 }
@@ -80,17 +120,284 @@ impl Object {
     pub fn is_nil(&self) -> bool {
         matches!(self, Object::Nil)
     }
-    pub fn is_string(&self) -> bool {
-        matches!(self, Object::Str(_))
-    }
     pub fn is_number(&self) -> bool {
-        matches!(self, Object::Number(_))
+        matches!(
+            self,
+            Object::Integer(_) | Object::BigInt(_) | Object::Number(_)
+        )
     }
     pub fn is_falsey(&self) -> bool {
         matches!(self, Object::Bool(false) | Object::Nil)
     }
+    // Integers and floats that hold an exact integer within the range
+    // of an i64. These can be used as operands of bitwise operators.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Object::Integer(n) => Some(*n),
+            Object::Number(n) => float_to_integer(*n),
+            _ => None,
+        }
+    }
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Object::Integer(n) => Some(*n as f64),
+            Object::BigInt(n) => Some(n.to_f64()),
+            Object::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+    pub fn checked_add(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(a, b) => a.checked_add(b).map(Object::Integer).ok_or_else(overflow),
+            Operands::BigInts(a, b) => Ok(Object::BigInt(a.add(&b))),
+            Operands::Floats(a, b) => Ok(Object::Number(a + b)),
+        }
+    }
+    pub fn checked_sub(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(a, b) => a.checked_sub(b).map(Object::Integer).ok_or_else(overflow),
+            Operands::BigInts(a, b) => Ok(Object::BigInt(a.sub(&b))),
+            Operands::Floats(a, b) => Ok(Object::Number(a - b)),
+        }
+    }
+    pub fn checked_mul(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(a, b) => a.checked_mul(b).map(Object::Integer).ok_or_else(overflow),
+            Operands::BigInts(a, b) => Ok(Object::BigInt(a.mul(&b))),
+            Operands::Floats(a, b) => Ok(Object::Number(a * b)),
+        }
+    }
+    // Division of integers produces a float, use floor division ('~/') for
+    // an integral result. Division of big integers is always floor division
+    // so that the result stays exact.
+    pub fn checked_div(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(_, 0) => Err(String::from("division by zero")),
+            Operands::Integers(a, b) => Ok(Object::Number(a as f64 / b as f64)),
+            Operands::BigInts(..) => self.floor_div(other),
+            Operands::Floats(_, 0.0) => Err(String::from("division by zero")),
+            Operands::Floats(a, b) => Ok(Object::Number(a / b)),
+        }
+    }
+    pub fn floor_div(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(_, 0) => Err(String::from("division by zero")),
+            Operands::Integers(a, b) => {
+                let quotient = a.checked_div(b).ok_or_else(overflow)?;
+                if a % b != 0 && (a < 0) != (b < 0) {
+                    Ok(Object::Integer(quotient - 1))
+                } else {
+                    Ok(Object::Integer(quotient))
+                }
+            }
+            Operands::BigInts(a, b) => match a.div_rem_floor(&b) {
+                Some((quotient, _)) => Ok(Object::BigInt(quotient)),
+                None => Err(String::from("division by zero")),
+            },
+            Operands::Floats(_, 0.0) => Err(String::from("division by zero")),
+            Operands::Floats(a, b) => Ok(Object::Number((a / b).floor())),
+        }
+    }
+    // The remainder takes the sign of the divisor so that it is consistent
+    // with floor division: a == (a ~/ b) * b + a % b
+    pub fn checked_rem(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(_, 0) => Err(String::from("modulo by zero")),
+            Operands::Integers(a, b) => {
+                let rem = a.wrapping_rem(b);
+                if rem != 0 && (rem < 0) != (b < 0) {
+                    Ok(Object::Integer(rem + b))
+                } else {
+                    Ok(Object::Integer(rem))
+                }
+            }
+            Operands::BigInts(a, b) => match a.div_rem_floor(&b) {
+                Some((_, rem)) => Ok(Object::BigInt(rem)),
+                None => Err(String::from("modulo by zero")),
+            },
+            Operands::Floats(_, 0.0) => Err(String::from("modulo by zero")),
+            Operands::Floats(a, b) => Ok(Object::Number(a - b * (a / b).floor())),
+        }
+    }
+    // An integer raised to a negative integer produces a float. A big
+    // integer can only be raised to a non-negative exponent.
+    pub fn pow(&self, other: &Object) -> Result<Object, String> {
+        match numeric_operands(self, other)? {
+            Operands::Integers(a, b) if b >= 0 => u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_pow(b))
+                .map(Object::Integer)
+                .ok_or_else(overflow),
+            Operands::Integers(a, b) => Ok(Object::Number((a as f64).powf(b as f64))),
+            Operands::BigInts(a, b) => match b.to_i64().and_then(|b| u32::try_from(b).ok()) {
+                Some(b) => Ok(Object::BigInt(a.pow(b))),
+                None => Err(String::from(
+                    "bigint exponent must be a non-negative integer",
+                )),
+            },
+            Operands::Floats(a, b) => Ok(Object::Number(a.powf(b))),
+        }
+    }
+    pub fn checked_neg(&self) -> Result<Object, String> {
+        match self {
+            Object::Integer(n) => n.checked_neg().map(Object::Integer).ok_or_else(overflow),
+            Object::BigInt(n) => Ok(Object::BigInt(n.neg())),
+            Object::Number(n) => Ok(Object::Number(-n)),
+            _ => Err(invalid_operation()),
+        }
+    }
     pub fn is_a_valid_key(&self) -> bool {
-        matches!(self, Object::Str(_) | Object::Number(_) | Object::Bool(_))
+        matches!(
+            self,
+            Object::Str(_)
+                | Object::Integer(_)
+                | Object::BigInt(_)
+                | Object::Number(_)
+                | Object::Bool(_)
+        )
+    }
+    // Value of the property 'a.name', which is the same as 'a["name"]' for
+    // maps. Missing properties produce nil.
+    pub fn get_property(&self, name: &str) -> Result<Rc<Object>, String> {
+        match self {
+            Object::Map(map) => {
+                let key = Object::Str(name.to_string());
+                let value = map.pairs.borrow().get(&key).cloned();
+                Ok(value.unwrap_or_else(|| Rc::new(Object::Nil)))
+            }
+            // Errors caught by 'catch' have the message and the line
+            Object::Error(err) => Ok(Rc::new(match name {
+                "message" => Object::Str(err.msg.clone()),
+                "line" => Object::Integer(err.line as i64),
+                _ => Object::Nil,
+            })),
+            _ => Err(String::from("property access not supported")),
+        }
+    }
+    // Element of an array or character of a string at 'index'. Negative
+    // indices count from the end. Indices out of range produce nil. As with
+    // slices, the index must be an integer.
+    pub fn index_sequence(&self, index: &Object) -> Result<Rc<Object>, String> {
+        let nil = || Ok(Rc::new(Object::Nil));
+        match self {
+            Object::Arr(arr) => {
+                let Some(idx) = index.as_integer() else {
+                    return Err(String::from("invalid index to array object"));
+                };
+                let elements = arr.elements.borrow();
+                match sequence_position(idx, elements.len()) {
+                    Some(pos) => Ok(elements[pos].clone()),
+                    None => nil(),
+                }
+            }
+            Object::Str(s) => {
+                let Some(idx) = index.as_integer() else {
+                    return Err(String::from("invalid index to string object"));
+                };
+                match sequence_position(idx, s.chars().count()) {
+                    Some(pos) => Ok(Rc::new(Object::Str(s.chars().nth(pos).unwrap().into()))),
+                    None => nil(),
+                }
+            }
+            _ => Err(String::from("index operator not supported")),
+        }
+    }
+    // Python style slice of an array or a string. The bounds and the step
+    // are nil when omitted. Negative bounds count from the end and bounds
+    // out of range are clamped, so slicing never fails on the indices.
+    // Strings are sliced by characters rather than by bytes.
+    pub fn slice(&self, start: &Object, end: &Object, step: &Object) -> Result<Object, String> {
+        let bound = |obj: &Object| match obj {
+            Object::Nil => Ok(None),
+            obj => obj
+                .as_integer()
+                .map(Some)
+                .ok_or_else(|| String::from("slice indices must be integers")),
+        };
+        let (start, end) = (bound(start)?, bound(end)?);
+        let step = bound(step)?.unwrap_or(1);
+        if step == 0 {
+            return Err(String::from("slice step cannot be zero"));
+        }
+        match self {
+            Object::Arr(arr) => {
+                let elements = arr.elements.borrow();
+                let sliced = slice_positions(elements.len(), start, end, step)
+                    .map(|i| elements[i].clone())
+                    .collect();
+                Ok(Object::Arr(Rc::new(Array::new(sliced))))
+            }
+            Object::Str(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                let sliced = slice_positions(chars.len(), start, end, step)
+                    .map(|i| chars[i])
+                    .collect();
+                Ok(Object::Str(sliced))
+            }
+            _ => Err(String::from("slice operator not supported")),
+        }
+    }
+    // Elements bound by an array pattern with 'count' names, followed by an
+    // array of the remaining elements if the pattern has a rest name.
+    pub fn unpack_array(&self, count: usize, rest: bool) -> Result<Vec<Rc<Object>>, String> {
+        let Object::Arr(arr) = self else {
+            return Err(String::from("array pattern requires an array"));
+        };
+        let elements = arr.elements.borrow();
+        if elements.len() < count || (!rest && elements.len() > count) {
+            let expected = if rest { "at least " } else { "" };
+            return Err(format!(
+                "array pattern expects {}{} element(s), got {}",
+                expected,
+                count,
+                elements.len()
+            ));
+        }
+        let mut values = elements[..count].to_vec();
+        if rest {
+            let remaining = elements[count..].to_vec();
+            values.push(Rc::new(Object::Arr(Rc::new(Array::new(remaining)))));
+        }
+        Ok(values)
+    }
+    // Values bound by a map pattern. Every key must be present in the map.
+    pub fn unpack_map(&self, keys: &[Rc<Object>]) -> Result<Vec<Rc<Object>>, String> {
+        let Object::Map(map) = self else {
+            return Err(String::from("map pattern requires a map"));
+        };
+        let pairs = map.pairs.borrow();
+        keys.iter()
+            .map(|key| match pairs.get(key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(format!("map pattern key '{}' not found", key)),
+            })
+            .collect()
+    }
+    // Total order over valid map keys. Keys are ordered by type first
+    // (booleans, numbers and then strings) and then by their value.
+    pub fn cmp_key(&self, other: &Object) -> Ordering {
+        fn rank(obj: &Object) -> usize {
+            match obj {
+                Object::Bool(_) => 0,
+                Object::Integer(_) | Object::BigInt(_) | Object::Number(_) => 1,
+                Object::Str(_) => 2,
+                _ => 3,
+            }
+        }
+        match (self, other) {
+            (Object::Bool(a), Object::Bool(b)) => a.cmp(b),
+            (Object::Integer(a), Object::Integer(b)) => a.cmp(b),
+            (Object::Number(a), Object::Number(b)) => a.total_cmp(b),
+            (a, b) if a.is_number() && b.is_number() => {
+                self.partial_cmp(other).unwrap_or_else(|| {
+                    self.as_float()
+                        .unwrap()
+                        .total_cmp(&other.as_float().unwrap())
+                })
+            }
+            (Object::Str(a), Object::Str(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
     }
 }
 
@@ -99,6 +406,8 @@ impl fmt::Display for Object {
         match self {
             Self::Nil => write!(f, "nil"),
             Self::Str(s) => write!(f, "{}", s),
+            Self::Integer(val) => write!(f, "{}", val),
+            Self::BigInt(val) => write!(f, "{}", val),
             Self::Number(val) => write!(f, "{}", val),
             Self::Bool(val) => write!(f, "{}", val),
             Self::Return(val) => write!(f, "{}", val),
@@ -108,68 +417,77 @@ impl fmt::Display for Object {
             Self::Arr(val) => write!(f, "{}", val),
             Self::Map(val) => write!(f, "{}", val),
             Self::Clos(val) => write!(f, "{}", val),
+            Self::Iter(val) => write!(f, "{}", val),
+            Self::Cell(val) => write!(f, "{}", val.borrow()),
+            Self::Error(val) => write!(f, "{}", val),
         }
     }
 }
 
-impl ops::Add for &Object {
-    type Output = Object;
-
-    fn add(self, other: &Object) -> Object {
-        match (self, other) {
-            (&Object::Number(a), &Object::Number(b)) => Object::Number(a + b),
-            _ => panic!("Invalid operation"),
-        }
-    }
+// Operands of arithmetic operators. Integers are promoted to big integers
+// when one of the operands is a big integer, and to floats when one of the
+// operands is a float. Big integers are never mixed with floats, as that
+// would silently lose their precision.
+enum Operands {
+    Integers(i64, i64),
+    BigInts(BigInt, BigInt),
+    Floats(f64, f64),
 }
 
-impl ops::Sub for &Object {
-    type Output = Object;
-    fn sub(self, other: &Object) -> Object {
-        match (self, other) {
-            (&Object::Number(a), &Object::Number(b)) => Object::Number(a - b),
-            _ => panic!("Invalid operation"),
+fn numeric_operands(left: &Object, right: &Object) -> Result<Operands, String> {
+    match (left, right) {
+        (Object::Integer(a), Object::Integer(b)) => Ok(Operands::Integers(*a, *b)),
+        (Object::BigInt(a), Object::BigInt(b)) => Ok(Operands::BigInts(a.clone(), b.clone())),
+        (Object::BigInt(a), Object::Integer(b)) => {
+            Ok(Operands::BigInts(a.clone(), BigInt::from(*b)))
+        }
+        (Object::Integer(a), Object::BigInt(b)) => {
+            Ok(Operands::BigInts(BigInt::from(*a), b.clone()))
         }
+        (Object::BigInt(_), _) | (_, Object::BigInt(_))
+            if left.is_number() && right.is_number() =>
+        {
+            Err(String::from("cannot mix bigint and float operands"))
+        }
+        _ => match (left.as_float(), right.as_float()) {
+            (Some(a), Some(b)) => Ok(Operands::Floats(a, b)),
+            _ => Err(invalid_operation()),
+        },
     }
 }
 
-impl ops::Mul for &Object {
-    type Output = Object;
-    fn mul(self, other: &Object) -> Object {
-        match (self, other) {
-            (&Object::Number(a), &Object::Number(b)) => Object::Number(a * b),
-            _ => panic!("Invalid operation"),
-        }
-    }
+fn overflow() -> String {
+    String::from("integer overflow")
 }
 
-impl ops::Div for &Object {
-    type Output = Object;
-    fn div(self, other: &Object) -> Object {
-        match (self, other) {
-            (&Object::Number(a), &Object::Number(b)) => Object::Number(a / b),
-            _ => panic!("Invalid operation"),
-        }
-    }
+fn invalid_operation() -> String {
+    String::from("invalid binary operation")
 }
 
-impl ops::Neg for &Object {
-    type Output = Object;
-    fn neg(self) -> Object {
-        match self {
-            &Object::Number(a) => Object::Number(-a),
-            _ => panic!("Invalid operation"),
-        }
+// The integer value of a float, if it holds an exact integer that fits
+// in an i64 (-2^63 <= n < 2^63)
+fn float_to_integer(n: f64) -> Option<i64> {
+    if n.fract() == 0.0 && n >= -(2f64.powi(63)) && n < 2f64.powi(63) {
+        Some(n as i64)
+    } else {
+        None
     }
 }
 
 impl Hash for Object {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            Object::Number(ref n) => {
-                // Use the built-in hash function for f64
-                state.write_u64(n.to_bits());
-            }
+            // Equal integers and floats must hash the same, so a float that
+            // holds an exact integer is hashed as that integer
+            Object::Integer(n) => state.write_i64(*n),
+            Object::BigInt(n) => match n.to_i64() {
+                Some(i) => state.write_i64(i),
+                None => n.hash(state),
+            },
+            Object::Number(n) => match float_to_integer(*n) {
+                Some(i) => state.write_i64(i),
+                None => state.write_u64(n.to_bits()),
+            },
             Object::Bool(ref b) => b.hash(state),
             Object::Str(ref s) => s.hash(state),
             _ => "".hash(state),
@@ -180,22 +498,87 @@ impl Hash for Object {
 #[derive(Debug, Clone)]
 pub struct Function {
     pub params: Vec<Identifier>,
+    pub defaults: Vec<Expression>,
+    pub rest: Option<Identifier>,
     pub body: BlockStatement,
     pub env: Rc<RefCell<Environment>>,
+    // constants that the bindings of the function may not shadow
+    pub consts: HashSet<String>,
+    pub doc: Option<String>,
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let params_str = self
-            .params
-            .iter()
-            .map(|p| format!("{}, ", p))
-            .collect::<String>();
-        let params_str = params_str.trim_end_matches(|c| c == ' ' || c == ',');
+        let params_str = format_params(&self.params, &self.defaults, &self.rest);
         write!(f, "fn({}) {{\n{}\n}}\n", params_str, self.body)
     }
 }
 
+// Position of 'index' in a sequence of 'len' items. Negative indices count
+// from the end of the sequence.
+fn sequence_position(index: i64, len: usize) -> Option<usize> {
+    let len = len as i64;
+    let index = if index < 0 { index + len } else { index };
+    (0..len).contains(&index).then_some(index as usize)
+}
+
+// Positions of the items in a slice of a sequence of 'len' items. This
+// follows Python, where the bounds default to the whole sequence in the
+// direction of the step, and are clamped to the sequence otherwise.
+fn slice_positions(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: i64,
+) -> impl Iterator<Item = usize> {
+    let len = len as i64;
+    // The range of positions that the bounds are clamped to
+    let (lower, upper) = if step > 0 { (0, len) } else { (-1, len - 1) };
+    let clamp = |bound: i64| {
+        if bound < 0 {
+            (bound + len).max(lower)
+        } else {
+            bound.min(upper)
+        }
+    };
+    let start = start.map_or(if step > 0 { lower } else { upper }, clamp);
+    let end = end.map_or(if step > 0 { upper } else { lower }, clamp);
+    // The position after the last one may not fit an i64 for a large step
+    std::iter::successors(Some(start), move |i| i.checked_add(step))
+        .take_while(move |&i| if step > 0 { i < end } else { i > end })
+        .map(|i| i as usize)
+}
+
+// Check the number of arguments passed to a function that accepts between
+// 'min' and 'max' arguments, or any number above 'min' if 'max' is None
+pub fn check_arity(min: usize, max: Option<usize>, got: usize) -> Result<(), String> {
+    if got >= min && max.is_none_or(|max| got <= max) {
+        return Ok(());
+    }
+    let want = match max {
+        Some(max) if max == min => min.to_string(),
+        Some(max) => format!("{}..{}", min, max),
+        None => format!("{}..", min),
+    };
+    Err(format!(
+        "wrong number of arguments: want={}, got={}",
+        want, got
+    ))
+}
+
+// A runtime error, or an error returned by a builtin, as seen by a 'catch'
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorObject {
+    pub msg: String,
+    pub line: usize,
+}
+
+impl fmt::Display for ErrorObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error: {}", self.msg)
+    }
+}
+
 pub type BuiltinFunctionProto = fn(Vec<Rc<Object>>) -> Result<Rc<Object>, String>;
 
 #[derive(Debug, Clone)]
@@ -222,39 +605,86 @@ impl PartialEq for BuiltinFunction {
     }
 }
 
+// Arrays and maps are shared by reference. Interior mutability allows
+// index assignments to update them in place and every binding that refers
+// to the same array or map sees the update.
 #[derive(Debug, Clone)]
 pub struct Array {
-    pub elements: Vec<Rc<Object>>,
+    pub elements: RefCell<Vec<Rc<Object>>>,
+}
+
+impl Array {
+    pub fn new(elements: Vec<Rc<Object>>) -> Self {
+        Self {
+            elements: RefCell::new(elements),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct HMap {
-    pub pairs: HashMap<Rc<Object>, Rc<Object>>,
+    pub pairs: RefCell<HashMap<Rc<Object>, Rc<Object>>>,
+}
+
+impl HMap {
+    #[allow(clippy::mutable_key_type)]
+    pub fn new(pairs: HashMap<Rc<Object>, Rc<Object>>) -> Self {
+        Self {
+            pairs: RefCell::new(pairs),
+        }
+    }
+}
+
+thread_local! {
+    // Arrays and maps being displayed or compared. An array or a map can
+    // hold itself after an index assignment, so they are only visited once.
+    static VISITING: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+// Call 'f' unless the containers are already being visited, in which case
+// there is a cycle and None is returned
+fn visit<T>(a: *const (), b: *const (), f: impl FnOnce() -> T) -> Option<T> {
+    let key = (a as usize, b as usize);
+    if VISITING.with(|v| v.borrow().contains(&key)) {
+        return None;
+    }
+    VISITING.with(|v| v.borrow_mut().push(key));
+    let result = f();
+    VISITING.with(|v| v.borrow_mut().pop());
+    Some(result)
 }
 
 impl fmt::Display for Array {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let elements_str = self
-            .elements
-            .iter()
-            .map(|p| format!("{}, ", p))
-            .collect::<String>();
-        let elements_str = elements_str.trim_end_matches(|c| c == ' ' || c == ',');
-        write!(f, "[{}]", elements_str)
+        let elements_str = visit(self as *const _ as _, std::ptr::null(), || {
+            self.elements
+                .borrow()
+                .iter()
+                .map(|p| format!("{}, ", p))
+                .collect::<String>()
+        });
+        match elements_str {
+            Some(elements_str) => write!(f, "[{}]", elements_str.trim_end_matches([' ', ','])),
+            None => write!(f, "[...]"),
+        }
     }
 }
 
 impl PartialEq for Array {
     fn eq(&self, other: &Self) -> bool {
-        if self.elements.len() != other.elements.len() {
+        let elements = self.elements.borrow();
+        let other_elements = other.elements.borrow();
+        if elements.len() != other_elements.len() {
             return false;
         }
-        for (a, b) in self.elements.iter().zip(&other.elements) {
-            if *a != *b {
-                return false;
-            }
-        }
-        true
+        // Arrays that hold each other are equal if nothing else differs
+        visit(self as *const _ as _, other as *const _ as _, || {
+            elements
+                .iter()
+                .zip(other_elements.iter())
+                .all(|(a, b)| a == b)
+        })
+        .unwrap_or(true)
     }
 }
 
@@ -262,32 +692,34 @@ impl Eq for Array {}
 
 impl fmt::Display for HMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let pairs_str = self
-            .pairs
-            .iter()
-            .map(|(k, v)| format!(r#""{}": {}, "#, k, v))
-            .collect::<String>();
-        let pairs_str = pairs_str.trim_end_matches(|c| c == ' ' || c == ',');
-        write!(f, "{{{}}}", pairs_str)
+        let pairs_str = visit(self as *const _ as _, std::ptr::null(), || {
+            self.pairs
+                .borrow()
+                .iter()
+                .map(|(k, v)| format!(r#""{}": {}, "#, k, v))
+                .collect::<String>()
+        });
+        match pairs_str {
+            Some(pairs_str) => write!(f, "{{{}}}", pairs_str.trim_end_matches([' ', ','])),
+            None => write!(f, "{{...}}"),
+        }
     }
 }
 
 // compare HMap objects without considering the order of key-value pairs
 impl PartialEq for HMap {
     fn eq(&self, other: &Self) -> bool {
-        if self.pairs.len() != other.pairs.len() {
+        let pairs = self.pairs.borrow();
+        let other_pairs = other.pairs.borrow();
+        if pairs.len() != other_pairs.len() {
             return false;
         }
-        for (key, value) in &self.pairs {
-            if let Some(other_value) = other.pairs.get(key) {
-                if value != other_value {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
-        true
+        visit(self as *const _ as _, other as *const _ as _, || {
+            pairs
+                .iter()
+                .all(|(key, value)| other_pairs.get(key) == Some(value))
+        })
+        .unwrap_or(true)
     }
 }
 
@@ -305,6 +737,16 @@ pub struct CompiledFunction {
     pub instructions: Rc<Instructions>,
     pub num_locals: usize,
     pub num_params: usize,
+    // Range of the number of arguments accepted. 'max_args' is None if the
+    // last parameter collects the remaining arguments into an array.
+    pub min_args: usize,
+    pub max_args: Option<usize>,
+    // Offsets to start executing from, indexed by the number of optional
+    // arguments passed. The instructions before the body evaluate the
+    // default values of the parameters in order, so a call starts at the
+    // default value of the first missing argument.
+    pub entries: Vec<usize>,
+    pub doc: Option<String>,
 }
 
 impl CompiledFunction {
@@ -313,6 +755,10 @@ impl CompiledFunction {
             instructions: Rc::new(instructions),
             num_locals,
             num_params,
+            min_args: num_params,
+            max_args: Some(num_params),
+            entries: Vec::new(),
+            doc: None,
         }
     }
 }
@@ -335,18 +781,18 @@ impl Eq for CompiledFunction {}
 // a place to keep the free variables it carries around, 'free'. This object
 // is used to represent functions that 'close over' their environment at the
 // time of their definition. The environment here is captured in a vector of
-// Object's. Note that closures are only created at runtime and aren't
-// available to the compiler. Instead an opcode 'OpClosure' is used by the
-// compiler to inform the VM to create a closure and wrap the function and
-// its environment.
+// cells shared with the enclosing function. Note that closures are only
+// created at runtime and aren't available to the compiler. Instead an opcode
+// 'OpClosure' is used by the compiler to inform the VM to create a closure
+// and wrap the function and its environment.
 #[derive(Debug, Clone, Default)]
 pub struct Closure {
     pub func: Rc<CompiledFunction>,
-    pub free: Vec<Rc<Object>>,
+    pub free: Vec<FreeCell>,
 }
 
 impl Closure {
-    pub fn new(func: Rc<CompiledFunction>, free: Vec<Rc<Object>>) -> Self {
+    pub fn new(func: Rc<CompiledFunction>, free: Vec<FreeCell>) -> Self {
         Self { func, free }
     }
 }
@@ -362,3 +808,80 @@ impl PartialEq for Closure {
         self.func == other.func
     }
 }
+
+// The state of a 'for .. in' loop over an array, a map or a string. Each
+// entry is a key-value pair. Arrays and strings are keyed by the position
+// of their elements (characters for strings). Maps are visited in sorted
+// key order so that the iteration order is deterministic. When the loop
+// has a single variable, it is bound to the value of each entry for arrays
+// and strings, and to the key of each entry for maps.
+#[derive(Debug, Clone)]
+pub struct Iter {
+    entries: Rc<Vec<(Rc<Object>, Rc<Object>)>>,
+    keyed: bool,
+    pos: usize,
+}
+
+impl Iter {
+    pub fn new(obj: &Object) -> Option<Self> {
+        let (entries, keyed) = match obj {
+            Object::Arr(arr) => (
+                arr.elements
+                    .borrow()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| (Rc::new(Object::Integer(i as i64)), e.clone()))
+                    .collect(),
+                false,
+            ),
+            Object::Str(s) => (
+                s.chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        (
+                            Rc::new(Object::Integer(i as i64)),
+                            Rc::new(Object::Str(c.to_string())),
+                        )
+                    })
+                    .collect(),
+                false,
+            ),
+            Object::Map(map) => {
+                let mut pairs: Vec<(Rc<Object>, Rc<Object>)> = map
+                    .pairs
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                pairs.sort_by(|a, b| a.0.cmp_key(&b.0));
+                (pairs, true)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            entries: Rc::new(entries),
+            keyed,
+            pos: 0,
+        })
+    }
+
+    // Advance the iterator and return the objects to bind to the loop
+    // variables. 'num_vars' is either 1 (value or key) or 2 (key and value)
+    pub fn next(&mut self, num_vars: usize) -> Option<Vec<Rc<Object>>> {
+        let (key, value) = self.entries.get(self.pos)?;
+        self.pos += 1;
+        if num_vars == 2 {
+            Some(vec![key.clone(), value.clone()])
+        } else if self.keyed {
+            Some(vec![key.clone()])
+        } else {
+            Some(vec![value.clone()])
+        }
+    }
+}
+
+impl fmt::Display for Iter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<iterator>")
+    }
+}
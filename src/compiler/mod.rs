@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use self::symtab::Symbol;
@@ -6,12 +9,16 @@ use crate::code::definitions::{self, *};
 use crate::code::opcode::Opcode;
 use crate::common::builtins::BUILTINS;
 use crate::common::error::CompileError;
+use crate::common::module;
 use crate::common::object::CompiledFunction;
 use crate::common::object::Object;
 use crate::compiler::symtab::SymbolTable;
 use crate::parser::ast::expr::*;
 use crate::parser::ast::stmt::BlockStatement;
+use crate::parser::ast::stmt::ImportStmt;
+use crate::parser::ast::stmt::Pattern;
 use crate::parser::ast::stmt::Statement;
+use crate::parser::ast::stmt::TryStmt;
 use crate::parser::ast::*;
 
 pub mod symtab;
@@ -23,6 +30,10 @@ pub struct Bytecode {
     pub constants: Vec<Rc<Object>>,
 }
 
+// A name bound by a match pattern, with the keys to reach its value and
+// the start of the slice if it is the rest of an array
+type MatchBinding = (Identifier, Vec<Object>, Option<usize>);
+
 #[derive(Default, Clone)]
 struct EmittedInstruction {
     opcode: Opcode,
@@ -35,6 +46,15 @@ impl EmittedInstruction {
     }
 }
 
+// A block of a try statement that is being compiled. A 'return' inside it
+// removes the exception handler set for the block, if any, and evaluates
+// the finally block before returning.
+#[derive(Clone)]
+struct TryBlock {
+    handler: bool,
+    finally: Option<BlockStatement>,
+}
+
 // Before compiling a function body (i.e. enter a new scope),
 // push a new object of type CompilationScope onto the scopes stack
 #[derive(Default, Clone)]
@@ -42,6 +62,16 @@ struct CompilationScope {
     instructions: Instructions,
     last_ins: EmittedInstruction, // instruction before the current
     prev_ins: EmittedInstruction, // instruction before the last
+    tries: Vec<TryBlock>,         // enclosing blocks of try statements, innermost last
+    module: bool,                 // the scope is the body of a module
+}
+
+// A module that has been compiled into a function. The hidden global 'slot'
+// holds the exports of the module once the function has been called.
+#[derive(Clone)]
+struct CompiledModule {
+    slot: Rc<Symbol>,
+    func: usize, // index of the function in the constant pool
 }
 
 pub struct Compiler {
@@ -49,28 +79,39 @@ pub struct Compiler {
     pub symtab: SymbolTable,
     scopes: Vec<CompilationScope>,
     scope_index: usize,
+    files: Vec<PathBuf>,         // files being compiled, innermost last
+    importers: Vec<SymbolTable>, // symbol tables of the files importing a module
+    modules: HashMap<PathBuf, CompiledModule>, // modules compiled so far
 }
 
 impl Compiler {
     pub fn new() -> Compiler {
-        let mut symtab = SymbolTable::default();
+        let main_scope = CompilationScope::default();
+        Compiler {
+            constants: Vec::new(),
+            symtab: Self::builtins_symtab(),
+            scopes: vec![main_scope],
+            scope_index: 0,
+            files: Vec::new(),
+            importers: Vec::new(),
+            modules: HashMap::new(),
+        }
+    }
 
+    // A symbol table that only defines the builtins
+    fn builtins_symtab() -> SymbolTable {
+        let mut symtab = SymbolTable::default();
         for (i, sym) in BUILTINS.iter().enumerate() {
             // Define the built-in function via an index into the 'BUILTINS' array
             symtab.define_builtin(i, &sym.name);
         }
+        symtab
+    }
 
-        let main_scope = CompilationScope {
-            instructions: Instructions::default(),
-            last_ins: EmittedInstruction::default(),
-            prev_ins: EmittedInstruction::default(),
-        };
-        Compiler {
-            constants: Vec::new(),
-            symtab,
-            scopes: vec![main_scope],
-            scope_index: 0,
-        }
+    // Set the file being compiled, which the paths of its imports are
+    // relative to
+    pub fn set_file(&mut self, file: PathBuf) {
+        self.files = vec![file];
     }
 
     pub fn new_with_state(symtab: SymbolTable, constants: Vec<Rc<Object>>) -> Compiler {
@@ -81,12 +122,7 @@ impl Compiler {
     }
 
     pub fn enter_scope(&mut self) {
-        let scope = CompilationScope {
-            instructions: Instructions::default(),
-            last_ins: EmittedInstruction::default(),
-            prev_ins: EmittedInstruction::default(),
-        };
-        self.scopes.push(scope);
+        self.scopes.push(CompilationScope::default());
         self.scope_index += 1;
         self.symtab = SymbolTable::new_enclosed(self.symtab.clone());
     }
@@ -176,6 +212,90 @@ impl Compiler {
         };
     }
 
+    fn store_symbol(&mut self, sym: Rc<Symbol>, line: usize) {
+        // Use a Symbol's scope to emit the right instruction
+        if sym.scope == SymbolScope::Global {
+            self.emit(Opcode::SetGlobal, &[sym.index], line);
+        } else {
+            self.emit(Opcode::SetLocal, &[sym.index], line);
+        }
+    }
+
+    // Define a name bound by the program. A 'const' binding of this or an
+    // enclosing scope, including the earlier lines of the REPL, may not be
+    // redefined or shadowed until the block that declared it has ended.
+    fn define_binding(
+        &mut self,
+        name: &Identifier,
+        mutable: bool,
+    ) -> Result<Rc<Symbol>, CompileError> {
+        if self.symtab.is_const(&name.value) {
+            return Err(CompileError::new(
+                &format!("cannot redefine constant {}", name.value),
+                name.token.line,
+            ));
+        }
+        Ok(if mutable {
+            self.symtab.define(&name.value)
+        } else {
+            self.symtab.define_const(&name.value)
+        })
+    }
+
+    // Load the cell of a variable rather than its value so that the closure
+    // being created shares the variable with the enclosing function
+    fn capture_symbol(&mut self, sym: Rc<Symbol>, line: usize) {
+        match sym.scope {
+            SymbolScope::Local => {
+                self.emit(Opcode::CaptureLocal, &[sym.index], line);
+            }
+            SymbolScope::Free => {
+                self.emit(Opcode::CaptureFree, &[sym.index], line);
+            }
+            _ => self.load_symbol(sym, line),
+        }
+    }
+
+    // Compile an assignment to an existing binding. The assigned value is
+    // left on the stack as the result of the assignment expression.
+    fn compile_assignment(&mut self, ident: Identifier, line: usize) -> Result<(), CompileError> {
+        let symbol = match self.symtab.resolve(&ident.value) {
+            Some(symbol) => symbol,
+            None => {
+                return Err(CompileError::new(
+                    &format!("undefined variable {}", ident.value),
+                    line,
+                ))
+            }
+        };
+        let (set_op, get_op) = match symbol.scope {
+            SymbolScope::Global => (Opcode::SetGlobal, Opcode::GetGlobal),
+            SymbolScope::Local => (Opcode::SetLocal, Opcode::GetLocal),
+            SymbolScope::Free => (Opcode::SetFree, Opcode::GetFree),
+            SymbolScope::Builtin => {
+                return Err(CompileError::new(
+                    &format!("cannot assign to builtin function {}", ident.value),
+                    line,
+                ))
+            }
+            SymbolScope::Function => {
+                return Err(CompileError::new(
+                    &format!("cannot assign to function {} within its body", ident.value),
+                    line,
+                ))
+            }
+        };
+        if !symbol.mutable {
+            return Err(CompileError::new(
+                &format!("cannot assign to constant {}", ident.value),
+                line,
+            ));
+        }
+        self.emit(set_op, &[symbol.index], line);
+        self.emit(get_op, &[symbol.index], line);
+        Ok(())
+    }
+
     // Save the last and the previous instructions
     fn set_last_instruction(&mut self, op: Opcode, pos: usize) {
         let prev_ins = self.scopes[self.scope_index].last_ins.clone();
@@ -248,9 +368,11 @@ impl Compiler {
     }
 
     fn compile_block_statement(&mut self, stmt: BlockStatement) -> Result<(), CompileError> {
+        let consts = self.symtab.consts();
         for stmt in stmt.statements {
             self.compile_statement(stmt)?;
         }
+        self.symtab.close_consts(&consts);
         Ok(())
     }
 
@@ -270,27 +392,267 @@ impl Compiler {
             Statement::Let(stmt) => {
                 // Defining the symbol before the value allows compiling
                 // recursive functions that has reference to its own name.
-                let symbol = self.symtab.define(&stmt.name.value);
+                let symbol = self.define_binding(&stmt.name, !stmt.is_const())?;
                 self.compile_let_stmt(stmt.value)?;
-
-                // Use a Symbol's scope to emit the right instruction
-                if symbol.scope == SymbolScope::Global {
-                    self.emit(Opcode::SetGlobal, &[symbol.index], stmt.token.line);
-                } else {
-                    self.emit(Opcode::SetLocal, &[symbol.index], stmt.token.line);
+                self.store_symbol(symbol, stmt.token.line);
+            }
+            Statement::Destructure(stmt) => {
+                let line = stmt.token.line;
+                let mutable = !stmt.is_const();
+                // The names are defined after the value so that the value
+                // can refer to earlier bindings with the same names
+                self.compile_expression(stmt.value)?;
+                let names = match stmt.pattern {
+                    Pattern::Array(mut names, rest) => {
+                        self.emit(
+                            Opcode::UnpackArray,
+                            &[names.len(), rest.is_some() as usize],
+                            line,
+                        );
+                        names.extend(rest);
+                        names
+                    }
+                    Pattern::Map(names) => {
+                        for name in &names {
+                            let key = self.add_constant(Object::Str(name.value.clone()));
+                            self.emit(Opcode::Constant, &[key], line);
+                        }
+                        self.emit(Opcode::UnpackMap, &[names.len()], line);
+                        names
+                    }
+                };
+                // The unpacked values are pushed in order, so bind them in reverse
+                let symbols = names
+                    .iter()
+                    .map(|name| self.define_binding(name, mutable))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for symbol in symbols.into_iter().rev() {
+                    self.store_symbol(symbol, line);
                 }
             }
             Statement::Return(stmt) => {
                 self.compile_expression(stmt.value)?;
+                self.compile_try_exits(stmt.token.line)?;
                 self.emit(Opcode::ReturnValue, &[0], stmt.token.line);
             }
+            Statement::Defer(stmt) => {
+                if self.scope_index == 0 || self.scopes[self.scope_index].module {
+                    return Err(CompileError::new(
+                        "defer is only allowed inside a function",
+                        stmt.token.line,
+                    ));
+                }
+                // The deferred expression follows 'OpDefer', which registers
+                // it with the frame of the call and jumps over it. The VM
+                // evaluates it when the function returns, see 'OpEndDefer'.
+                let line = stmt.token.line;
+                let defer_pos = self.emit(Opcode::Defer, &[0xFFFF], line);
+                self.compile_expression(stmt.value)?;
+                self.emit(Opcode::Pop, &[0], line);
+                self.emit(Opcode::EndDefer, &[0], line);
+                let after_defer_pos = self.get_curr_instructions().len();
+                self.change_operand(defer_pos, after_defer_pos);
+            }
+            Statement::Throw(stmt) => {
+                self.compile_expression(stmt.value)?;
+                self.emit(Opcode::Throw, &[0], stmt.token.line);
+            }
+            Statement::Try(stmt) => self.compile_try_stmt(stmt)?,
+            Statement::Import(stmt) => self.compile_import_stmt(stmt)?,
             _ => {}
         }
         Ok(())
     }
 
+    // A module is compiled once into a function that evaluates the module
+    // and returns its exports. The first import to run calls the function
+    // and keeps the exports in a hidden global for the other imports:
+    //     GetGlobal M  JumpIfNotNil E  Pop  Closure F  Call 0  SetGlobal M  GetGlobal M  E: ...
+    fn compile_import_stmt(&mut self, stmt: ImportStmt) -> Result<(), CompileError> {
+        let line = stmt.token.line;
+        let path =
+            module::resolve(&stmt.path, &self.files).map_err(|e| CompileError::new(&e, line))?;
+        let compiled = match self.modules.get(&path) {
+            Some(compiled) => compiled.clone(),
+            None => {
+                let program = module::load(&path).map_err(|e| CompileError::new(&e, line))?;
+                let compiled = self.compile_module(path.clone(), program, line)?;
+                self.modules.insert(path, compiled.clone());
+                compiled
+            }
+        };
+        self.load_symbol(compiled.slot.clone(), line);
+        let jump_pos = self.emit(Opcode::JumpIfNotNil, &[0xFFFF], line);
+        self.emit(Opcode::Pop, &[0], line);
+        self.emit(Opcode::Closure, &[compiled.func, 0], line);
+        self.emit(Opcode::Call, &[0], line);
+        self.store_symbol(compiled.slot.clone(), line);
+        self.load_symbol(compiled.slot, line);
+        let after_pos = self.get_curr_instructions().len();
+        self.change_operand(jump_pos, after_pos);
+        let symbol = self.define_binding(&stmt.name, true)?;
+        self.store_symbol(symbol, line);
+        Ok(())
+    }
+
+    // The module has a symbol table of its own whose root only defines the
+    // builtins. So the bindings of the module are the locals of its function
+    // and it cannot see the bindings of the files that import it.
+    fn compile_module(
+        &mut self,
+        path: PathBuf,
+        program: Program,
+        line: usize,
+    ) -> Result<CompiledModule, CompileError> {
+        // The hidden global is defined in the symbol table of the main program
+        let name = format!("module#{}", path.display());
+        let slot = match self.importers.first_mut() {
+            Some(main) => main.define_global(&name),
+            None => self.symtab.define_global(&name),
+        };
+        let module_symtab = SymbolTable::new_enclosed(Self::builtins_symtab());
+        let importer = mem::replace(&mut self.symtab, module_symtab);
+        self.importers.push(importer);
+        self.files.push(path);
+        self.scopes.push(CompilationScope {
+            module: true,
+            ..Default::default()
+        });
+        self.scope_index += 1;
+
+        let exports = module::exports(&program);
+        let result = self.compile_statements(program.statements);
+        if result.is_ok() {
+            // Return the exports as a map of their names to their values
+            for name in &exports {
+                let key = self.add_constant(Object::Str(name.clone()));
+                self.emit(Opcode::Constant, &[key], line);
+                if let Some(symbol) = self.symtab.resolve(name) {
+                    self.load_symbol(symbol, line);
+                }
+            }
+            self.emit(Opcode::Map, &[exports.len() * 2], line);
+            self.emit(Opcode::ReturnValue, &[0], line);
+        }
+        let num_locals = self.symtab.get_num_definitions();
+        let instructions = self.leave_module();
+        result?;
+
+        let func = CompiledFunction::new(instructions, num_locals, 0);
+        let func = self.add_constant(Object::CompiledFunc(Rc::new(func)));
+        Ok(CompiledModule { slot, func })
+    }
+
+    fn leave_module(&mut self) -> Instructions {
+        let instructions = self.get_curr_instructions();
+        self.scopes.truncate(self.scopes.len() - 1);
+        self.scope_index -= 1;
+        self.files.pop();
+        if let Some(importer) = self.importers.pop() {
+            self.symtab = importer;
+        }
+        instructions
+    }
+
+    // The body is protected by an exception handler that continues at the
+    // catch block with the error on the stack:
+    //        SetupTry H  <body> PopTry <finally> Jump E
+    //     H: <bind> SetupTry F  <catch> PopTry <finally> Jump E
+    //     F: <finally> Throw
+    //     E: ...
+    // With a finally block, the errors raised in the catch block, or in the
+    // body when there is no catch block, are caught at 'F' so that the
+    // finally block is evaluated before they are thrown again. The finally
+    // block is compiled once for each way of leaving the statement.
+    // The catch block ends with a jump even when nothing follows it, as a
+    // statement ending with a Pop would become the return value of the
+    // enclosing function.
+    fn compile_try_stmt(&mut self, stmt: TryStmt) -> Result<(), CompileError> {
+        let line = stmt.token.line;
+        let finally = stmt.finally;
+        let setup_pos = self.emit(Opcode::SetupTry, &[0xFFFF], line);
+        self.compile_try_block(stmt.body, true, &finally)?;
+        self.emit(Opcode::PopTry, &[0], line);
+        if let Some(finally) = &finally {
+            self.compile_block_statement(finally.clone())?;
+        }
+        let mut end_jumps = vec![self.emit(Opcode::Jump, &[0xFFFF], line)];
+        let handler_pos = self.get_curr_instructions().len();
+        self.change_operand(setup_pos, handler_pos);
+        if let Some((name, body)) = stmt.catch {
+            let symbol = self.define_binding(&name, true)?;
+            self.store_symbol(symbol, name.token.line);
+            let setup_pos = finally
+                .is_some()
+                .then(|| self.emit(Opcode::SetupTry, &[0xFFFF], line));
+            self.compile_try_block(body, setup_pos.is_some(), &finally)?;
+            if let Some(finally) = &finally {
+                self.emit(Opcode::PopTry, &[0], line);
+                self.compile_block_statement(finally.clone())?;
+            }
+            end_jumps.push(self.emit(Opcode::Jump, &[0xFFFF], line));
+            if let Some(setup_pos) = setup_pos {
+                let handler_pos = self.get_curr_instructions().len();
+                self.change_operand(setup_pos, handler_pos);
+            }
+        }
+        if let Some(finally) = finally {
+            self.compile_block_statement(finally)?;
+            self.emit(Opcode::Throw, &[0], line);
+        }
+        let after_pos = self.get_curr_instructions().len();
+        for jump_pos in end_jumps {
+            self.change_operand(jump_pos, after_pos);
+        }
+        Ok(())
+    }
+
+    fn compile_try_block(
+        &mut self,
+        body: BlockStatement,
+        handler: bool,
+        finally: &Option<BlockStatement>,
+    ) -> Result<(), CompileError> {
+        let finally = finally.clone();
+        self.scopes[self.scope_index]
+            .tries
+            .push(TryBlock { handler, finally });
+        let result = self.compile_block_statement(body);
+        self.scopes[self.scope_index].tries.pop();
+        result
+    }
+
+    // Leave the enclosing try blocks for a 'return', innermost first. The
+    // handlers are removed so that an error in a finally block is not
+    // caught by the try statement that it belongs to.
+    fn compile_try_exits(&mut self, line: usize) -> Result<(), CompileError> {
+        let tries = self.scopes[self.scope_index].tries.clone();
+        for (i, block) in tries.iter().enumerate().rev() {
+            // A 'return' within a finally block only leaves the outer ones
+            self.scopes[self.scope_index].tries.truncate(i);
+            if block.handler {
+                self.emit(Opcode::PopTry, &[0], line);
+            }
+            if let Some(finally) = &block.finally {
+                self.compile_block_statement(finally.clone())?;
+            }
+        }
+        self.scopes[self.scope_index].tries = tries;
+        Ok(())
+    }
+
     fn compile_expression(&mut self, expr: Expression) -> Result<(), CompileError> {
         match expr {
+            Expression::Integer(num) => {
+                let obj = Object::Integer(num.value);
+                let idx = self.add_constant(obj);
+                self.emit(Opcode::Constant, &[idx], num.token.line);
+            }
+            Expression::BigInt(num) => {
+                let obj = Object::BigInt(num.value);
+                let idx = self.add_constant(obj);
+                self.emit(Opcode::Constant, &[idx], num.token.line);
+            }
             Expression::Number(num) => {
                 let obj = Object::Number(num.value);
                 let idx = self.add_constant(obj);
@@ -301,7 +663,22 @@ impl Compiler {
                 let idx = self.add_constant(obj);
                 self.emit(Opcode::Constant, &[idx], s.token.line);
             }
+            Expression::Interpolated(s) => {
+                let len = s.parts.len();
+                for part in s.parts {
+                    self.compile_expression(part)?;
+                }
+                self.emit(Opcode::Concat, &[len], s.token.line);
+            }
             Expression::Array(arr) => {
+                if arr
+                    .elements
+                    .iter()
+                    .any(|e| matches!(e, Expression::Spread(_)))
+                {
+                    self.compile_spread_list(arr.elements, arr.token.line)?;
+                    return Ok(());
+                }
                 let len = arr.elements.len();
                 for e in arr.elements {
                     self.compile_expression(e)?;
@@ -316,6 +693,20 @@ impl Compiler {
                 }
                 self.emit(Opcode::Map, &[len], map.token.line);
             }
+            Expression::Binary(binary) if binary.operator == "&&" || binary.operator == "||" => {
+                self.compile_logical_expr(binary)?;
+            }
+            Expression::Binary(binary) if binary.operator == "??" => {
+                // The right operand is only evaluated when the left one is nil:
+                //     <left>  JumpIfNotNil E  Pop  <right>  E:
+                let line = binary.token.line;
+                self.compile_expression(*binary.left)?;
+                let jump_pos = self.emit(Opcode::JumpIfNotNil, &[0xFFFF], line);
+                self.emit(Opcode::Pop, &[0], line);
+                self.compile_expression(*binary.right)?;
+                let after_pos = self.get_curr_instructions().len();
+                self.change_operand(jump_pos, after_pos);
+            }
             Expression::Binary(binary) => {
                 // In case of '<', re order the operands to reuse the '>' operator
                 match binary.operator.as_ref() {
@@ -339,6 +730,9 @@ impl Compiler {
                     "-" => {
                         self.emit(Opcode::Minus, &[0], u.token.line);
                     }
+                    "~" => {
+                        self.emit(Opcode::BitNot, &[0], u.token.line);
+                    }
                     _ => return Err(CompileError::new("invalid binary operator", u.token.line)),
                 }
             }
@@ -356,8 +750,11 @@ impl Compiler {
                 self.compile_block_statement(expr.then_stmt)?;
                 // Get rid of the extra Pop that comes with the result of compiling 'then_stmt'
                 // This is so that we don't loose the result of the 'if' expression
+                // A block that doesn't end with an expression results in Nil
                 if self.is_last_instruction(Opcode::Pop) {
                     self.remove_last_pop();
+                } else {
+                    self.emit(Opcode::Nil, &[0], expr.token.line);
                 }
 
                 // Emit an 'Jump' with a placeholder. Save it's position so it can be altered later
@@ -380,6 +777,8 @@ impl Compiler {
                         self.compile_block_statement(else_stmt)?;
                         if self.is_last_instruction(Opcode::Pop) {
                             self.remove_last_pop();
+                        } else {
+                            self.emit(Opcode::Nil, &[0], expr.token.line);
                         }
                     }
                 }
@@ -389,6 +788,51 @@ impl Compiler {
                 // else branch – it could be Nil or a real 'else_stmt'
                 self.change_operand(jump_pos, after_else_pos);
             }
+            Expression::While(expr) => {
+                // Save the position of the condition so the end of the body
+                // can jump back to it for the next iteration
+                let loop_start_pos = self.get_curr_instructions().len();
+                self.compile_expression(*expr.condition)?;
+                // Emit an 'JumpIfFalse' with a placeholder to exit the loop
+                let jump_if_false_pos = self.emit(Opcode::JumpIfFalse, &[0xFFFF], expr.token.line);
+                self.compile_block_statement(expr.body)?;
+                // Jump backwards to re-evaluate the condition
+                self.emit(Opcode::Jump, &[loop_start_pos], expr.token.line);
+                // offset of the next-to-be-emitted instruction
+                let after_body_pos = self.get_curr_instructions().len();
+                self.change_operand(jump_if_false_pos, after_body_pos);
+                // A while loop is an expression that always evaluates to Nil
+                self.emit(Opcode::Nil, &[0], expr.token.line);
+            }
+            Expression::For(expr) => {
+                let line = expr.token.line;
+                let num_vars = expr.idents.len();
+                // Replace the collection on the stack with an iterator. The
+                // iterator stays on the stack while the loop is running.
+                self.compile_expression(*expr.iterable)?;
+                self.emit(Opcode::Iter, &[0], line);
+                let loop_start_pos = self.get_curr_instructions().len();
+                // Emit an 'IterNext' with a placeholder jump address to exit the loop
+                let iter_next_pos = self.emit(Opcode::IterNext, &[0xFFFF, num_vars], line);
+                // 'IterNext' pushes the loop variables in order, so bind them in reverse
+                let symbols = expr
+                    .idents
+                    .iter()
+                    .map(|ident| self.define_binding(ident, true))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for symbol in symbols.into_iter().rev() {
+                    self.store_symbol(symbol, line);
+                }
+                self.compile_block_statement(expr.body)?;
+                self.emit(Opcode::Jump, &[loop_start_pos], line);
+                // Patch the jump address of 'IterNext' to point past the loop
+                let after_body_pos = self.get_curr_instructions().len();
+                let new_instruction =
+                    definitions::make(Opcode::IterNext, &[after_body_pos, num_vars], line);
+                self.replace_instruction(iter_next_pos, &new_instruction.code);
+                // A for loop is an expression that always evaluates to Nil
+                self.emit(Opcode::Nil, &[0], line);
+            }
             Expression::Ident(expr) => {
                 if let Some(symbol) = self.symtab.resolve(&expr.token.literal) {
                     self.load_symbol(symbol, expr.token.line);
@@ -399,13 +843,23 @@ impl Compiler {
                     ));
                 }
             }
-            Expression::Index(expr) => {
-                // Compile the expression being indexed
-                self.compile_expression(*expr.left)?;
-                // Compile the index expression
-                self.compile_expression(*expr.index)?;
-                // Emit the index operator
-                self.emit(Opcode::Index, &[0], expr.token.line);
+            chain @ (Expression::Index(_)
+            | Expression::Slice(_)
+            | Expression::Property(_)
+            | Expression::MethodCall(_)
+            | Expression::Call(_)) => {
+                let mut jumps = Vec::new();
+                self.compile_chain(chain, &mut jumps)?;
+                let after_pos = self.get_curr_instructions().len();
+                for jump_pos in jumps {
+                    self.change_operand(jump_pos, after_pos);
+                }
+            }
+            Expression::Match(expr) => self.compile_match_expr(expr)?,
+            Expression::Nil => {
+                // The 'nil' literal has no token to take the line from. Pushing
+                // nil never fails, so the line is never reported.
+                self.emit(Opcode::Nil, &[0], 0);
             }
             Expression::Function(func) => {
                 // enter scope of a function
@@ -420,9 +874,28 @@ impl Compiler {
                 // onto the stack. Since these definitions are done in the scope of
                 // the newly compiled function, they become part of the local
                 // variables (num_locals) of the function.
-                let num_params = func.params.len();
-                for p in func.params {
-                    self.symtab.define(&p.value);
+                // The parameter collecting the remaining arguments is the
+                // last local defined for the parameters.
+                let min_args = func.params.len() - func.defaults.len();
+                let max_args = func.rest.is_none().then_some(func.params.len());
+                let symbols = func
+                    .params
+                    .iter()
+                    .chain(&func.rest)
+                    .map(|p| self.define_binding(p, true))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let num_params = symbols.len();
+                // Compile the default values ahead of the body. A call that
+                // omits some arguments starts at the default value of the
+                // first missing one, see 'CompiledFunction::entries'.
+                let mut entries = Vec::new();
+                if !func.defaults.is_empty() {
+                    for (default, symbol) in func.defaults.into_iter().zip(&symbols[min_args..]) {
+                        entries.push(self.get_curr_instructions().len());
+                        self.compile_expression(default)?;
+                        self.store_symbol(symbol.clone(), func.token.line);
+                    }
+                    entries.push(self.get_curr_instructions().len());
                 }
                 self.compile_block_statement(func.body)?;
                 // Leave function scope. If the last expression statement in a
@@ -446,22 +919,294 @@ impl Compiler {
                 let free_symbols = self.symtab.free_symbols.clone();
                 let instructions = self.leave_scope();
 
-                // load free symbols on stack
+                // load cells of free symbols on stack
                 for f in &free_symbols {
-                    self.load_symbol(f.clone(), func.token.line);
+                    self.capture_symbol(f.clone(), func.token.line);
                 }
-                let compiled_fn = Object::CompiledFunc(Rc::new(CompiledFunction::new(
-                    instructions,
-                    num_locals,
-                    num_params,
-                )));
+                let mut compiled_fn = CompiledFunction::new(instructions, num_locals, num_params);
+                compiled_fn.min_args = min_args;
+                compiled_fn.max_args = max_args;
+                compiled_fn.entries = entries;
+                compiled_fn.doc = func.doc;
+                let compiled_fn = Object::CompiledFunc(Rc::new(compiled_fn));
                 let idx = self.add_constant(compiled_fn);
                 // emit closure instruction with the index to the compiled fn
                 // and with number of free variables
                 self.emit(Opcode::Closure, &[idx, free_symbols.len()], func.token.line);
             }
+            Expression::Assign(assign) => {
+                let line = assign.token.line;
+                match *assign.target {
+                    Expression::Ident(ident) => {
+                        self.compile_expression(*assign.value)?;
+                        self.compile_assignment(ident, line)?;
+                    }
+                    Expression::Index(target) => {
+                        // 'SetIndex' updates the array or map in place and
+                        // leaves the assigned value on the stack
+                        self.compile_expression(*target.left)?;
+                        self.compile_expression(*target.index)?;
+                        self.compile_expression(*assign.value)?;
+                        self.emit(Opcode::SetIndex, &[0], line);
+                    }
+                    Expression::Property(target) => {
+                        // 'a.b = v' is the same as 'a["b"] = v'
+                        self.compile_expression(*target.left)?;
+                        let name = self.add_constant(Object::Str(target.name.value));
+                        self.emit(Opcode::Constant, &[name], line);
+                        self.compile_expression(*assign.value)?;
+                        self.emit(Opcode::SetIndex, &[0], line);
+                    }
+                    _ => return Err(CompileError::new("invalid assignment target", line)),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Compile a list of expressions, some of which are spread, into a single
+    // array on the stack. The elements before the first spread expression
+    // make up the initial array. Each spread array, and each group of the
+    // elements in between, is then appended to it with 'OpSpread':
+    //     [a, ...b, c, d]  =>  <a> Array 1  <b> Spread  <c> <d> Array 2 Spread
+    fn compile_spread_list(
+        &mut self,
+        elements: Vec<Expression>,
+        line: usize,
+    ) -> Result<(), CompileError> {
+        let mut count = 0;
+        let mut started = false;
+        for element in elements {
+            if let Expression::Spread(spread) = element {
+                if !started || count > 0 {
+                    self.emit(Opcode::Array, &[count], line);
+                    if started {
+                        self.emit(Opcode::Spread, &[0], line);
+                    }
+                }
+                started = true;
+                count = 0;
+                self.compile_expression(*spread.value)?;
+                self.emit(Opcode::Spread, &[0], spread.token.line);
+            } else {
+                self.compile_expression(element)?;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.emit(Opcode::Array, &[count], line);
+            self.emit(Opcode::Spread, &[0], line);
+        }
+        Ok(())
+    }
+
+    // Each arm tests its pattern against the value and jumps to the next
+    // arm as soon as a test fails. The names in the pattern are bound once
+    // all the tests pass, and then the guard is tested:
+    //     <tests> JumpIfFalse N ... <bindings> <guard> JumpIfFalse N <body> Jump E  N: ...
+    // The value is kept in a hidden variable so every test can load it.
+    // 'match' is a keyword, so the name cannot clash with a user variable.
+    fn compile_match_expr(&mut self, expr: MatchExpr) -> Result<(), CompileError> {
+        let line = expr.token.line;
+        self.compile_expression(*expr.subject)?;
+        let subject = self.symtab.define("match");
+        self.store_symbol(subject.clone(), line);
+        let mut end_jumps = Vec::new();
+        for arm in expr.arms {
+            let mut next_jumps = Vec::new();
+            let mut bindings = Vec::new();
+            self.compile_pattern_tests(
+                &arm.pattern,
+                &subject,
+                &mut Vec::new(),
+                &mut next_jumps,
+                &mut bindings,
+                line,
+            )?;
+            for (name, path, rest) in bindings {
+                self.load_match_path(&subject, &path, line);
+                if let Some(start) = rest {
+                    // The rest of an array is a slice starting after the elements
+                    let start = self.add_constant(Object::Integer(start as i64));
+                    self.emit(Opcode::Constant, &[start], line);
+                    self.emit(Opcode::Nil, &[0], line);
+                    self.emit(Opcode::Nil, &[0], line);
+                    self.emit(Opcode::Slice, &[0], line);
+                }
+                let symbol = self.define_binding(&name, true)?;
+                self.store_symbol(symbol, line);
+            }
+            if let Some(guard) = arm.guard {
+                self.compile_expression(guard)?;
+                next_jumps.push(self.emit(Opcode::JumpIfFalse, &[0xFFFF], line));
+            }
+            self.compile_block_statement(arm.body)?;
+            // Keep the value of the body as the result of the match
+            if self.is_last_instruction(Opcode::Pop) {
+                self.remove_last_pop();
+            } else {
+                self.emit(Opcode::Nil, &[0], line);
+            }
+            end_jumps.push(self.emit(Opcode::Jump, &[0xFFFF], line));
+            let next_pos = self.get_curr_instructions().len();
+            for jump_pos in next_jumps {
+                self.change_operand(jump_pos, next_pos);
+            }
+        }
+        // None of the arms matched
+        self.load_symbol(subject, line);
+        self.emit(Opcode::NoMatch, &[0], line);
+        let after_pos = self.get_curr_instructions().len();
+        for jump_pos in end_jumps {
+            self.change_operand(jump_pos, after_pos);
+        }
+        Ok(())
+    }
+
+    // Emit the tests of a pattern for the part of the value at 'path', which
+    // is the list of keys to index the value with. The names to bind are
+    // collected with their paths, and the number of elements before the rest
+    // for the rest of an array.
+    fn compile_pattern_tests(
+        &mut self,
+        pattern: &MatchPattern,
+        subject: &Rc<Symbol>,
+        path: &mut Vec<Object>,
+        jumps: &mut Vec<usize>,
+        bindings: &mut Vec<MatchBinding>,
+        line: usize,
+    ) -> Result<(), CompileError> {
+        match pattern {
+            MatchPattern::Wildcard => {}
+            MatchPattern::Binding(name) => bindings.push((name.clone(), path.clone(), None)),
+            MatchPattern::Literal(value) => {
+                self.load_match_path(subject, path, line);
+                self.compile_expression(value.clone())?;
+                self.emit(Opcode::Equal, &[0], line);
+                jumps.push(self.emit(Opcode::JumpIfFalse, &[0xFFFF], line));
+            }
+            MatchPattern::Array(elements, rest) => {
+                self.load_match_path(subject, path, line);
+                self.emit(
+                    Opcode::MatchArray,
+                    &[elements.len(), rest.is_some() as usize],
+                    line,
+                );
+                jumps.push(self.emit(Opcode::JumpIfFalse, &[0xFFFF], line));
+                for (i, element) in elements.iter().enumerate() {
+                    path.push(Object::Integer(i as i64));
+                    self.compile_pattern_tests(element, subject, path, jumps, bindings, line)?;
+                    path.pop();
+                }
+                if let Some(MatchPattern::Binding(name)) = rest.as_deref() {
+                    bindings.push((name.clone(), path.clone(), Some(elements.len())));
+                }
+            }
+            MatchPattern::Map(entries) => {
+                self.load_match_path(subject, path, line);
+                for (key, _) in entries {
+                    let key = self.add_constant(Object::Str(key.clone()));
+                    self.emit(Opcode::Constant, &[key], line);
+                }
+                self.emit(Opcode::MatchMap, &[entries.len()], line);
+                jumps.push(self.emit(Opcode::JumpIfFalse, &[0xFFFF], line));
+                for (key, pattern) in entries {
+                    path.push(Object::Str(key.clone()));
+                    self.compile_pattern_tests(pattern, subject, path, jumps, bindings, line)?;
+                    path.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Load the part of the value being matched at 'path'
+    fn load_match_path(&mut self, subject: &Rc<Symbol>, path: &[Object], line: usize) {
+        self.load_symbol(subject.clone(), line);
+        for key in path {
+            let key = self.add_constant(key.clone());
+            self.emit(Opcode::Constant, &[key], line);
+            self.emit(Opcode::Index, &[0], line);
+        }
+    }
+
+    // Compile a member access, index, slice or call. The object it applies
+    // to is compiled as a link of the same chain, so an optional access that
+    // finds nil skips the rest of the chain and leaves the nil as its result:
+    // 'a?.b.c' is nil if 'a' is nil. The jumps of the optional accesses are
+    // collected in 'jumps' to be patched at the end of the chain.
+    fn compile_chain(
+        &mut self,
+        expr: Expression,
+        jumps: &mut Vec<usize>,
+    ) -> Result<(), CompileError> {
+        match expr {
+            Expression::Index(expr) => {
+                // Compile the expression being indexed
+                self.compile_chain_link(*expr.left, expr.optional, jumps, expr.token.line)?;
+                // Compile the index expression
+                self.compile_expression(*expr.index)?;
+                // Emit the index operator
+                self.emit(Opcode::Index, &[0], expr.token.line);
+            }
+            Expression::Slice(expr) => {
+                let line = expr.token.line;
+                self.compile_chain_link(*expr.left, expr.optional, jumps, line)?;
+                // Omitted bounds and step are passed as nil
+                for part in [expr.start, expr.end, expr.step] {
+                    match part {
+                        Some(part) => self.compile_expression(*part)?,
+                        None => {
+                            self.emit(Opcode::Nil, &[0], line);
+                        }
+                    }
+                }
+                self.emit(Opcode::Slice, &[0], line);
+            }
+            Expression::Property(expr) => {
+                self.compile_chain_link(*expr.left, expr.optional, jumps, expr.token.line)?;
+                let name = self.add_constant(Object::Str(expr.name.value));
+                self.emit(Opcode::GetProperty, &[name], expr.token.line);
+            }
+            Expression::MethodCall(call) => {
+                let line = call.token.line;
+                self.compile_chain_link(*call.receiver, call.optional, jumps, line)?;
+                // A function of the same name defined by the program is
+                // loaded after the arguments so that the VM can call it
+                // when the receiver doesn't provide the method
+                let binding = self
+                    .symtab
+                    .resolve(&call.name.value)
+                    .filter(|sym| sym.scope != SymbolScope::Builtin);
+                let has_binding = usize::from(binding.is_some());
+                let name = self.add_constant(Object::Str(call.name.value));
+                if call.args.iter().any(|e| matches!(e, Expression::Spread(_))) {
+                    self.compile_spread_list(call.args, line)?;
+                    if let Some(sym) = binding {
+                        self.load_symbol(sym, line);
+                    }
+                    self.emit(Opcode::InvokeSpread, &[name, has_binding], line);
+                } else {
+                    let num_args = call.args.len();
+                    for arg in call.args {
+                        self.compile_expression(arg)?;
+                    }
+                    if let Some(sym) = binding {
+                        self.load_symbol(sym, line);
+                    }
+                    self.emit(Opcode::Invoke, &[name, num_args, has_binding], line);
+                }
+            }
             Expression::Call(call) => {
-                self.compile_expression(*call.func)?;
+                self.compile_chain_link(*call.func, false, jumps, call.token.line)?;
+                // The arguments are collected into an array if any of them is
+                // spread, since their number is only known at runtime
+                if call.args.iter().any(|e| matches!(e, Expression::Spread(_))) {
+                    self.compile_spread_list(call.args, call.token.line)?;
+                    self.emit(Opcode::CallSpread, &[0], call.token.line);
+                    return Ok(());
+                }
                 let num_args = call.args.len();
                 for arg in call.args {
                     self.compile_expression(arg)?;
@@ -469,11 +1214,54 @@ impl Compiler {
                 // First operand to OpCall is the number of arguments
                 self.emit(Opcode::Call, &[num_args], call.token.line);
             }
-            _ => {}
+            expr => self.compile_expression(expr)?,
+        }
+        Ok(())
+    }
+
+    // An optional access such as 'a?.b' jumps to the end of the chain if
+    // the object on the stack is nil
+    fn compile_chain_link(
+        &mut self,
+        left: Expression,
+        optional: bool,
+        jumps: &mut Vec<usize>,
+        line: usize,
+    ) -> Result<(), CompileError> {
+        self.compile_chain(left, jumps)?;
+        if optional {
+            jumps.push(self.emit(Opcode::JumpIfNil, &[0xFFFF], line));
         }
         Ok(())
     }
 
+    // Compile '&&' and '||' so that the right operand is only evaluated when
+    // the left one does not decide the result. Both evaluate to a boolean.
+    // For '&&', the operands are tested with 'JumpIfFalse':
+    //     <left>  JumpIfFalse F  <right>  JumpIfFalse F  True  Jump E  F: False  E:
+    // '||' is the same with 'JumpIfTrue' and the booleans swapped.
+    fn compile_logical_expr(&mut self, binary: BinaryExpr) -> Result<(), CompileError> {
+        let line = binary.token.line;
+        let (jump_op, decided, undecided) = if binary.operator == "&&" {
+            (Opcode::JumpIfFalse, Opcode::False, Opcode::True)
+        } else {
+            (Opcode::JumpIfTrue, Opcode::True, Opcode::False)
+        };
+        self.compile_expression(*binary.left)?;
+        let left_jump_pos = self.emit(jump_op, &[0xFFFF], line);
+        self.compile_expression(*binary.right)?;
+        let right_jump_pos = self.emit(jump_op, &[0xFFFF], line);
+        self.emit(undecided, &[0], line);
+        let jump_pos = self.emit(Opcode::Jump, &[0xFFFF], line);
+        let decided_pos = self.get_curr_instructions().len();
+        self.change_operand(left_jump_pos, decided_pos);
+        self.change_operand(right_jump_pos, decided_pos);
+        self.emit(decided, &[0], line);
+        let after_pos = self.get_curr_instructions().len();
+        self.change_operand(jump_pos, after_pos);
+        Ok(())
+    }
+
     fn compile_infix_expr(&mut self, operator: &str, line: usize) -> Result<(), CompileError> {
         match operator {
             "+" => {
@@ -488,6 +1276,30 @@ impl Compiler {
             "/" => {
                 self.emit(Opcode::Div, &[0], line);
             }
+            "%" => {
+                self.emit(Opcode::Mod, &[0], line);
+            }
+            "~/" => {
+                self.emit(Opcode::FloorDiv, &[0], line);
+            }
+            "**" => {
+                self.emit(Opcode::Pow, &[0], line);
+            }
+            "&" => {
+                self.emit(Opcode::BitAnd, &[0], line);
+            }
+            "|" => {
+                self.emit(Opcode::BitOr, &[0], line);
+            }
+            "^" => {
+                self.emit(Opcode::BitXor, &[0], line);
+            }
+            "<<" => {
+                self.emit(Opcode::ShiftLeft, &[0], line);
+            }
+            ">>" => {
+                self.emit(Opcode::ShiftRight, &[0], line);
+            }
             "==" => {
                 self.emit(Opcode::Equal, &[0], line);
             }
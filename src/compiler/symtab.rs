@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::rc::Rc;
 
@@ -7,6 +8,8 @@ pub struct Symbol {
     pub name: String,
     pub scope: SymbolScope,
     pub index: usize,
+    // false for the 'const' bindings
+    pub mutable: bool,
 }
 
 impl Symbol {
@@ -15,6 +18,7 @@ impl Symbol {
             name: name.to_string(),
             scope,
             index,
+            mutable: true,
         }
     }
 }
@@ -47,6 +51,9 @@ pub struct SymbolTable {
     pub outer: Option<Box<SymbolTable>>,
     // original symbols of the enclosing scope
     pub free_symbols: Vec<Rc<Symbol>>,
+    // 'const' bindings declared by blocks that have ended. These may be
+    // defined again but still not assigned to.
+    closed: HashSet<String>,
 }
 
 impl SymbolTable {
@@ -56,6 +63,7 @@ impl SymbolTable {
             num_definitions: 0,
             outer: Some(Box::new(outer)),
             free_symbols: Vec::new(),
+            closed: HashSet::new(),
         }
     }
 
@@ -67,7 +75,15 @@ impl SymbolTable {
     // i.e. its outer field is not set, then its scope is global.
     // If it is enclosed, the scope is local.
     pub fn define(&mut self, name: &str) -> Rc<Symbol> {
-        let symbol = Rc::new(Symbol::new(
+        self.define_symbol(name, true)
+    }
+
+    pub fn define_const(&mut self, name: &str) -> Rc<Symbol> {
+        self.define_symbol(name, false)
+    }
+
+    fn define_symbol(&mut self, name: &str, mutable: bool) -> Rc<Symbol> {
+        let mut symbol = Symbol::new(
             name,
             if self.outer.is_none() {
                 SymbolScope::Global
@@ -75,14 +91,25 @@ impl SymbolTable {
                 SymbolScope::Local
             },
             self.num_definitions,
-        ));
+        );
+        symbol.mutable = mutable;
+        let symbol = Rc::new(symbol);
 
         self.store.insert(name.to_string(), Rc::clone(&symbol));
+        self.closed.remove(name);
         self.num_definitions += 1;
 
         symbol
     }
 
+    // Define a global in the outermost symbol table
+    pub fn define_global(&mut self, name: &str) -> Rc<Symbol> {
+        match &mut self.outer {
+            Some(outer) => outer.define_global(name),
+            None => self.define(name),
+        }
+    }
+
     pub fn define_function_name(&mut self, name: &str) -> Rc<Symbol> {
         let symbol = Rc::new(Symbol::new(name, SymbolScope::Function, 0));
         self.store.insert(name.to_string(), Rc::clone(&symbol));
@@ -105,6 +132,33 @@ impl SymbolTable {
         None
     }
 
+    // Whether the name is a 'const' binding of this or an enclosing scope.
+    // Unlike 'resolve', this does not define free symbols.
+    pub fn is_const(&self, name: &str) -> bool {
+        match (self.store.get(name), &self.outer) {
+            (Some(symbol), _) => !symbol.mutable && !self.closed.contains(name),
+            (None, Some(outer)) => outer.is_const(name),
+            (None, None) => false,
+        }
+    }
+
+    // Names of the 'const' bindings of this scope that may not be defined again
+    pub fn consts(&self) -> HashSet<String> {
+        self.store
+            .iter()
+            .filter(|(name, symbol)| !symbol.mutable && !self.closed.contains(*name))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    // Let the constants defined since 'consts' was taken be defined again,
+    // e.g. by the other branch of an 'if', once the block that declared
+    // them has ended
+    pub fn close_consts(&mut self, consts: &HashSet<String>) {
+        let declared = &self.consts() - consts;
+        self.closed.extend(declared);
+    }
+
     pub fn define_builtin(&mut self, index: usize, name: &str) -> Rc<Symbol> {
         let symbol = Rc::new(Symbol::new(name, SymbolScope::Builtin, index));
         self.store.insert(name.to_string(), Rc::clone(&symbol));
@@ -115,7 +169,9 @@ impl SymbolTable {
         self.free_symbols.push(original.clone());
         let len = self.free_symbols.len();
 
-        let symbol = Rc::new(Symbol::new(&original.name, SymbolScope::Free, len - 1));
+        let mut symbol = Symbol::new(&original.name, SymbolScope::Free, len - 1);
+        symbol.mutable = original.mutable;
+        let symbol = Rc::new(symbol);
 
         self.store.insert(symbol.name.clone(), symbol.clone());
 
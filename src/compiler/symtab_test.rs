@@ -43,7 +43,7 @@ fn test_resolve_global() {
     let _ = global.define("a");
     let _ = global.define("b");
 
-    let expected = vec![
+    let expected = [
         Symbol::new("a", SymbolScope::Global, 0),
         Symbol::new("b", SymbolScope::Global, 1),
     ];
@@ -331,3 +331,26 @@ fn test_shadowing_function_name() {
         Some(s) => assert_eq!(expected, *s, "mismatch in function names"),
     }
 }
+
+#[test]
+fn test_define_const() {
+    let mut global = SymbolTable::default();
+    let a = global.define_const("a");
+    let b = global.define("b");
+    assert!(!a.mutable);
+    assert!(b.mutable);
+
+    let mut local = SymbolTable::new_enclosed(global);
+    let c = local.define_const("c");
+    assert_eq!(c.scope, SymbolScope::Local);
+    assert!(local.is_const("a"));
+    assert!(!local.is_const("b"));
+    assert!(!local.is_const("d"));
+
+    // free symbols keep the mutability of the original symbols
+    let mut nested = SymbolTable::new_enclosed(local);
+    let c = nested.resolve("c").expect("c not resolvable");
+    assert_eq!(c.scope, SymbolScope::Free);
+    assert!(!c.mutable);
+    assert!(nested.is_const("c"));
+}
@@ -5,6 +5,7 @@ use std::rc::Rc;
 use super::*;
 use crate::code::definitions;
 use crate::code::opcode::*;
+use crate::common::bigint::BigInt;
 use crate::common::environment::*;
 use crate::common::error::*;
 use crate::common::object::*;
@@ -31,7 +32,7 @@ fn parse_program(input: &str) -> Program {
 }
 
 #[cfg(test)]
-pub fn test_constants(expected: &Vec<Object>, actual: &Vec<Rc<Object>>) {
+pub fn test_constants(expected: &[Object], actual: &[Rc<Object>]) {
     assert_eq!(
         actual.len(),
         expected.len(),
@@ -41,10 +42,12 @@ pub fn test_constants(expected: &Vec<Object>, actual: &Vec<Rc<Object>>) {
     );
     for (exp, got) in expected.iter().zip(actual) {
         match exp {
-            Object::Bool(e) => test_boolean_object(got.clone(), e.clone()),
-            Object::Number(e) => test_numeric_object(got.clone(), e.clone()),
+            Object::Bool(e) => test_boolean_object(got.clone(), *e),
+            Object::Integer(e) => test_integer_object(got.clone(), *e),
+            Object::BigInt(e) => assert_eq!(got.as_ref(), &Object::BigInt(e.clone())),
+            Object::Number(e) => test_numeric_object(got.clone(), *e),
             Object::Str(s) => test_string_object(got, &s.clone()),
-            Object::CompiledFunc(func) => test_function_object(&got.clone(), &func),
+            Object::CompiledFunc(func) => test_function_object(&got.clone(), func),
             _ => {}
         }
     }
@@ -63,6 +66,19 @@ fn test_boolean_object(actual: Rc<Object>, exp: bool) {
     }
 }
 
+#[cfg(test)]
+fn test_integer_object(actual: Rc<Object>, exp: i64) {
+    if let Object::Integer(act) = *actual.clone() {
+        assert_eq!(
+            act, exp,
+            "object has wrong value. got={}, want={}",
+            act, exp
+        );
+    } else {
+        panic!("object is not an integer. got={:?}", actual);
+    }
+}
+
 #[cfg(test)]
 fn test_numeric_object(actual: Rc<Object>, exp: f64) {
     if let Object::Number(act) = *actual.clone() {
@@ -92,10 +108,7 @@ fn test_string_object(actual: &Object, expected: &str) {
 #[cfg(test)]
 fn test_function_object(actual_obj: &Object, expected: &CompiledFunction) {
     if let Object::CompiledFunc(actual) = actual_obj {
-        test_instructions(
-            &vec![(&*expected.instructions).clone()],
-            &actual.instructions,
-        );
+        test_instructions(&[(*expected.instructions).clone()], &actual.instructions);
     } else {
         panic!("object is not a compiled function. got={:?}", actual_obj);
     }
@@ -143,7 +156,7 @@ fn test_instructions(expected: &[Instructions], actual: &Instructions) {
 #[cfg(test)]
 fn run_compiler_tests(tests: &[CompilerTestCase]) {
     for (n, t) in tests.iter().enumerate() {
-        let program = parse_program(&t.input);
+        let program = parse_program(t.input);
         let mut compiler = Compiler::new();
         let result = compiler.compile(program);
         if let Err(err) = result {
@@ -161,7 +174,7 @@ fn test_integer_arithmetic() {
     let tests = vec![
         CompilerTestCase {
             input: "1 + 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -171,7 +184,7 @@ fn test_integer_arithmetic() {
         },
         CompilerTestCase {
             input: "1 - 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -181,7 +194,7 @@ fn test_integer_arithmetic() {
         },
         CompilerTestCase {
             input: "1 * 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -191,7 +204,7 @@ fn test_integer_arithmetic() {
         },
         CompilerTestCase {
             input: "2 / 1",
-            expected_constants: vec![Object::Number(2.), Object::Number(1.)],
+            expected_constants: vec![Object::Integer(2), Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -199,9 +212,81 @@ fn test_integer_arithmetic() {
                 definitions::make(Opcode::Pop, &[], 1),
             ],
         },
+        CompilerTestCase {
+            input: "2n * 3",
+            expected_constants: vec![Object::BigInt(BigInt::from(2)), Object::Integer(3)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Mul, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "2.5 * 2",
+            expected_constants: vec![Object::Number(2.5), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Mul, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "5 % 2",
+            expected_constants: vec![Object::Integer(5), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Mod, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "5 ~/ 2",
+            expected_constants: vec![Object::Integer(5), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::FloorDiv, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "~1 & 2 | 3 << 4",
+            expected_constants: vec![
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(4),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::BitNot, &[], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::BitAnd, &[], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::Constant, &[3], 1),
+                definitions::make(Opcode::ShiftLeft, &[], 1),
+                definitions::make(Opcode::BitOr, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "2 ** 3 ** 2",
+            expected_constants: vec![Object::Integer(2), Object::Integer(3), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::Pow, &[], 1),
+                definitions::make(Opcode::Pow, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
         CompilerTestCase {
             input: "1; 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Pop, &[], 1),
@@ -211,7 +296,7 @@ fn test_integer_arithmetic() {
         },
         CompilerTestCase {
             input: "-1",
-            expected_constants: vec![Object::Number(1.)],
+            expected_constants: vec![Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Minus, &[], 1),
@@ -244,7 +329,7 @@ fn test_boolean_expressions() {
         },
         CompilerTestCase {
             input: "1 > 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -255,7 +340,7 @@ fn test_boolean_expressions() {
         CompilerTestCase {
             input: "1 < 2",
             // Constants are in reverse order: '1 < 2' is '2 > 1'
-            expected_constants: vec![Object::Number(2.), Object::Number(1.)],
+            expected_constants: vec![Object::Integer(2), Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -265,7 +350,7 @@ fn test_boolean_expressions() {
         },
         CompilerTestCase {
             input: "1 == 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -275,7 +360,7 @@ fn test_boolean_expressions() {
         },
         CompilerTestCase {
             input: "1 != 2",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -322,7 +407,7 @@ fn test_conditional() {
     let tests = vec![
         CompilerTestCase {
             input: "if (true) { 10 }; 3333;",
-            expected_constants: vec![Object::Number(10.), Object::Number(3333.)],
+            expected_constants: vec![Object::Integer(10), Object::Integer(3333)],
             expected_instructions: vec![
                 // 0000 : The condition
                 definitions::make(Opcode::True, &[], 1),
@@ -345,9 +430,9 @@ fn test_conditional() {
         CompilerTestCase {
             input: "if (true) { 10 } else { 20 } ; 3333;",
             expected_constants: vec![
-                Object::Number(10.),
-                Object::Number(20.),
-                Object::Number(3333.),
+                Object::Integer(10),
+                Object::Integer(20),
+                Object::Integer(3333),
             ],
             expected_instructions: vec![
                 // 0000 : The condition
@@ -373,12 +458,204 @@ fn test_conditional() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_logical_operators() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "true && false",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::True, &[], 1),
+                // 0001
+                definitions::make(Opcode::JumpIfFalse, &[12], 1),
+                // 0004
+                definitions::make(Opcode::False, &[], 1),
+                // 0005
+                definitions::make(Opcode::JumpIfFalse, &[12], 1),
+                // 0008
+                definitions::make(Opcode::True, &[], 1),
+                // 0009
+                definitions::make(Opcode::Jump, &[13], 1),
+                // 0012
+                definitions::make(Opcode::False, &[], 1),
+                // 0013
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "false || true",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::False, &[], 1),
+                // 0001
+                definitions::make(Opcode::JumpIfTrue, &[12], 1),
+                // 0004
+                definitions::make(Opcode::True, &[], 1),
+                // 0005
+                definitions::make(Opcode::JumpIfTrue, &[12], 1),
+                // 0008
+                definitions::make(Opcode::False, &[], 1),
+                // 0009
+                definitions::make(Opcode::Jump, &[13], 1),
+                // 0012
+                definitions::make(Opcode::True, &[], 1),
+                // 0013
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_nil_operators() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "nil ?? 1",
+            expected_constants: vec![Object::Integer(1)],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::Nil, &[], 1),
+                // 0001
+                definitions::make(Opcode::JumpIfNotNil, &[8], 1),
+                // 0004
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0005
+                definitions::make(Opcode::Constant, &[0], 1),
+                // 0008
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "nil?.[1]",
+            expected_constants: vec![Object::Integer(1)],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::Nil, &[], 1),
+                // 0001
+                definitions::make(Opcode::JumpIfNil, &[8], 1),
+                // 0004
+                definitions::make(Opcode::Constant, &[0], 1),
+                // 0007
+                definitions::make(Opcode::Index, &[], 1),
+                // 0008
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "nil?.a",
+            expected_constants: vec![Object::Str("a".into())],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::Nil, &[], 1),
+                // 0001
+                definitions::make(Opcode::JumpIfNil, &[7], 1),
+                // 0004
+                definitions::make(Opcode::GetProperty, &[0], 1),
+                // 0007
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_while_loops() {
+    let tests = vec![CompilerTestCase {
+        input: "while (true) { 10 }; 3333;",
+        expected_constants: vec![Object::Integer(10), Object::Integer(3333)],
+        expected_instructions: vec![
+            // 0000 : The condition
+            definitions::make(Opcode::True, &[], 1),
+            // 0001 : Jump out of the loop when the condition is false
+            definitions::make(Opcode::JumpIfFalse, &[11], 1),
+            // 0004 : The body
+            definitions::make(Opcode::Constant, &[0], 1),
+            // 0007
+            definitions::make(Opcode::Pop, &[], 1),
+            // 0008 : Jump back to the condition
+            definitions::make(Opcode::Jump, &[0], 1),
+            // 0011 : The value of the while expression
+            definitions::make(Opcode::Nil, &[], 1),
+            // 0012 : [ Not part of the while expr - Pop its result ]
+            definitions::make(Opcode::Pop, &[], 1),
+            // 0013 : The instruction following the while expr
+            definitions::make(Opcode::Constant, &[1], 1),
+            // 0016
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_for_loops() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "for (x in []) { x }",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000 : The collection being iterated
+                definitions::make(Opcode::Array, &[0], 1),
+                // 0003 : Turn the collection into an iterator
+                definitions::make(Opcode::Iter, &[], 1),
+                // 0004 : Push the next element or exit the loop
+                definitions::make(Opcode::IterNext, &[18, 1], 1),
+                // 0008 : Bind the loop variable
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                // 0011 : The body
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                // 0014
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0015 : Jump back to fetch the next element
+                definitions::make(Opcode::Jump, &[4], 1),
+                // 0018 : The value of the for expression
+                definitions::make(Opcode::Nil, &[], 1),
+                // 0019
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "for (k, v in {}) { v }",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::Map, &[0], 1),
+                // 0003
+                definitions::make(Opcode::Iter, &[], 1),
+                // 0004
+                definitions::make(Opcode::IterNext, &[21, 2], 1),
+                // 0008 : The value is on top of the key
+                definitions::make(Opcode::SetGlobal, &[1], 1),
+                // 0011
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                // 0014
+                definitions::make(Opcode::GetGlobal, &[1], 1),
+                // 0017
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0018
+                definitions::make(Opcode::Jump, &[4], 1),
+                // 0021
+                definitions::make(Opcode::Nil, &[], 1),
+                // 0022
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+
+    run_compiler_tests(&tests);
+}
+
 #[test]
 fn test_global_let_statements() {
     let tests = vec![
         CompilerTestCase {
             input: "let one = 1;let two = 2;",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::SetGlobal, &[0], 1),
@@ -388,7 +665,7 @@ fn test_global_let_statements() {
         },
         CompilerTestCase {
             input: "let one = 1;one;",
-            expected_constants: vec![Object::Number(1.)],
+            expected_constants: vec![Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::SetGlobal, &[0], 1),
@@ -398,7 +675,7 @@ fn test_global_let_statements() {
         },
         CompilerTestCase {
             input: "let one = 1;let two = one;two;",
-            expected_constants: vec![Object::Number(1.)],
+            expected_constants: vec![Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::SetGlobal, &[0], 1),
@@ -413,6 +690,178 @@ fn test_global_let_statements() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_destructure_statements() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "let [a, b, ...c] = [1]; b",
+            expected_constants: vec![Object::Integer(1)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Array, &[1], 1),
+                definitions::make(Opcode::UnpackArray, &[2, 1], 1),
+                definitions::make(Opcode::SetGlobal, &[2], 1),
+                definitions::make(Opcode::SetGlobal, &[1], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::GetGlobal, &[1], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "fn(m) { let {x, y} = m; y }",
+            expected_constants: vec![
+                Object::Str(String::from("x")),
+                Object::Str(String::from("y")),
+                Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                    concat_instructions(&[
+                        definitions::make(Opcode::GetLocal, &[0], 1),
+                        definitions::make(Opcode::Constant, &[0], 1),
+                        definitions::make(Opcode::Constant, &[1], 1),
+                        definitions::make(Opcode::UnpackMap, &[2], 1),
+                        definitions::make(Opcode::SetLocal, &[2], 1),
+                        definitions::make(Opcode::SetLocal, &[1], 1),
+                        definitions::make(Opcode::GetLocal, &[2], 1),
+                        definitions::make(Opcode::ReturnValue, &[], 1),
+                    ]),
+                    3,
+                    1,
+                ))),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Closure, &[2, 0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_spread_expressions() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "let a = []; [1, ...a, 2, 3, ...a]",
+            expected_constants: vec![Object::Integer(1), Object::Integer(2), Object::Integer(3)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Array, &[0], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Array, &[1], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::Array, &[2], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "let a = []; len(...a, 1)",
+            expected_constants: vec![Object::Integer(1)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Array, &[0], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::GetBuiltin, &[0], 1),
+                definitions::make(Opcode::Array, &[0], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Array, &[1], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::CallSpread, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_slice_expressions() {
+    let tests = vec![CompilerTestCase {
+        input: "[1, 2, 3][1::-1]",
+        expected_constants: vec![
+            Object::Integer(1),
+            Object::Integer(2),
+            Object::Integer(3),
+            Object::Integer(1),
+            Object::Integer(1),
+        ],
+        expected_instructions: vec![
+            definitions::make(Opcode::Constant, &[0], 1),
+            definitions::make(Opcode::Constant, &[1], 1),
+            definitions::make(Opcode::Constant, &[2], 1),
+            definitions::make(Opcode::Array, &[3], 1),
+            definitions::make(Opcode::Constant, &[3], 1),
+            definitions::make(Opcode::Nil, &[], 1),
+            definitions::make(Opcode::Constant, &[4], 1),
+            definitions::make(Opcode::Minus, &[], 1),
+            definitions::make(Opcode::Slice, &[], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_property_and_method_calls() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "let m = {}; m.a; m.b = 1",
+            expected_constants: vec![
+                Object::Str("a".into()),
+                Object::Str("b".into()),
+                Object::Integer(1),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Map, &[0], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::GetProperty, &[0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::SetIndex, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: r#""a".split("", 1)"#,
+            expected_constants: vec![
+                Object::Str("a".into()),
+                Object::Str("split".into()),
+                Object::Str("".into()),
+                Object::Integer(1),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::Constant, &[3], 1),
+                definitions::make(Opcode::Invoke, &[1, 2, 0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "[].push(...[1])",
+            expected_constants: vec![Object::Str("push".into()), Object::Integer(1)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Array, &[0], 1),
+                definitions::make(Opcode::Array, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Array, &[1], 1),
+                definitions::make(Opcode::Spread, &[], 1),
+                definitions::make(Opcode::InvokeSpread, &[0, 0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
 #[test]
 fn test_string_expressions() {
     let tests = vec![
@@ -437,6 +886,22 @@ fn test_string_expressions() {
                 definitions::make(Opcode::Pop, &[], 1),
             ],
         },
+        CompilerTestCase {
+            input: r#""one ${1} two ${true}""#,
+            expected_constants: vec![
+                Object::Str(String::from("one ")),
+                Object::Integer(1),
+                Object::Str(String::from(" two ")),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::True, &[], 1),
+                definitions::make(Opcode::Concat, &[4], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
     ];
 
     run_compiler_tests(&tests);
@@ -457,7 +922,7 @@ fn test_array_literals() {
         },
         CompilerTestCase {
             input: "[1, 2, 3]",
-            expected_constants: vec![Object::Number(1.), Object::Number(2.), Object::Number(3.)],
+            expected_constants: vec![Object::Integer(1), Object::Integer(2), Object::Integer(3)],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
                 definitions::make(Opcode::Constant, &[1], 1),
@@ -469,12 +934,12 @@ fn test_array_literals() {
         CompilerTestCase {
             input: "[1 + 2, 3 - 4, 5 * 6]",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
-                Object::Number(3.),
-                Object::Number(4.),
-                Object::Number(5.),
-                Object::Number(6.),
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(4),
+                Object::Integer(5),
+                Object::Integer(6),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
@@ -509,12 +974,12 @@ fn test_hash_literals() {
         CompilerTestCase {
             input: "{1: 2, 3: 4, 5: 6}",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
-                Object::Number(3.),
-                Object::Number(4.),
-                Object::Number(5.),
-                Object::Number(6.),
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(4),
+                Object::Integer(5),
+                Object::Integer(6),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
@@ -530,12 +995,12 @@ fn test_hash_literals() {
         CompilerTestCase {
             input: "{1: 2 + 3, 4: 5 * 6}",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
-                Object::Number(3.),
-                Object::Number(4.),
-                Object::Number(5.),
-                Object::Number(6.),
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(4),
+                Object::Integer(5),
+                Object::Integer(6),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
@@ -560,11 +1025,11 @@ fn test_index_expressions() {
         CompilerTestCase {
             input: "[1, 2, 3][1 + 1]",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
-                Object::Number(3.),
-                Object::Number(1.),
-                Object::Number(1.),
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(1),
+                Object::Integer(1),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
@@ -581,10 +1046,10 @@ fn test_index_expressions() {
         CompilerTestCase {
             input: "{1: 2}[2 - 1]",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
-                Object::Number(2.),
-                Object::Number(1.),
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(2),
+                Object::Integer(1),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Constant, &[0], 1),
@@ -602,14 +1067,63 @@ fn test_index_expressions() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_default_and_rest_parameters() {
+    let tests = vec![CompilerTestCase {
+        input: "fn(a, b = 2, c = a, ...d) { d }",
+        expected_constants: vec![
+            Object::Integer(2),
+            Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                concat_instructions(&[
+                    definitions::make(Opcode::Constant, &[0], 1),
+                    definitions::make(Opcode::SetLocal, &[1], 1),
+                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::SetLocal, &[2], 1),
+                    definitions::make(Opcode::GetLocal, &[3], 1),
+                    definitions::make(Opcode::ReturnValue, &[], 1),
+                ]),
+                4,
+                4,
+            ))),
+        ],
+        expected_instructions: vec![
+            definitions::make(Opcode::Closure, &[1, 0], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+    run_compiler_tests(&tests);
+
+    // The entries point to the default value of each optional parameter
+    // followed by the start of the body
+    let tests = [
+        ("fn(a, b = 2, c = a, ...d) { d }", 1, None, vec![0, 5, 9]),
+        ("fn(a = 1) { a }", 0, Some(1), vec![0, 5]),
+        ("fn(a, ...b) { a }", 1, None, vec![]),
+        ("fn(a, b) { a }", 2, Some(2), vec![]),
+    ];
+    for (input, min_args, max_args, entries) in tests {
+        let mut compiler = Compiler::new();
+        compiler.compile(parse_program(input)).unwrap();
+        let bytecode = compiler.bytecode();
+        match bytecode.constants.last().map(|c| c.as_ref()) {
+            Some(Object::CompiledFunc(func)) => {
+                assert_eq!(func.min_args, min_args, "{}", input);
+                assert_eq!(func.max_args, max_args, "{}", input);
+                assert_eq!(func.entries, entries, "{}", input);
+            }
+            c => panic!("constant is not a function. got={:?}", c),
+        }
+    }
+}
+
 #[test]
 fn test_functions() {
     let tests = vec![
         CompilerTestCase {
             input: "fn() { return 5 + 10 }",
             expected_constants: vec![
-                Object::Number(5.),
-                Object::Number(10.),
+                Object::Integer(5),
+                Object::Integer(10),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::Constant, &[0], 1),
@@ -630,8 +1144,8 @@ fn test_functions() {
         CompilerTestCase {
             input: "fn() { 5 + 10 }",
             expected_constants: vec![
-                Object::Number(5.),
-                Object::Number(10.),
+                Object::Integer(5),
+                Object::Integer(10),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::Constant, &[0], 1),
@@ -652,8 +1166,8 @@ fn test_functions() {
         CompilerTestCase {
             input: "fn() { 1; 2 }",
             expected_constants: vec![
-                Object::Number(1.),
-                Object::Number(2.),
+                Object::Integer(1),
+                Object::Integer(2),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::Constant, &[0], 1),
@@ -693,6 +1207,52 @@ fn test_functions_without_return_value() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_deferred_expressions() {
+    let tests = vec![CompilerTestCase {
+        input: "fn() { defer 1; 2 }",
+        expected_constants: vec![
+            Object::Integer(1),
+            Object::Integer(2),
+            Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                concat_instructions(&[
+                    // 0000 : Register the deferred expression and skip it
+                    definitions::make(Opcode::Defer, &[8], 1),
+                    // 0003 : The deferred expression
+                    definitions::make(Opcode::Constant, &[0], 1),
+                    // 0006
+                    definitions::make(Opcode::Pop, &[], 1),
+                    // 0007
+                    definitions::make(Opcode::EndDefer, &[], 1),
+                    // 0008 : The return value
+                    definitions::make(Opcode::Constant, &[1], 1),
+                    // 0011
+                    definitions::make(Opcode::ReturnValue, &[], 1),
+                ]),
+                0,
+                0,
+            ))),
+        ],
+        expected_instructions: vec![
+            definitions::make(Opcode::Closure, &[2, 0], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+
+    run_compiler_tests(&tests);
+
+    let program = parse_program("defer 1;");
+    let mut compiler = Compiler::new();
+    match compiler.compile(program) {
+        Ok(_) => panic!("defer outside of a function compiled without errors"),
+        Err(err) => assert_eq!(err.msg, "defer is only allowed inside a function"),
+    }
+
+    // Each time a defer in a loop is reached, it registers the expression
+    let program = parse_program("fn() { for (x in [1]) { if (x) { defer x; } } }");
+    assert!(Compiler::new().compile(program).is_ok());
+}
+
 #[test]
 fn test_compiler_scopes() {
     let mut compiler = Compiler::new();
@@ -743,7 +1303,7 @@ fn test_function_calls() {
         CompilerTestCase {
             input: "fn() { 24 }()",
             expected_constants: vec![
-                Object::Number(24.),
+                Object::Integer(24),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         // The literal '24'
@@ -765,7 +1325,7 @@ fn test_function_calls() {
             // Function is bound to a name here
             input: "let noArg = fn() { 24 }; noArg();",
             expected_constants: vec![
-                Object::Number(24.),
+                Object::Integer(24),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         // The literal '24'
@@ -799,7 +1359,7 @@ fn test_function_calls() {
                     0,
                     1,
                 ))),
-                Object::Number(24.),
+                Object::Integer(24),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Closure, &[0, 0], 1),
@@ -830,9 +1390,9 @@ fn test_function_calls() {
                     0,
                     3,
                 ))),
-                Object::Number(24.),
-                Object::Number(25.),
-                Object::Number(26.),
+                Object::Integer(24),
+                Object::Integer(25),
+                Object::Integer(26),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Closure, &[0, 0], 1),
@@ -855,7 +1415,7 @@ fn test_let_statement_scopes() {
         CompilerTestCase {
             input: "let num = 55; fn() { num }",
             expected_constants: vec![
-                Object::Number(55.),
+                Object::Integer(55),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         // push the value of global variable 'num'
@@ -879,7 +1439,7 @@ fn test_let_statement_scopes() {
         CompilerTestCase {
             input: "fn() { let num = 55; num }",
             expected_constants: vec![
-                Object::Number(55.),
+                Object::Integer(55),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         // constant - number 55
@@ -909,8 +1469,8 @@ fn test_let_statement_scopes() {
                 }
             ",
             expected_constants: vec![
-                Object::Number(55.),
-                Object::Number(77.),
+                Object::Integer(55),
+                Object::Integer(77),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::Constant, &[0], 1), // 55
@@ -935,6 +1495,127 @@ fn test_let_statement_scopes() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_pipeline() {
+    let tests = vec![CompilerTestCase {
+        input: "[] |> len; [] |> push(1)",
+        expected_constants: vec![Object::Integer(1)],
+        expected_instructions: vec![
+            definitions::make(Opcode::GetBuiltin, &[0], 1),
+            definitions::make(Opcode::Array, &[0], 1),
+            definitions::make(Opcode::Call, &[1], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+            definitions::make(Opcode::GetBuiltin, &[5], 1),
+            definitions::make(Opcode::Array, &[0], 1),
+            definitions::make(Opcode::Constant, &[0], 1),
+            definitions::make(Opcode::Call, &[2], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_match_expression() {
+    let tests = vec![CompilerTestCase {
+        input: "match (1) { [a] => a, _ => 2 }",
+        expected_constants: vec![Object::Integer(1), Object::Integer(0), Object::Integer(2)],
+        expected_instructions: vec![
+            // 0000
+            definitions::make(Opcode::Constant, &[0], 1),
+            // 0003
+            definitions::make(Opcode::SetGlobal, &[0], 1),
+            // 0006
+            definitions::make(Opcode::GetGlobal, &[0], 1),
+            // 0009
+            definitions::make(Opcode::MatchArray, &[1, 0], 1),
+            // 0013
+            definitions::make(Opcode::JumpIfFalse, &[32], 1),
+            // 0016
+            definitions::make(Opcode::GetGlobal, &[0], 1),
+            // 0019
+            definitions::make(Opcode::Constant, &[1], 1),
+            // 0022
+            definitions::make(Opcode::Index, &[], 1),
+            // 0023
+            definitions::make(Opcode::SetGlobal, &[1], 1),
+            // 0026
+            definitions::make(Opcode::GetGlobal, &[1], 1),
+            // 0029
+            definitions::make(Opcode::Jump, &[42], 1),
+            // 0032
+            definitions::make(Opcode::Constant, &[2], 1),
+            // 0035
+            definitions::make(Opcode::Jump, &[42], 1),
+            // 0038
+            definitions::make(Opcode::GetGlobal, &[0], 1),
+            // 0041
+            definitions::make(Opcode::NoMatch, &[], 1),
+            // 0042
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+    run_compiler_tests(&tests);
+}
+
+#[test]
+fn test_try_statement() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "try { 1 } catch (e) { 2 }",
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::SetupTry, &[11], 1),
+                // 0003
+                definitions::make(Opcode::Constant, &[0], 1),
+                // 0006
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0007
+                definitions::make(Opcode::PopTry, &[], 1),
+                // 0008
+                definitions::make(Opcode::Jump, &[21], 1),
+                // 0011
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                // 0014
+                definitions::make(Opcode::Constant, &[1], 1),
+                // 0017
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0018
+                definitions::make(Opcode::Jump, &[21], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "try { throw 1; } finally { 2 }",
+            // The finally block is compiled for both ways of leaving the body
+            expected_constants: vec![Object::Integer(1), Object::Integer(2), Object::Integer(2)],
+            expected_instructions: vec![
+                // 0000
+                definitions::make(Opcode::SetupTry, &[15], 1),
+                // 0003
+                definitions::make(Opcode::Constant, &[0], 1),
+                // 0006
+                definitions::make(Opcode::Throw, &[], 1),
+                // 0007
+                definitions::make(Opcode::PopTry, &[], 1),
+                // 0008
+                definitions::make(Opcode::Constant, &[1], 1),
+                // 0011
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0012
+                definitions::make(Opcode::Jump, &[20], 1),
+                // 0015
+                definitions::make(Opcode::Constant, &[2], 1),
+                // 0018
+                definitions::make(Opcode::Pop, &[], 1),
+                // 0019
+                definitions::make(Opcode::Throw, &[], 1),
+            ],
+        },
+    ];
+    run_compiler_tests(&tests);
+}
+
 #[test]
 fn test_builtins() {
     let tests = vec![
@@ -943,7 +1624,7 @@ fn test_builtins() {
                 len([]);
                 push([], 1);
             "#,
-            expected_constants: vec![Object::Number(1.)],
+            expected_constants: vec![Object::Integer(1)],
             expected_instructions: vec![
                 definitions::make(Opcode::GetBuiltin, &[0], 1),
                 definitions::make(Opcode::Array, &[0], 1),
@@ -1008,7 +1689,7 @@ fn test_closures() {
             ))),
             Object::CompiledFunc(Rc::new(CompiledFunction::new(
                 concat_instructions(&[
-                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::CaptureLocal, &[0], 1),
                     // #free-vars is 1 as there is one free variable on the stack
                     // that needs to be saved into the free field of the closure
                     definitions::make(Opcode::Closure, &[0, 1], 1),
@@ -1069,8 +1750,8 @@ fn test_nested_closures() {
                 // inner-most function as a closure that has two free variables
                 // The number of free variables is passed as the second arg.
                 concat_instructions(&[
-                    definitions::make(Opcode::GetFree, &[0], 1),
-                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::CaptureFree, &[0], 1),
+                    definitions::make(Opcode::CaptureLocal, &[0], 1),
                     // two free variables on stack
                     definitions::make(Opcode::Closure, &[0, 2], 1),
                     definitions::make(Opcode::ReturnValue, &[], 1),
@@ -1082,7 +1763,7 @@ fn test_nested_closures() {
                 // outer-most function has no free variables but compiles
                 // the middle closure that has a single free variable
                 concat_instructions(&[
-                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::CaptureLocal, &[0], 1),
                     definitions::make(Opcode::Closure, &[1, 1], 1),
                     definitions::make(Opcode::ReturnValue, &[], 1),
                 ]),
@@ -1115,10 +1796,10 @@ fn test_closures_with_scopes() {
                 }
             ",
         expected_constants: vec![
-            Object::Number(55.),
-            Object::Number(66.),
-            Object::Number(77.),
-            Object::Number(88.),
+            Object::Integer(55),
+            Object::Integer(66),
+            Object::Integer(77),
+            Object::Integer(88),
             Object::CompiledFunc(Rc::new(CompiledFunction::new(
                 concat_instructions(&[
                     definitions::make(Opcode::Constant, &[3], 1),
@@ -1139,8 +1820,8 @@ fn test_closures_with_scopes() {
                 concat_instructions(&[
                     definitions::make(Opcode::Constant, &[2], 1),
                     definitions::make(Opcode::SetLocal, &[0], 1),
-                    definitions::make(Opcode::GetFree, &[0], 1),
-                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::CaptureFree, &[0], 1),
+                    definitions::make(Opcode::CaptureLocal, &[0], 1),
                     definitions::make(Opcode::Closure, &[4, 2], 1),
                     definitions::make(Opcode::ReturnValue, &[], 1),
                 ]),
@@ -1151,7 +1832,7 @@ fn test_closures_with_scopes() {
                 concat_instructions(&[
                     definitions::make(Opcode::Constant, &[1], 1),
                     definitions::make(Opcode::SetLocal, &[0], 1),
-                    definitions::make(Opcode::GetLocal, &[0], 1),
+                    definitions::make(Opcode::CaptureLocal, &[0], 1),
                     definitions::make(Opcode::Closure, &[5, 1], 1),
                     definitions::make(Opcode::ReturnValue, &[], 1),
                 ]),
@@ -1169,6 +1850,189 @@ fn test_closures_with_scopes() {
     run_compiler_tests(&tests);
 }
 
+#[test]
+fn test_assignments() {
+    let tests = vec![
+        CompilerTestCase {
+            input: "let x = 1; x = 2;",
+            expected_constants: vec![Object::Integer(1), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "fn() { let x = 1; x = 2; }",
+            expected_constants: vec![
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                    concat_instructions(&[
+                        definitions::make(Opcode::Constant, &[0], 1),
+                        definitions::make(Opcode::SetLocal, &[0], 1),
+                        definitions::make(Opcode::Constant, &[1], 1),
+                        definitions::make(Opcode::SetLocal, &[0], 1),
+                        definitions::make(Opcode::GetLocal, &[0], 1),
+                        definitions::make(Opcode::ReturnValue, &[], 1),
+                    ]),
+                    1,
+                    0,
+                ))),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Closure, &[2, 0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "fn(a) { fn() { a = 2 } }",
+            expected_constants: vec![
+                Object::Integer(2),
+                Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                    // assignment to the captured variable writes into its cell
+                    concat_instructions(&[
+                        definitions::make(Opcode::Constant, &[0], 1),
+                        definitions::make(Opcode::SetFree, &[0], 1),
+                        definitions::make(Opcode::GetFree, &[0], 1),
+                        definitions::make(Opcode::ReturnValue, &[], 1),
+                    ]),
+                    0,
+                    0,
+                ))),
+                Object::CompiledFunc(Rc::new(CompiledFunction::new(
+                    concat_instructions(&[
+                        definitions::make(Opcode::CaptureLocal, &[0], 1),
+                        definitions::make(Opcode::Closure, &[1, 1], 1),
+                        definitions::make(Opcode::ReturnValue, &[], 1),
+                    ]),
+                    1,
+                    1,
+                ))),
+            ],
+            expected_instructions: vec![
+                definitions::make(Opcode::Closure, &[2, 0], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+        CompilerTestCase {
+            input: "let a = [1]; a[0] = 2;",
+            expected_constants: vec![Object::Integer(1), Object::Integer(0), Object::Integer(2)],
+            expected_instructions: vec![
+                definitions::make(Opcode::Constant, &[0], 1),
+                definitions::make(Opcode::Array, &[1], 1),
+                definitions::make(Opcode::SetGlobal, &[0], 1),
+                definitions::make(Opcode::GetGlobal, &[0], 1),
+                definitions::make(Opcode::Constant, &[1], 1),
+                definitions::make(Opcode::Constant, &[2], 1),
+                definitions::make(Opcode::SetIndex, &[], 1),
+                definitions::make(Opcode::Pop, &[], 1),
+            ],
+        },
+    ];
+
+    run_compiler_tests(&tests);
+
+    let tests = [
+        ("x = 1", "undefined variable x"),
+        ("len = 1", "cannot assign to builtin function len"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_program(input);
+        let mut compiler = Compiler::new();
+        match compiler.compile(program) {
+            Ok(_) => panic!("'{}' compiled without errors", input),
+            Err(err) => assert_eq!(err.msg, expected),
+        }
+    }
+}
+
+#[test]
+fn test_const_bindings() {
+    // A constant compiles the same as a 'let' binding
+    let tests = vec![CompilerTestCase {
+        input: "const x = 1; x;",
+        expected_constants: vec![Object::Integer(1)],
+        expected_instructions: vec![
+            definitions::make(Opcode::Constant, &[0], 1),
+            definitions::make(Opcode::SetGlobal, &[0], 1),
+            definitions::make(Opcode::GetGlobal, &[0], 1),
+            definitions::make(Opcode::Pop, &[], 1),
+        ],
+    }];
+
+    run_compiler_tests(&tests);
+
+    let tests = [
+        ("const x = 1; x = 2;", "cannot assign to constant x"),
+        ("const x = 1; let x = 2;", "cannot redefine constant x"),
+        ("const x = 1; const x = 2;", "cannot redefine constant x"),
+        (
+            "const [a, b] = [1, 2]; b = 3;",
+            "cannot assign to constant b",
+        ),
+        ("let a = 1; const {a} = {};", ""),
+        (
+            "const x = 1; let f = fn() { x = 2 };",
+            "cannot assign to constant x",
+        ),
+        (
+            "const x = 1; let f = fn() { let x = 2 };",
+            "cannot redefine constant x",
+        ),
+        (
+            "const x = 1; let f = fn(x) { x };",
+            "cannot redefine constant x",
+        ),
+        (
+            "const x = 1; for (x in [1]) { x }",
+            "cannot redefine constant x",
+        ),
+        (
+            "let f = fn() { const x = 1; fn() { x = 2 } };",
+            "cannot assign to constant x",
+        ),
+        (
+            "const e = 1; try { throw 1; } catch (e) { e }",
+            "cannot redefine constant e",
+        ),
+        (
+            "const x = 1; match (1) { x => x }",
+            "cannot redefine constant x",
+        ),
+        ("if (true) { const x = 1; } else { const x = 2; }", ""),
+        (
+            "if (true) { const x = 1; } x = 2;",
+            "cannot assign to constant x",
+        ),
+        (
+            "if (true) { const x = 1; const x = 2; }",
+            "cannot redefine constant x",
+        ),
+    ];
+    for (input, expected) in tests {
+        let program = parse_program(input);
+        let mut compiler = Compiler::new();
+        match compiler.compile(program) {
+            Ok(_) if expected.is_empty() => {}
+            Ok(_) => panic!("'{}' compiled without errors", input),
+            Err(err) => assert_eq!(err.msg, expected, "{}", input),
+        }
+    }
+
+    // The REPL compiles each line with the symbol table of the earlier lines
+    let mut compiler = Compiler::new();
+    compiler.compile(parse_program("const x = 1;")).unwrap();
+    let mut compiler = Compiler::new_with_state(compiler.symtab, compiler.constants);
+    match compiler.compile(parse_program("let x = 2;")) {
+        Ok(_) => panic!("a constant was redefined"),
+        Err(err) => assert_eq!(err.msg, "cannot redefine constant x"),
+    }
+}
+
 #[test]
 fn test_recursive_functions() {
     let tests = vec![
@@ -1178,7 +2042,7 @@ fn test_recursive_functions() {
             countDown(1);
         "#,
             expected_constants: vec![
-                Object::Number(1.0),
+                Object::Integer(1),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         // first load callee, the the args and then the OpCall
@@ -1193,7 +2057,7 @@ fn test_recursive_functions() {
                     1,
                     0,
                 ))),
-                Object::Number(1.0),
+                Object::Integer(1),
             ],
             expected_instructions: vec![
                 definitions::make(Opcode::Closure, &[1, 0], 1),
@@ -1213,7 +2077,7 @@ fn test_recursive_functions() {
             wrapper();
         "#,
             expected_constants: vec![
-                Object::Number(1.0),
+                Object::Integer(1),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::CurrClosure, &[], 1),
@@ -1226,7 +2090,7 @@ fn test_recursive_functions() {
                     1,
                     0,
                 ))),
-                Object::Number(1.0),
+                Object::Integer(1),
                 Object::CompiledFunc(Rc::new(CompiledFunction::new(
                     concat_instructions(&[
                         definitions::make(Opcode::Closure, &[1, 0], 1),
@@ -1,22 +1,50 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
 
+use crate::common::builtins::lookup_method;
 use crate::common::builtins::BUILTINS;
 use crate::common::environment::*;
 use crate::common::error::RTError;
+use crate::common::module;
 use crate::common::object::*;
 use crate::parser::ast::expr::*;
 use crate::parser::ast::stmt::BlockStatement;
+use crate::parser::ast::stmt::DestructureStmt;
+use crate::parser::ast::stmt::Pattern;
 use crate::parser::ast::stmt::Statement;
+use crate::parser::ast::stmt::TryStmt;
 use crate::parser::ast::*;
 use crate::scanner::token::*;
 
-pub struct Evaluator {}
+// Expressions deferred by a function call along with the environment
+// they are to be evaluated in
+type DeferredExprs = Vec<(Rc<RefCell<Environment>>, Expression)>;
+
+pub struct Evaluator {
+    // One entry per function call being evaluated, innermost call last
+    deferred: Vec<DeferredExprs>,
+    // Files being evaluated, innermost last, and the exports of the modules
+    // that have been imported
+    files: Vec<PathBuf>,
+    modules: HashMap<PathBuf, Rc<Object>>,
+}
 
 impl Evaluator {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            deferred: Vec::new(),
+            files: Vec::new(),
+            modules: HashMap::new(),
+        }
+    }
+
+    // Set the file being evaluated, which the paths of its imports are
+    // relative to
+    pub fn set_file(&mut self, file: PathBuf) {
+        self.files = vec![file];
     }
 
     pub fn eval_program(
@@ -54,7 +82,10 @@ impl Evaluator {
         env: &Rc<RefCell<Environment>>,
         stmt: BlockStatement,
     ) -> Result<Rc<Object>, RTError> {
-        self.eval_statements_nounwrap(env, stmt.statements)
+        let consts = env.borrow().consts();
+        let result = self.eval_statements_nounwrap(env, stmt.statements);
+        env.borrow_mut().close_consts(&consts);
+        result
     }
 
     // Unwrap return values here since this is the outer most block
@@ -86,13 +117,39 @@ impl Evaluator {
         expr: Expression,
     ) -> Result<Rc<Object>, RTError> {
         match expr {
+            Expression::Integer(num) => Ok(Rc::new(Object::Integer(num.value))),
+            Expression::BigInt(num) => Ok(Rc::new(Object::BigInt(num.value))),
             Expression::Number(num) => Ok(Rc::new(Object::Number(num.value))),
             Expression::Str(s) => Ok(Rc::new(Object::Str(s.value))),
+            Expression::Interpolated(s) => {
+                let mut the_str = String::new();
+                for part in s.parts {
+                    the_str.push_str(&self.eval_expression(env, part)?.to_string());
+                }
+                Ok(Rc::new(Object::Str(the_str)))
+            }
             Expression::Bool(num) => Ok(Rc::new(Object::Bool(num.value))),
             Expression::Unary(unary) => {
                 let right = self.eval_expression(env, *unary.right)?;
                 self.eval_prefix_expr(&unary.operator, &right, unary.token.line)
             }
+            Expression::Binary(binary) if binary.operator == "&&" || binary.operator == "||" => {
+                // Evaluate the right operand only if the left does not decide the result
+                let left = !self.eval_expression(env, *binary.left)?.is_falsey();
+                if left == (binary.operator == "||") {
+                    return Ok(Rc::new(Object::Bool(left)));
+                }
+                let right = !self.eval_expression(env, *binary.right)?.is_falsey();
+                Ok(Rc::new(Object::Bool(right)))
+            }
+            Expression::Binary(binary) if binary.operator == "??" => {
+                // Evaluate the right operand only if the left one is nil
+                let left = self.eval_expression(env, *binary.left)?;
+                if !matches!(*left, Object::Nil) {
+                    return Ok(left);
+                }
+                self.eval_expression(env, *binary.right)
+            }
             Expression::Binary(binary) => {
                 let left = self.eval_expression(env, *binary.left)?;
                 let right = self.eval_expression(env, *binary.right)?;
@@ -112,20 +169,30 @@ impl Evaluator {
                 // an else evaluates to a nil object
                 Ok(Rc::new(Object::Nil))
             }
+            Expression::While(expr) => self.eval_while_expr(env, expr),
+            Expression::For(expr) => self.eval_for_expr(env, expr),
             Expression::Function(expr) => Ok(self.eval_function_expr(env, expr)),
             Expression::Ident(expr) => self.eval_identifier_expr(env, &expr.token),
-            Expression::Call(expr) => Ok(self.eval_call_expr(env, expr)?),
-            Expression::Array(arr) => Ok(Rc::new(Object::Arr(Rc::new(Array {
-                elements: self.eval_expressions(env, (*arr.elements).to_vec())?,
-            })))),
+            Expression::Array(arr) => Ok(Rc::new(Object::Arr(Rc::new(Array::new(
+                self.eval_expressions(env, (*arr.elements).to_vec())?,
+            ))))),
             Expression::Hash(expr) => Ok(self.eval_hash_literal(env, expr)?),
-            Expression::Index(expr) => Ok(self.eval_index_expr(env, expr)?),
+            chain @ (Expression::Index(_)
+            | Expression::Slice(_)
+            | Expression::Property(_)
+            | Expression::MethodCall(_)
+            | Expression::Call(_)) => Ok(self
+                .eval_chain(env, chain)?
+                .unwrap_or_else(|| Rc::new(Object::Nil))),
+            Expression::Match(expr) => self.eval_match_expr(env, expr),
+            Expression::Assign(expr) => self.eval_assign_expr(env, expr),
             _ => Ok(Rc::new(Object::Nil)),
         }
     }
 
     // Evaluate a vector of expressions, typically arguments to a function call
-    // Note that the arguments are evaluated from left to right.
+    // Note that the arguments are evaluated from left to right. A spread
+    // expression is replaced by the elements of the array it evaluates to.
     fn eval_expressions(
         &mut self,
         env: &Rc<RefCell<Environment>>,
@@ -133,6 +200,19 @@ impl Evaluator {
     ) -> Result<Vec<Rc<Object>>, RTError> {
         let mut result = Vec::new();
         for expr in exprs {
+            if let Expression::Spread(spread) = expr {
+                let obj = self.eval_expression(env, *spread.value)?;
+                match obj.as_ref() {
+                    Object::Arr(arr) => result.extend(arr.elements.borrow().iter().cloned()),
+                    _ => {
+                        return Err(RTError::new(
+                            "spread operand must be an array",
+                            spread.token.line,
+                        ))
+                    }
+                }
+                continue;
+            }
             let obj = self.eval_expression(env, expr)?;
             result.push(Rc::clone(&obj));
         }
@@ -144,10 +224,121 @@ impl Evaluator {
         env: &Rc<RefCell<Environment>>,
         name: &Identifier,
         expr: Expression,
+        is_const: bool,
     ) -> Result<Rc<Object>, RTError> {
         let value = self.eval_expression(env, expr)?;
         let name = name.token.clone();
-        env.borrow_mut().set(&name, value);
+        if is_const {
+            env.borrow_mut().set_const(&name, value)?;
+        } else {
+            env.borrow_mut().set(&name, value)?;
+        }
+        Ok(Rc::new(Object::Nil))
+    }
+
+    // The first arm whose pattern matches and whose guard is true gives the
+    // value. The names in the pattern are bound only if the pattern matches.
+    fn eval_match_expr(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        expr: MatchExpr,
+    ) -> Result<Rc<Object>, RTError> {
+        let value = self.eval_expression(env, *expr.subject)?;
+        for arm in expr.arms {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(env, arm.pattern, &value, &mut bindings)? {
+                continue;
+            }
+            for (name, value) in bindings {
+                env.borrow_mut().set(&name.token, value)?;
+            }
+            if let Some(guard) = arm.guard {
+                if self.eval_expression(env, guard)?.is_falsey() {
+                    continue;
+                }
+            }
+            return self.eval_block_statement(env, arm.body);
+        }
+        Err(RTError::new(
+            &format!("no match arm for {}", value),
+            expr.token.line,
+        ))
+    }
+
+    // Test a pattern against a value, collecting the names to bind
+    fn match_pattern(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        pattern: MatchPattern,
+        value: &Rc<Object>,
+        bindings: &mut Vec<(Identifier, Rc<Object>)>,
+    ) -> Result<bool, RTError> {
+        match pattern {
+            MatchPattern::Wildcard => Ok(true),
+            MatchPattern::Binding(name) => {
+                bindings.push((name, Rc::clone(value)));
+                Ok(true)
+            }
+            MatchPattern::Literal(literal) => Ok(*self.eval_expression(env, literal)? == **value),
+            MatchPattern::Array(mut elements, rest) => {
+                let Ok(values) = value.unpack_array(elements.len(), rest.is_some()) else {
+                    return Ok(false);
+                };
+                elements.extend(rest.map(|rest| *rest));
+                for (element, value) in elements.into_iter().zip(values) {
+                    if !self.match_pattern(env, element, &value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            MatchPattern::Map(entries) => {
+                let keys: Vec<Rc<Object>> = entries
+                    .iter()
+                    .map(|(key, _)| Rc::new(Object::Str(key.clone())))
+                    .collect();
+                let Ok(values) = value.unpack_map(&keys) else {
+                    return Ok(false);
+                };
+                for ((_, pattern), value) in entries.into_iter().zip(values) {
+                    if !self.match_pattern(env, pattern, &value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn eval_destructure_stmt(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        stmt: DestructureStmt,
+    ) -> Result<Rc<Object>, RTError> {
+        let is_const = stmt.is_const();
+        let value = self.eval_expression(env, stmt.value)?;
+        let (names, values) = match stmt.pattern {
+            Pattern::Array(mut names, rest) => {
+                let values = value.unpack_array(names.len(), rest.is_some());
+                names.extend(rest);
+                (names, values)
+            }
+            Pattern::Map(names) => {
+                let keys: Vec<Rc<Object>> = names
+                    .iter()
+                    .map(|name| Rc::new(Object::Str(name.value.clone())))
+                    .collect();
+                (names, value.unpack_map(&keys))
+            }
+        };
+        let values = values.map_err(|e| RTError::new(&e, stmt.token.line))?;
+        for (name, value) in names.iter().zip(values) {
+            if is_const {
+                env.borrow_mut().set_const(&name.token, value)?;
+            } else {
+                env.borrow_mut().set(&name.token, value)?;
+            }
+        }
         Ok(Rc::new(Object::Nil))
     }
 
@@ -159,15 +350,148 @@ impl Evaluator {
         match stmt {
             Statement::Expr(stmt) => self.eval_expression(env, stmt.value),
             Statement::Return(stmt) => self.eval_return_stmt(env, stmt.value),
-            Statement::Let(stmt) => self.eval_let_stmt(env, &stmt.name, stmt.value),
+            Statement::Let(stmt) => {
+                let is_const = stmt.is_const();
+                self.eval_let_stmt(env, &stmt.name, stmt.value, is_const)
+            }
+            Statement::Destructure(stmt) => self.eval_destructure_stmt(env, stmt),
+            Statement::Defer(stmt) => match self.deferred.last_mut() {
+                Some(deferred) => {
+                    deferred.push((env.clone(), stmt.value));
+                    Ok(Rc::new(Object::Nil))
+                }
+                None => Err(RTError::new(
+                    "defer is only allowed inside a function",
+                    stmt.token.line,
+                )),
+            },
+            Statement::Throw(stmt) => {
+                let value = self.eval_expression(env, stmt.value)?;
+                Err(RTError::thrown(value, stmt.token.line))
+            }
+            Statement::Try(stmt) => self.eval_try_stmt(env, stmt),
+            Statement::Import(stmt) => {
+                let module = self.eval_import(&stmt.path, stmt.token.line)?;
+                env.borrow_mut().set(&stmt.name.token, module)?;
+                Ok(Rc::new(Object::Nil))
+            }
             _ => Ok(Rc::new(Object::Nil)),
         }
     }
 
+    // A module is evaluated once, in an environment of its own, into a map
+    // of its exports that is shared by all the imports of the module
+    fn eval_import(&mut self, path: &str, line: usize) -> Result<Rc<Object>, RTError> {
+        let path = module::resolve(path, &self.files).map_err(|e| RTError::new(&e, line))?;
+        if let Some(exports) = self.modules.get(&path) {
+            return Ok(Rc::clone(exports));
+        }
+        let program = module::load(&path).map_err(|e| RTError::new(&e, line))?;
+        let names = module::exports(&program);
+        let env = Rc::new(RefCell::new(Environment::default()));
+        // The module is not part of the function that imports it
+        let deferred = mem::take(&mut self.deferred);
+        self.files.push(path.clone());
+        let result = self.eval_statements(&env, program.statements);
+        self.files.pop();
+        self.deferred = deferred;
+        result?;
+
+        #[allow(clippy::mutable_key_type)]
+        let pairs = names
+            .into_iter()
+            .filter_map(|name| {
+                let value = env.borrow().get(&name)?;
+                Some((Rc::new(Object::Str(name)), value))
+            })
+            .collect();
+        let exports = Rc::new(Object::Map(Rc::new(HMap::new(pairs))));
+        self.modules.insert(path, Rc::clone(&exports));
+        Ok(exports)
+    }
+
+    // The catch block handles the errors raised in the body, which are
+    // either thrown values or runtime errors. The finally block is evaluated
+    // however the other blocks end, and a return or an error in it replaces
+    // the outcome of those blocks.
+    fn eval_try_stmt(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        stmt: TryStmt,
+    ) -> Result<Rc<Object>, RTError> {
+        let mut result = self.eval_block_statement(env, stmt.body);
+        if let Some((name, body)) = stmt.catch {
+            if let Err(err) = result {
+                env.borrow_mut().set(&name.token, err.into_object())?;
+                result = self.eval_block_statement(env, body);
+            }
+        }
+        if let Some(body) = stmt.finally {
+            let value = self.eval_block_statement(env, body)?;
+            if let Object::Return(_) = *value {
+                return Ok(value);
+            }
+        }
+        // Only a return leaves the statement with a value
+        match *result? {
+            Object::Return(ref value) => Ok(Rc::new(Object::Return(Rc::clone(value)))),
+            _ => Ok(Rc::new(Object::Nil)),
+        }
+    }
+
+    // Evaluate the body as long as the condition holds. A return statement
+    // inside the body stops the loop and propagates the wrapped return value.
+    // The loop itself evaluates to a nil object.
+    fn eval_while_expr(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        expr: WhileExpr,
+    ) -> Result<Rc<Object>, RTError> {
+        loop {
+            let condition = self.eval_expression(env, (*expr.condition).clone())?;
+            if condition.is_falsey() {
+                break;
+            }
+            let result = self.eval_block_statement(env, expr.body.clone())?;
+            if let Object::Return(_) = *result {
+                return Ok(result);
+            }
+        }
+        Ok(Rc::new(Object::Nil))
+    }
+
+    // Evaluate the body once for every entry of an array, a map or a string
+    // after binding the loop variables in the current environment.
+    fn eval_for_expr(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        expr: ForExpr,
+    ) -> Result<Rc<Object>, RTError> {
+        let iterable = self.eval_expression(env, *expr.iterable)?;
+        let mut iter = Iter::new(&iterable).ok_or_else(|| {
+            RTError::new(
+                &format!("object is not iterable: {}", iterable),
+                expr.token.line,
+            )
+        })?;
+        while let Some(values) = iter.next(expr.idents.len()) {
+            for (ident, value) in expr.idents.iter().zip(values) {
+                env.borrow_mut().set(&ident.token, value)?;
+            }
+            let result = self.eval_block_statement(env, expr.body.clone())?;
+            if let Object::Return(_) = *result {
+                return Ok(result);
+            }
+        }
+        Ok(Rc::new(Object::Nil))
+    }
+
     fn is_truthy(obj: &Object) -> bool {
         match obj {
             Object::Nil => false,
             Object::Bool(b) => *b,
+            Object::Integer(n) => *n != 0,
+            Object::BigInt(n) => !n.is_zero(),
             Object::Number(n) => *n != 0.,
             _ => true,
         }
@@ -182,6 +506,10 @@ impl Evaluator {
         match operator {
             "!" => Ok(self.eval_bang_operator_expr(right)),
             "-" => self.eval_minus_operator_expr(right, line),
+            "~" => match right.as_integer() {
+                Some(n) => Ok(Rc::new(Object::Integer(!n))),
+                None => Err(RTError::new("bitwise operand must be an integer", line)),
+            },
             _ => Err(RTError::new("invalid prefix operator", line)),
         }
     }
@@ -192,9 +520,12 @@ impl Evaluator {
     }
 
     fn eval_minus_operator_expr(&self, right: &Object, line: usize) -> Result<Rc<Object>, RTError> {
-        match right {
-            Object::Number(num) => Ok(Rc::new(Object::Number(-num))),
-            _ => Err(RTError::new("invalid unary operation", line)),
+        if !right.is_number() {
+            return Err(RTError::new("invalid unary operation", line));
+        }
+        match right.checked_neg() {
+            Ok(num) => Ok(Rc::new(num)),
+            Err(msg) => Err(RTError::new(&msg, line)),
         }
     }
 
@@ -205,30 +536,39 @@ impl Evaluator {
         right: &Object,
         line: usize,
     ) -> Result<Rc<Object>, RTError> {
+        if matches!(operator, "&" | "|" | "^" | "<<" | ">>") {
+            return self.eval_bitwise_expr(operator, left, right, line);
+        }
         match (left, right) {
-            (Object::Number(left), Object::Number(right)) => match operator {
-                "+" => Ok(Rc::new(Object::Number(left + right))),
-                "-" => Ok(Rc::new(Object::Number(left - right))),
-                "*" => Ok(Rc::new(Object::Number(left * right))),
-                "/" => Ok(Rc::new(Object::Number(left / right))),
-                "<" => Ok(Rc::new(Object::Bool(left < right))),
-                ">" => Ok(Rc::new(Object::Bool(left > right))),
-                "==" => Ok(Rc::new(Object::Bool(left == right))),
-                "!=" => Ok(Rc::new(Object::Bool(left != right))),
-                _ => Err(RTError::new("invalid binary operator", line)),
-            },
+            (left, right) if left.is_number() && right.is_number() => {
+                let result = match operator {
+                    "+" => left.checked_add(right),
+                    "-" => left.checked_sub(right),
+                    "*" => left.checked_mul(right),
+                    "/" => left.checked_div(right),
+                    "~/" => left.floor_div(right),
+                    "%" => left.checked_rem(right),
+                    "**" => left.pow(right),
+                    "<" => Ok(Object::Bool(left < right)),
+                    ">" => Ok(Object::Bool(left > right)),
+                    "==" => Ok(Object::Bool(left == right)),
+                    "!=" => Ok(Object::Bool(left != right)),
+                    _ => return Err(RTError::new("invalid binary operator", line)),
+                };
+                result.map(Rc::new).map_err(|msg| RTError::new(&msg, line))
+            }
             (Object::Str(left), Object::Str(right)) => match operator {
                 "+" => Ok(Rc::new(Object::Str(format!("{}{}", left, right)))),
                 "==" => Ok(Rc::new(Object::Bool(left == right))),
                 "!=" => Ok(Rc::new(Object::Bool(left != right))),
                 _ => Err(RTError::new("invalid binary operator", line)),
             },
-            (Object::Str(s), Object::Number(n)) | (Object::Number(n), Object::Str(s)) => {
-                match operator {
-                    "*" => Ok(Rc::new(Object::Str(s.repeat(*n as usize)))),
-                    _ => Err(RTError::new("invalid binary operator", line)),
-                }
-            }
+            (Object::Str(s), n) | (n, Object::Str(s)) if n.is_number() => match operator {
+                "*" => Ok(Rc::new(Object::Str(
+                    s.repeat(n.as_float().unwrap() as usize),
+                ))),
+                _ => Err(RTError::new("invalid binary operator", line)),
+            },
             (Object::Bool(left), Object::Bool(right)) => match operator {
                 "==" => Ok(Rc::new(Object::Bool(left == right))),
                 "!=" => Ok(Rc::new(Object::Bool(left != right))),
@@ -244,10 +584,40 @@ impl Evaluator {
                 "!=" => Ok(Rc::new(Object::Bool(left != right))),
                 _ => Err(RTError::new("invalid binary operation", line)),
             },
+            // Anything can be compared with nil
+            (Object::Nil, _) | (_, Object::Nil) => match operator {
+                "==" => Ok(Rc::new(Object::Bool(left == right))),
+                "!=" => Ok(Rc::new(Object::Bool(left != right))),
+                _ => Err(RTError::new("invalid binary operation", line)),
+            },
             _ => Err(RTError::new("invalid binary operation", line)),
         }
     }
 
+    fn eval_bitwise_expr(
+        &self,
+        operator: &str,
+        left: &Object,
+        right: &Object,
+        line: usize,
+    ) -> Result<Rc<Object>, RTError> {
+        let (Some(left), Some(right)) = (left.as_integer(), right.as_integer()) else {
+            return Err(RTError::new("bitwise operands must be integers", line));
+        };
+        let result = match operator {
+            "&" => Some(left & right),
+            "|" => Some(left | right),
+            "^" => Some(left ^ right),
+            "<<" => u32::try_from(right).ok().and_then(|r| left.checked_shl(r)),
+            ">>" => u32::try_from(right).ok().and_then(|r| left.checked_shr(r)),
+            _ => return Err(RTError::new("invalid binary operator", line)),
+        };
+        match result {
+            Some(result) => Ok(Rc::new(Object::Integer(result))),
+            None => Err(RTError::new("shift amount out of range", line)),
+        }
+    }
+
     fn eval_identifier_expr(
         &self,
         environment: &Rc<RefCell<Environment>>,
@@ -265,6 +635,85 @@ impl Evaluator {
         }
     }
 
+    // Update the binding in the environment that defines it. This could be
+    // an enclosing environment, for example when a closure assigns to a
+    // variable of the function that created it. Index assignments update
+    // the array or the map in place.
+    fn eval_assign_expr(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        expr: AssignExpr,
+    ) -> Result<Rc<Object>, RTError> {
+        let line = expr.token.line;
+        match *expr.target {
+            Expression::Ident(ident) => {
+                let value = self.eval_expression(env, *expr.value)?;
+                env.borrow_mut()
+                    .assign(&ident.value, value.clone())
+                    .map_err(|e| RTError::new(&e, line))?;
+                Ok(value)
+            }
+            Expression::Index(target) => {
+                let left = self.eval_expression(env, *target.left)?;
+                let index = self.eval_expression(env, *target.index)?;
+                let value = self.eval_expression(env, *expr.value)?;
+                self.eval_set_index_expr(&left, index, value.clone(), line)?;
+                Ok(value)
+            }
+            Expression::Property(target) => {
+                let left = self.eval_expression(env, *target.left)?;
+                let index = Rc::new(Object::Str(target.name.value));
+                let value = self.eval_expression(env, *expr.value)?;
+                self.eval_set_index_expr(&left, index, value.clone(), line)?;
+                Ok(value)
+            }
+            _ => Err(RTError::new("invalid assignment target", line)),
+        }
+    }
+
+    fn eval_set_index_expr(
+        &mut self,
+        left: &Object,
+        index: Rc<Object>,
+        value: Rc<Object>,
+        line: usize,
+    ) -> Result<(), RTError> {
+        match left {
+            Object::Arr(arr) => {
+                let idx = match index.as_integer() {
+                    Some(idx) => idx,
+                    _ => return Err(RTError::new("invalid index to array object", line)),
+                };
+                let mut elements = arr.elements.borrow_mut();
+                // Negative indices count from the end
+                let pos = if idx < 0 {
+                    idx + elements.len() as i64
+                } else {
+                    idx
+                };
+                if pos < 0 || pos >= elements.len() as i64 {
+                    return Err(RTError::new(
+                        &format!("array index out of bounds: {}", idx),
+                        line,
+                    ));
+                }
+                elements[pos as usize] = value;
+                Ok(())
+            }
+            Object::Map(map) => {
+                if !index.is_a_valid_key() {
+                    return Err(RTError::new(
+                        "hash key should be a numeric, a string or a boolean",
+                        line,
+                    ));
+                }
+                map.pairs.borrow_mut().insert(index, value);
+                Ok(())
+            }
+            _ => Err(RTError::new("index assignment not supported", line)),
+        }
+    }
+
     // Evaluate expression that defines a function
     fn eval_function_expr(
         &self,
@@ -273,8 +722,12 @@ impl Evaluator {
     ) -> Rc<Object> {
         Rc::new(Object::Func(Rc::new(Function {
             params: func.params,
+            defaults: func.defaults,
+            rest: func.rest,
             body: func.body,
             env: environment.clone(),
+            consts: environment.borrow().visible_consts(),
+            doc: func.doc,
         })))
     }
 
@@ -283,50 +736,166 @@ impl Evaluator {
     // It can be an 'Identifier' or a 'FunctionLiteral'. It evaluates to a
     // 'Function' object. To call the function, first evaluate the list of
     // arguments which is evaluating a list of expressions.
-    fn eval_call_expr(
+    // Evaluate a member access, index, slice or call. The object it applies
+    // to is evaluated as a link of the same chain, so an optional access that
+    // finds nil skips the rest of the chain, which is indicated by None:
+    // 'a?.b.c' is nil if 'a' is nil.
+    fn eval_chain(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        expr: Expression,
+    ) -> Result<Option<Rc<Object>>, RTError> {
+        let result = match expr {
+            Expression::Index(expr) => {
+                let Some(left) = self.eval_chain_link(env, *expr.left, expr.optional)? else {
+                    return Ok(None);
+                };
+                self.eval_index_expr(env, left, *expr.index, expr.token.line)?
+            }
+            Expression::Slice(expr) => {
+                let line = expr.token.line;
+                let Some(left) = self.eval_chain_link(env, *expr.left, expr.optional)? else {
+                    return Ok(None);
+                };
+                // Omitted bounds and step are passed as nil
+                let mut parts = Vec::with_capacity(3);
+                for part in [expr.start, expr.end, expr.step] {
+                    parts.push(match part {
+                        Some(part) => self.eval_expression(env, *part)?,
+                        None => Rc::new(Object::Nil),
+                    });
+                }
+                let result = left
+                    .slice(&parts[0], &parts[1], &parts[2])
+                    .map_err(|msg| RTError::new(&msg, line))?;
+                Rc::new(result)
+            }
+            Expression::Property(expr) => {
+                let Some(left) = self.eval_chain_link(env, *expr.left, expr.optional)? else {
+                    return Ok(None);
+                };
+                left.get_property(&expr.name.value)
+                    .map_err(|msg| RTError::new(&msg, expr.token.line))?
+            }
+            Expression::MethodCall(call) => {
+                let Some(receiver) = self.eval_chain_link(env, *call.receiver, call.optional)?
+                else {
+                    return Ok(None);
+                };
+                self.eval_method_call(env, receiver, &call.name.value, call.args, call.token.line)?
+            }
+            Expression::Call(call) => {
+                let Some(function) = self.eval_chain_link(env, *call.func, false)? else {
+                    return Ok(None);
+                };
+                let args = self.eval_expressions(env, (*call.args).to_vec())?;
+                match &*function {
+                    Object::Func(func) => self.invoke_function_call(func, args, call.token.line)?,
+                    Object::Builtin(func) => {
+                        self.invoke_builtin_function(func, args, call.token.line)?
+                    }
+                    _ => return Err(RTError::new("calling non-function", call.token.line)),
+                }
+            }
+            expr => self.eval_expression(env, expr)?,
+        };
+        Ok(Some(result))
+    }
+
+    // An optional access such as 'a?.b' ends the chain if the object is nil
+    fn eval_chain_link(
+        &mut self,
+        env: &Rc<RefCell<Environment>>,
+        left: Expression,
+        optional: bool,
+    ) -> Result<Option<Rc<Object>>, RTError> {
+        let left = self.eval_chain(env, left)?;
+        Ok(left.filter(|left| !(optional && matches!(**left, Object::Nil))))
+    }
+
+    // Call a function stored in a map, or a function bound in the caller's
+    // environment or a builtin with the receiver as the first argument
+    fn eval_method_call(
         &mut self,
         env: &Rc<RefCell<Environment>>,
-        call: CallExpr,
+        receiver: Rc<Object>,
+        name: &str,
+        args: Vec<Expression>,
+        line: usize,
     ) -> Result<Rc<Object>, RTError> {
-        let function = self.eval_expression(env, *call.func)?;
-        let args = self.eval_expressions(env, (*call.args).to_vec())?;
-        match &*function {
-            Object::Func(func) => self.invoke_function_call(func, args),
-            Object::Builtin(func) => self.invoke_builtin_function(func, args),
-            _ => Err(RTError::new(
-                &format!("Not a function: '{}'", call.token.literal),
-                call.token.line,
-            )),
+        let mut args = self.eval_expressions(env, args)?;
+        let binding = env.borrow().get(name);
+        let (method, pass_receiver) =
+            lookup_method(&receiver, name, binding).map_err(|msg| RTError::new(&msg, line))?;
+        if pass_receiver {
+            args.insert(0, receiver);
+        }
+        match &*method {
+            Object::Func(func) => self.invoke_function_call(func, args, line),
+            Object::Builtin(func) => self.invoke_builtin_function(func, args, line),
+            _ => Err(RTError::new("calling non-function", line)),
         }
     }
 
     // This function creates a new function environment that is enclosed by
     // the function's environment. In this new enclosed environment, it binds
     /// the argument of the function calls to the function's parameter names.
+    /// The default values of the missing arguments are evaluated in the new
+    /// environment so that they can refer to the preceding parameters.
     fn invoke_function_call(
         &mut self,
         function: &Function,
-        args: Vec<Rc<Object>>,
+        mut args: Vec<Rc<Object>>,
+        line: usize,
     ) -> Result<Rc<Object>, RTError> {
+        let num_params = function.params.len();
+        let min_args = num_params - function.defaults.len();
+        let max_args = function.rest.is_none().then_some(num_params);
+        check_arity(min_args, max_args, args.len()).map_err(|e| RTError::new(&e, line))?;
         // Create extended env.
         // Do not use the current environment as the enclosing env. Instead use the
         // environment that 'function' object carries around. That is the environment
         // that the function was defined in.
-        let mut extended_env = Environment::new_enclosing(function.env.clone());
+        let extended_env = Rc::new(RefCell::new(Environment::new_enclosing(
+            function.env.clone(),
+            function.consts.clone(),
+        )));
+        let rest = args.split_off(args.len().min(num_params));
         // Convert arguments to params
         for (i, param) in function.params.iter().enumerate() {
-            extended_env.set(&param.token, args[i].clone())
+            let value = match args.get(i) {
+                Some(arg) => arg.clone(),
+                None => {
+                    let default = function.defaults[i - min_args].clone();
+                    self.eval_expression(&extended_env, default)?
+                }
+            };
+            extended_env.borrow_mut().set(&param.token, value)?;
+        }
+        if let Some(param) = &function.rest {
+            let rest = Rc::new(Object::Arr(Rc::new(Array::new(rest))));
+            extended_env.borrow_mut().set(&param.token, rest)?;
         }
         // TODO: Do not clone the block statements
-        self.eval_statements(
-            &Rc::new(RefCell::new(extended_env)),
-            function.body.statements.clone(),
-        )
+        self.deferred.push(Vec::new());
+        let result = self.eval_statements(&extended_env, function.body.statements.clone());
+        let deferred = self.deferred.pop().unwrap_or_default();
+        // Evaluate deferred expressions in reverse order after the return
+        // value, even if the function failed. The error of a deferred
+        // expression replaces the result.
+        let mut result = result;
+        for (env, expr) in deferred.into_iter().rev() {
+            if let Err(err) = self.eval_expression(&env, expr) {
+                result = Err(err);
+            }
+        }
+        result
     }
     fn invoke_builtin_function(
         &mut self,
         func: &BuiltinFunction,
         args: Vec<Rc<Object>>,
+        line: usize,
     ) -> Result<Rc<Object>, RTError> {
         let builtin_func = func.func;
 
@@ -336,7 +905,7 @@ impl Evaluator {
             Err(s) => {
                 // Prefix error messaage with the function name
                 let msg = format!("{}: {}", func.name, s);
-                Err(RTError::new(&msg, 1))
+                Err(RTError::new(&msg, line))
             }
         }
     }
@@ -344,39 +913,19 @@ impl Evaluator {
     fn eval_index_expr(
         &mut self,
         env: &Rc<RefCell<Environment>>,
-        expr: IndexExpr,
-    ) -> Result<Rc<Object>, RTError> {
-        let obj = self.eval_expression(env, (*expr.left).clone())?;
-        if let Object::Arr(arr) = &*obj {
-            let index = self.eval_expression(env, *expr.index)?;
-            self.eval_array_index_expr(arr, Rc::clone(&index), expr.token.line)
-        } else if let Object::Map(map) = &*obj {
-            let index = self.eval_expression(env, *expr.index)?;
-            self.eval_hash_index_expr(map, Rc::clone(&index), expr.token.line)
-        } else {
-            Err(RTError::new(
-                "index operator not supported",
-                expr.token.line,
-            ))
-        }
-    }
-
-    fn eval_array_index_expr(
-        &mut self,
-        arr: &Array,
-        index: Rc<Object>,
+        obj: Rc<Object>,
+        index: Expression,
         line: usize,
     ) -> Result<Rc<Object>, RTError> {
-        if let Object::Number(idx) = *index {
-            let idx = idx;
-            if idx < 0. || idx >= arr.elements.len() as f64 {
-                // Out of bounds
-                Ok(Rc::new(Object::Nil))
-            } else {
-                Ok(arr.elements[idx as usize].clone())
-            }
+        if let Object::Arr(_) | Object::Str(_) = &*obj {
+            let index = self.eval_expression(env, index)?;
+            obj.index_sequence(&index)
+                .map_err(|msg| RTError::new(&msg, line))
+        } else if let Object::Map(map) = &*obj {
+            let index = self.eval_expression(env, index)?;
+            self.eval_hash_index_expr(map, Rc::clone(&index), line)
         } else {
-            Err(RTError::new("invalid index to array object", line))
+            Err(RTError::new("index operator not supported", line))
         }
     }
 
@@ -402,7 +951,7 @@ impl Evaluator {
             .collect();
 
         match pairs {
-            Ok(pairs) => Ok(Rc::new(Object::Map(Rc::new(HMap { pairs })))),
+            Ok(pairs) => Ok(Rc::new(Object::Map(Rc::new(HMap::new(pairs))))),
             Err(e) => Err(e),
         }
     }
@@ -419,7 +968,7 @@ impl Evaluator {
                 line,
             ));
         }
-        if let Some(val) = map.pairs.get(&index) {
+        if let Some(val) = map.pairs.borrow().get(&index) {
             Ok(Rc::clone(val))
         } else {
             Ok(Rc::new(Object::Nil))
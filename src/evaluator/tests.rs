@@ -7,8 +7,13 @@ use crate::parser::*;
 use crate::scanner::*;
 use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
 use std::rc::Rc;
 
 #[cfg(test)]
@@ -20,7 +25,7 @@ fn check_parse_errors(parser: &Parser) {
 
 #[cfg(test)]
 fn test_numeric_object(evaluated: Rc<Object>, expected: f64) {
-    if let Object::Number(num) = *evaluated {
+    if let Some(num) = evaluated.as_float() {
         assert_eq!(
             num, expected,
             "object has wrong value. got={}, want={}",
@@ -31,6 +36,19 @@ fn test_numeric_object(evaluated: Rc<Object>, expected: f64) {
     }
 }
 
+#[cfg(test)]
+fn test_integer_object(evaluated: Rc<Object>, expected: i64) {
+    if let Object::Integer(num) = *evaluated {
+        assert_eq!(
+            num, expected,
+            "object has wrong value. got={}, want={}",
+            num, expected
+        );
+    } else {
+        panic!("object is not an integer. got={:?}", evaluated);
+    }
+}
+
 #[cfg(test)]
 fn test_string_object(evaluated: Rc<Object>, expected: &str) {
     if let Object::Str(s) = &*evaluated {
@@ -164,6 +182,152 @@ fn test_eval_numeric_expr() {
     }
 }
 
+#[test]
+fn test_eval_float_expr() {
+    let tests = [
+        ("1.5 * 2", 3.),
+        ("0.25 + 0.5", 0.75),
+        ("1e3 - 1_000", 0.),
+        ("0xff + 0o7 + 0b1", 263.),
+        ("-2.5e-1", -0.25),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_eval_modulo_floor_division_and_power() {
+    let tests = [
+        ("7 % 3", 1.),
+        ("-7 % 3", 2.),
+        ("7 % -3", -2.),
+        ("7 ~/ 2", 3.),
+        ("-7 ~/ 2", -4.),
+        ("2 ** 10", 1024.),
+        ("2 ** 3 ** 2", 512.),
+        ("-2 ** 2", -4.),
+        ("2 ** -1", 0.5),
+        ("1 + 2 * 3 ** 2 % 5", 4.),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_eval_integer_expr() {
+    let tests = [
+        ("9007199254740993", 9007199254740993),
+        ("9007199254740992 + 1", 9007199254740993),
+        ("9223372036854775807", i64::MAX),
+        ("-9223372036854775807 - 1", i64::MIN),
+        ("7 ~/ 2", 3),
+        ("-7 % 3", 2),
+        ("2 ** 62", 1 << 62),
+        ("~5", -6),
+        ("len([1, 2, 3])", 3),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    // Mixing integers and floats produces a float
+    let tests = [("1 + 2.5", 3.5), ("7 / 2", 3.5), ("2 ** -1", 0.5)];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => {
+                assert!(matches!(*evaluated, Object::Number(_)), "{}", input);
+                test_numeric_object(evaluated, expected);
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        ("1 == 1.0", true),
+        ("1 != 1.5", true),
+        ("1 < 1.5", true),
+        ("2.5 > 2", true),
+        (r#"let m = {1: "a"}; m[1.0] == "a""#, true),
+        (
+            r#"let m = {}; m[2.0] = "a"; m[2] = "b"; m[2.0] == "b""#,
+            true,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_boolean_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_eval_bigint_expr() {
+    let tests = [
+        ("9223372036854775807n + 1", "9223372036854775808"),
+        (
+            "let f = fn(n) { if (n < 2) { 1n } else { n * f(n - 1) } }; f(25)",
+            "15511210043330985984000000",
+        ),
+        ("-7n / 2", "-4"),
+        ("-7n % 2", "1"),
+        ("(2n ** 89 - 1) % 1000000007", "280261901"),
+        (r#"bigint("-5") * bigint(3)"#, "-15"),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => match evaluated.as_ref() {
+                Object::BigInt(n) => assert_eq!(n.to_string(), expected, "{}", input),
+                _ => panic!("object is not a bigint. got={:?}", evaluated),
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        ("10n == 10", true),
+        ("10n != 11n", true),
+        ("2n < 2.5", true),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_boolean_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_eval_bitwise_expr() {
+    let tests = [
+        ("12 & 10", 8.),
+        ("12 | 10", 14.),
+        ("12 ^ 10", 6.),
+        ("~0", -1.),
+        ("~5 & 0xff", 250.),
+        ("1 << 10", 1024.),
+        ("-16 >> 2", -4.),
+        ("(3 << 8 | 0x2a) >> 8", 3.),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
 #[test]
 fn test_eval_string_expr() {
     struct StringObj {
@@ -187,6 +351,22 @@ fn test_eval_string_expr() {
             input: r#"30 * "*" "#,
             expected: "******************************",
         },
+        StringObj {
+            input: r#"let name = "monkey"; "Hello ${name}!""#,
+            expected: "Hello monkey!",
+        },
+        StringObj {
+            input: r#"let f = fn() { }; "${1 + 2} ${[1, "a"]} ${ {"k": true}["k"] } ${f()}""#,
+            expected: "3 [1, a] true nil",
+        },
+        StringObj {
+            input: r#""${"in ${"side"}"}""#,
+            expected: "in side",
+        },
+        StringObj {
+            input: r#""\${x}""#,
+            expected: "${x}",
+        },
     ];
     for test in numeric_tests {
         let evaluated = test_eval(test.input);
@@ -376,6 +556,129 @@ fn test_if_else_expr() {
     }
 }
 
+#[test]
+fn test_logical_operators() {
+    let tests = [
+        ("true && true", true),
+        ("true && false", false),
+        ("false || true", true),
+        ("false || false", false),
+        ("1 < 2 && 2 < 3 || false", true),
+        (r#"0 && """#, true),
+        ("if (false) { 1 } || [1][5]", false),
+        ("false && len(1)", false),
+        ("true || len(1)", true),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_boolean_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    match test_eval("let x = 0; let f = fn() { x = x + 1; true }; false && f(); true || f(); x") {
+        Ok(evaluated) => test_numeric_object(evaluated, 0.),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn test_while_expr() {
+    struct WhileExpr {
+        input: &'static str,
+        expected: Object,
+    }
+    let while_tests = vec![
+        WhileExpr {
+            input: "while (false) { 10 }",
+            expected: Object::Nil,
+        },
+        WhileExpr {
+            input: "while (1 > 2) { 10 }; 20",
+            expected: Object::Number(20.),
+        },
+        WhileExpr {
+            input: "let f = fn() { while (true) { return 10; } }; f()",
+            expected: Object::Number(10.),
+        },
+        WhileExpr {
+            input: "let f = fn(x) { while (x) { return 10; }; 20 }; f(false)",
+            expected: Object::Number(20.),
+        },
+        // only false and nil end the loop, as in the VM
+        WhileExpr {
+            input: "let i = 0; let n = 0; while (i) { n = n + 1; i = nil; }; n",
+            expected: Object::Number(1.),
+        },
+    ];
+    for test in while_tests {
+        let evaluated = test_eval(test.input);
+        match evaluated {
+            Ok(evaluated) => match test.expected {
+                Object::Number(expected) => test_numeric_object(evaluated, expected),
+                Object::Nil => test_nil_object(evaluated),
+                _ => panic!("Invalid expected object"),
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_for_expr() {
+    struct ForExpr {
+        input: &'static str,
+        expected: Object,
+    }
+    let for_tests = vec![
+        ForExpr {
+            input: "for (x in []) { x }",
+            expected: Object::Nil,
+        },
+        ForExpr {
+            input: "for (x in [1, 2, 3]) { x }; x",
+            expected: Object::Number(3.),
+        },
+        ForExpr {
+            input: "let f = fn(a) { for (x in a) { if (x > 2) { return x; } } }; f([1, 2, 3, 4])",
+            expected: Object::Number(3.),
+        },
+        ForExpr {
+            input: r#"for (c in "abc") { c }; c"#,
+            expected: Object::Str("c".to_string()),
+        },
+        ForExpr {
+            input: r#"for (i, c in "héllo") { if (i == 1) { return c; } }"#,
+            expected: Object::Str("é".to_string()),
+        },
+        ForExpr {
+            input: r#"for (k in {"b": 2, "c": 3, "a": 1}) { }; k"#,
+            expected: Object::Str("c".to_string()),
+        },
+        ForExpr {
+            input: r#"let f = fn(m) { for (k, v in m) { return k + str(v); } }; f({"b": 2, "a": 1})"#,
+            expected: Object::Str("a1".to_string()),
+        },
+    ];
+    for test in for_tests {
+        let evaluated = test_eval(test.input);
+        match evaluated {
+            Ok(evaluated) => match test.expected {
+                Object::Number(expected) => test_numeric_object(evaluated, expected),
+                Object::Str(expected) => test_string_object(evaluated, &expected),
+                Object::Nil => test_nil_object(evaluated),
+                _ => panic!("Invalid expected object"),
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    match test_eval("for (x in 5) { x }") {
+        Ok(obj) => panic!("No error object returned. got={:?}", obj),
+        Err(err) => assert_eq!(err.msg, "object is not iterable: 5"),
+    }
+}
+
 #[test]
 fn test_return_stmt() {
     struct ReturnTest {
@@ -468,8 +771,8 @@ fn test_error_handling() {
             expected: RTError::new("index operator not supported", 1),
         },
         ErrorTest {
-            input: r#""not_array"[1]"#,
-            expected: RTError::new("index operator not supported", 1),
+            input: r#""not_array"["x"]"#,
+            expected: RTError::new("invalid index to string object", 1),
         },
         ErrorTest {
             input: "fn(x) {x}[1]",
@@ -483,6 +786,58 @@ fn test_error_handling() {
             input: r#"{"name": "Monkey"} [fn(x) {x}]"#,
             expected: RTError::new("hash key should be a numeric, a string or a boolean", 1),
         },
+        ErrorTest {
+            input: "1 / 0",
+            expected: RTError::new("division by zero", 1),
+        },
+        ErrorTest {
+            input: "let x = 0; 1 ~/ x",
+            expected: RTError::new("division by zero", 1),
+        },
+        ErrorTest {
+            input: "1 % 0",
+            expected: RTError::new("modulo by zero", 1),
+        },
+        ErrorTest {
+            input: "9223372036854775807 + 1",
+            expected: RTError::new("integer overflow", 1),
+        },
+        ErrorTest {
+            input: "3037000500 * 3037000500",
+            expected: RTError::new("integer overflow", 1),
+        },
+        ErrorTest {
+            input: "2 ** 63",
+            expected: RTError::new("integer overflow", 1),
+        },
+        ErrorTest {
+            input: "let x = -9223372036854775807 - 1; -x",
+            expected: RTError::new("integer overflow", 1),
+        },
+        ErrorTest {
+            input: "1n + 1.5",
+            expected: RTError::new("cannot mix bigint and float operands", 1),
+        },
+        ErrorTest {
+            input: "1n ~/ 0",
+            expected: RTError::new("division by zero", 1),
+        },
+        ErrorTest {
+            input: "1.5 & 1",
+            expected: RTError::new("bitwise operands must be integers", 1),
+        },
+        ErrorTest {
+            input: "true | false",
+            expected: RTError::new("bitwise operands must be integers", 1),
+        },
+        ErrorTest {
+            input: "~0.5",
+            expected: RTError::new("bitwise operand must be an integer", 1),
+        },
+        ErrorTest {
+            input: "1 << 64",
+            expected: RTError::new("shift amount out of range", 1),
+        },
     ];
     for (i, test) in error_tests.iter().enumerate() {
         let evaluated = test_eval(test.input);
@@ -503,7 +858,7 @@ fn test_let_statement() {
         input: &'static str,
         expected: f64,
     }
-    let error_tests = vec![
+    let error_tests = [
         LetTest {
             input: "let a = 5; a;",
             expected: 5.,
@@ -530,6 +885,240 @@ fn test_let_statement() {
     }
 }
 
+#[test]
+fn test_assign_expr() {
+    let tests = [
+        ("let x = 1; x = 2; x", 2.),
+        ("let x = 1; let y = x = 5; x + y", 10.),
+        ("let x = 0; while (x < 10) { x = x + 1 }; x", 10.),
+        ("let f = fn(a) { a = a * 2; a }; f(21)", 42.),
+        ("let x = 1; let f = fn() { x = x + 1 }; f(); f(); x", 3.),
+        (
+            "let counter = fn() { let c = 0; fn() { c = c + 1 } }; let next = counter(); next(); next()",
+            2.,
+        ),
+        (
+            "let f = fn() { let x = 1; let g = fn() { x }; x = 5; g() }; f()",
+            5.,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    match test_eval("x = 1") {
+        Ok(obj) => panic!("No error object returned. got={:?}", obj),
+        Err(err) => assert_eq!(err.msg, "Undefined identifier: 'x'"),
+    }
+}
+
+#[test]
+fn test_const_bindings() {
+    let tests = [
+        ("const x = 1; const f = fn() { x + 1 }; f()", 2.),
+        (
+            "const f = fn(n) { if (n < 2) { n } else { f(n - 1) + f(n - 2) } }; f(10)",
+            55.,
+        ),
+        (
+            "let sum = 0; for (x in [1, 2, 3]) { const y = x * 2; sum = sum + y }; sum",
+            12.,
+        ),
+        ("let i = 0; while (i < 3) { const j = i; i = j + 1 }; i", 3.),
+        (
+            r#"const [a, ...b] = [1, 2, 3]; const {c} = {"c": 4}; a + len(b) + c"#,
+            7.,
+        ),
+        ("const a = [1]; a[0] = 2; a[0]", 2.),
+        // a constant may be declared again once the block declaring it ends
+        ("if (true) { const x = 1; x } else { const x = 2; x }", 1.),
+        (
+            "match (2) { 1 => { const x = 1; x }, _ => { const x = 2; x } }",
+            2.,
+        ),
+        ("if (true) { const x = 1; } const x = 2; x", 2.),
+        // constants declared after a function don't restrict its bindings
+        (
+            "let f = fn(x) { let y = 3; x + y }; const x = 1; const y = 2; f(7)",
+            10.,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let error_tests = [
+        ("const x = 1; x = 2;", "cannot assign to constant x"),
+        ("const x = 1; let x = 2;", "cannot redefine constant x"),
+        ("const x = 1; const x = 2;", "cannot redefine constant x"),
+        (
+            "const [a, b] = [1, 2]; b = 3;",
+            "cannot assign to constant b",
+        ),
+        (
+            "const x = 1; let f = fn() { x = 2 }; f()",
+            "cannot assign to constant x",
+        ),
+        (
+            "const x = 1; let f = fn() { let x = 2 }; f()",
+            "cannot redefine constant x",
+        ),
+        (
+            "const x = 1; let f = fn(x) { x }; f(2)",
+            "cannot redefine constant x",
+        ),
+        (
+            "const x = 1; for (x in [1]) { x }",
+            "cannot redefine constant x",
+        ),
+        (
+            "for (x in [1, 2]) { const y = x; y = 3 }",
+            "cannot assign to constant y",
+        ),
+        (
+            "const e = 1; try { throw 1; } catch (e) { e }",
+            "cannot redefine constant e",
+        ),
+        (
+            "if (true) { const x = 1; } x = 2;",
+            "cannot assign to constant x",
+        ),
+        (
+            "if (true) { const x = 1; const x = 2; }",
+            "cannot redefine constant x",
+        ),
+    ];
+    for (input, expected) in error_tests {
+        match test_eval(input) {
+            Ok(obj) => panic!("No error object returned for '{}'. got={:?}", input, obj),
+            Err(err) => assert_eq!(err.msg, expected, "{}", input),
+        }
+    }
+
+    // The REPL evaluates each line in the environment of the earlier lines
+    let env = Rc::new(RefCell::new(Environment::default()));
+    let mut evaluator = Evaluator::new();
+    for (input, expected) in [
+        ("const x = 1;", None),
+        ("const x = 2;", Some("cannot redefine constant x")),
+    ] {
+        let program = Parser::new(Scanner::new(input)).parse_program();
+        match (evaluator.eval_program(&env, program), expected) {
+            (Ok(_), None) => {}
+            (Err(err), Some(expected)) => assert_eq!(err.msg, expected),
+            (result, _) => panic!("unexpected result for '{}': {:?}", input, result),
+        }
+    }
+    test_integer_object(env.borrow().get("x").unwrap(), 1);
+}
+
+#[test]
+fn test_deferred_expressions() {
+    let tests = vec![
+        ("fn() { defer 10; 5 }()", Object::Number(5.)),
+        ("fn() { return 5; defer len(1); }()", Object::Number(5.)),
+        (
+            "fn() { if (false) { defer len(1); } 5 }()",
+            Object::Number(5.),
+        ),
+        ("fn() { defer 10; }()", Object::Nil),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => match expected {
+                Object::Number(expected) => test_numeric_object(evaluated, expected),
+                _ => test_nil_object(evaluated),
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let error_tests = [
+        ("fn() { defer len(1); 5 }()", "len: unsupported argument"),
+        (
+            "fn() { defer len(1); return 5; }()",
+            "len: unsupported argument",
+        ),
+        (
+            "fn() { defer len(1); if (true) { return 5; } 10 }()",
+            "len: unsupported argument",
+        ),
+        // Deferred expressions are evaluated in reverse order and the
+        // error of the last one to fail is passed on
+        (
+            "fn() { defer len(1); defer first(1); 5 }()",
+            "len: unsupported argument",
+        ),
+        (r#"fn() { defer 1; throw "x"; }()"#, "x"),
+        ("defer 1;", "defer is only allowed inside a function"),
+    ];
+    for (i, (input, expected)) in error_tests.iter().enumerate() {
+        match test_eval(input) {
+            Ok(obj) => panic!("[{}] No error object returned. got={:?}", i, obj),
+            Err(err) => assert_eq!(&err.msg, expected, "[{}] wrong error message", i),
+        }
+    }
+
+    let input = "let n = 0; for (x in [1, 2]) { let f = fn() { defer n = n + x; }; f() }; n";
+    match test_eval(input) {
+        Ok(evaluated) => test_numeric_object(evaluated, 3.),
+        Err(e) => panic!("{}", e),
+    }
+
+    let tests = [
+        (
+            r#"let log = ""; let f = fn() { defer log = log + "a"; defer log = log + "b"; 5 }; f(); log"#,
+            "ba",
+        ),
+        // the deferred expressions of the functions left by an error are
+        // evaluated before it is caught
+        (
+            r#"let log = ""; let f = fn() { defer log = log + "d"; throw "x"; }; try { f(); } catch (e) { log = log + e; } log"#,
+            "dx",
+        ),
+        (
+            r#"let log = "";
+            let g = fn() { defer log = log + "g"; len(1); };
+            let f = fn() { defer log = log + "f"; g(); };
+            try { f(); } catch (e) { log = log + e.message; }
+            log"#,
+            "gflen: unsupported argument",
+        ),
+        // a failing deferred expression doesn't stop the earlier ones
+        (
+            r#"let log = ""; let f = fn() { defer log = log + "a"; defer len(1); 5 }; try { f(); } catch (e) { log = log + e.message; } log"#,
+            "alen: unsupported argument",
+        ),
+        // a defer in a loop registers its expression each time it is reached
+        (
+            r#"let log = "";
+            let f = fn() {
+                defer log = log + "a";
+                for (x in [1, 2, 3]) { if (x != 2) { defer log = log + "d"; } }
+                log = log + "r";
+            };
+            f(); f(); log"#,
+            "rddardda",
+        ),
+        (
+            r#"let log = ""; let f = fn() { let i = 0; while (i < 2) { defer log = log + "d"; i = i + 1; } throw "x"; }; try { f(); } catch (e) { log = log + e; } log"#,
+            "ddx",
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_string_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
 #[test]
 fn test_function_object() {
     let input = "fn(x) { x + 2; };";
@@ -688,15 +1277,14 @@ fn test_builtin_functions() {
         },
         BuiltinTest {
             input: "rest([1, 2, 3])",
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![Rc::new(Object::Number(2.)), Rc::new(Object::Number(3.))],
-            })),
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Number(2.)),
+                Rc::new(Object::Number(3.)),
+            ]))),
         },
         BuiltinTest {
             input: "let a = [1, 2, 3, 4]; rest(rest(rest(a)))",
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![Rc::new(Object::Number(4.))],
-            })),
+            expected: Object::Arr(Rc::new(Array::new(vec![Rc::new(Object::Number(4.))]))),
         },
         BuiltinTest {
             input: r#"puts("Hello")"#,
@@ -709,8 +1297,8 @@ fn test_builtin_functions() {
             Ok(evaluated) => match test.expected.clone() {
                 Object::Arr(arr_exp) => {
                     if let Object::Arr(arr) = &*evaluated.clone() {
-                        for (i, item) in arr_exp.elements.iter().enumerate() {
-                            if let Object::Number(exp) = *arr.elements[i] {
+                        for (i, item) in arr_exp.elements.borrow().iter().enumerate() {
+                            if let Object::Number(exp) = *arr.elements.borrow()[i] {
                                 test_numeric_object((*item).clone(), exp)
                             }
                         }
@@ -739,7 +1327,7 @@ fn test_builtin_functions() {
         input: &'static str,
         expected: RTError,
     }
-    let error_tests = vec![
+    let error_tests = [
         ErrorTest {
             input: "len(1)",
             expected: RTError::new("len: unsupported argument", 1),
@@ -780,9 +1368,9 @@ fn test_array_literals() {
     match evaluated {
         Ok(obj) => match &*obj {
             Object::Arr(arr) => {
-                test_numeric_object(arr.elements[0].clone(), 1.);
-                test_numeric_object(arr.elements[1].clone(), 4.);
-                test_numeric_object(arr.elements[2].clone(), 6.);
+                test_numeric_object(arr.elements.borrow()[0].clone(), 1.);
+                test_numeric_object(arr.elements.borrow()[1].clone(), 4.);
+                test_numeric_object(arr.elements.borrow()[2].clone(), 6.);
             }
             _ => panic!("object is not an array. got={:?}", *obj),
         },
@@ -827,7 +1415,7 @@ fn test_array_index_expressions() {
         },
         ArrayIndexExpr {
             input: "[1, 2, 3][-1]",
-            expected: Object::Nil,
+            expected: Object::Number(3.),
         },
     ];
 
@@ -878,6 +1466,23 @@ fn test_hash_key() {
         "numbers with different content have the same hash keys"
     );
 
+    // Integers and floats that are equal must have the same hash key
+    let hash_i1 = make_object_hash(Object::Integer(42));
+    let hash_i2 = make_object_hash(Object::Number(42.));
+    let hash_i3 = make_object_hash(Object::Number(-0.));
+    let hash_i4 = make_object_hash(Object::Integer(0));
+
+    assert_eq!(Object::Integer(42), Object::Number(42.));
+    assert_eq!(
+        hash_i1, hash_i2,
+        "equal integers and floats have different hash keys"
+    );
+    assert_eq!(
+        hash_i3, hash_i4,
+        "equal integers and floats have different hash keys"
+    );
+    assert_ne!(Object::Integer(42), Object::Number(42.5));
+
     let hash_b1 = make_object_hash(Object::Bool(true));
     let hash_b2 = make_object_hash(Object::Bool(true));
     let hash_b3 = make_object_hash(Object::Bool(false));
@@ -907,12 +1512,12 @@ fn test_hash_literals() {
     if let Ok(obj) = evaluated {
         match &*obj {
             Object::Map(map) => {
-                test_numeric_object(map.pairs[&Object::Str("one".into())].clone(), 1.);
-                test_numeric_object(map.pairs[&Object::Str("two".into())].clone(), 2.);
-                test_numeric_object(map.pairs[&Object::Str("three".into())].clone(), 3.);
-                test_numeric_object(map.pairs[&Object::Number(4.)].clone(), 4.);
-                test_numeric_object(map.pairs[&Object::Bool(true)].clone(), 5.);
-                test_numeric_object(map.pairs[&Object::Bool(false)].clone(), 6.);
+                test_numeric_object(map.pairs.borrow()[&Object::Str("one".into())].clone(), 1.);
+                test_numeric_object(map.pairs.borrow()[&Object::Str("two".into())].clone(), 2.);
+                test_numeric_object(map.pairs.borrow()[&Object::Str("three".into())].clone(), 3.);
+                test_numeric_object(map.pairs.borrow()[&Object::Number(4.)].clone(), 4.);
+                test_numeric_object(map.pairs.borrow()[&Object::Bool(true)].clone(), 5.);
+                test_numeric_object(map.pairs.borrow()[&Object::Bool(false)].clone(), 6.);
             }
             _ => panic!("object is not an hash literal. got={:?}", *obj),
         }
@@ -980,6 +1585,71 @@ fn test_hash_index_expressions() {
     }
 }
 
+#[test]
+fn test_index_assignments() {
+    let tests = [
+        ("let a = [1, 2, 3]; a[1] = 5; a[1]", 5.),
+        (r#"let m = {}; m["a"] = 1; m["a"]"#, 1.),
+        (r#"let m = {"x": 1}; m["x"] = m["x"] + 1"#, 2.),
+        ("let a = [1, 2]; let b = a; b[0] = 9; a[0]", 9.),
+        ("let a = [[1], [2]]; a[1][0] = 5; a[1][0]", 5.),
+        (
+            "let f = fn(arr) { arr[0] = 10 }; let a = [1]; f(a); a[0]",
+            10.,
+        ),
+        (
+            "let a = [1, 2]; let b = push(a, 3); b[0] = 7; a[0] + len(a)",
+            3.,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_numeric_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let error_tests = [
+        ("let a = [1]; a[1] = 2", "array index out of bounds: 1"),
+        ("let a = [1]; a[-2] = 2", "array index out of bounds: -2"),
+        (
+            r#"let a = [1]; a["x"] = 2"#,
+            "invalid index to array object",
+        ),
+        ("let a = [1]; a[1 / 2] = 2", "invalid index to array object"),
+        (
+            "let m = {}; m[[1]] = 2",
+            "hash key should be a numeric, a string or a boolean",
+        ),
+        (
+            r#"let s = "abc"; s[0] = "x""#,
+            "index assignment not supported",
+        ),
+    ];
+    for (i, (input, expected)) in error_tests.iter().enumerate() {
+        match test_eval(input) {
+            Ok(obj) => panic!("[{}] No error object returned. got={:?}", i, obj),
+            Err(err) => assert_eq!(&err.msg, expected, "[{}] wrong error message", i),
+        }
+    }
+
+    // An array or a map that holds itself is printed and compared once
+    let tests = [
+        ("let a = [1]; a[0] = a; str(a)", "[[...]]"),
+        (r#"let m = {"k": 1}; m["k"] = m; "${m}""#, r#"{"k": {...}}"#),
+        (
+            "let a = [1]; a[0] = a; let b = [1]; b[0] = b; str([a == a, a == b, a == [1]])",
+            "[true, true, false]",
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_string_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
 #[test]
 fn test_recursive_fibonacci() {
     let input = r#"
@@ -1009,3 +1679,568 @@ fn test_recursive_fibonacci() {
         Err(e) => panic!("{}", e),
     }
 }
+
+#[test]
+fn test_help_builtin() {
+    let tests = [
+        (
+            "/// Add two numbers\n/// and return the sum\nlet add = fn(a, b) { a + b }; help(add)",
+            Some("Add two numbers\nand return the sum"),
+        ),
+        (
+            "let f = fn(x) { /// Add x\n let g = fn(y) { x + y }; g }; help(f(1))",
+            Some("Add x"),
+        ),
+        ("/* not documented */ let f = fn() { 1 }; help(f)", None),
+        ("help(len)", None),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => match expected {
+                Some(doc) => test_string_object(evaluated, doc),
+                None => test_nil_object(evaluated),
+            },
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_destructure_statement() {
+    let tests = [
+        ("let [a, b] = [1, 2]; a * 10 + b", 12),
+        ("let [a, ...rest] = [1, 2, 3]; len(rest) * 10 + rest[1]", 23),
+        (r#"let {x, y} = {"y": 2, "x": 1, "z": 3}; x * 10 + y"#, 12),
+        ("let a = 1; let b = 2; let [a, b] = [b, a]; a - b", 1),
+        (
+            "let f = fn(pair) { let [x, y] = pair; x * y }; f([6, 7])",
+            42,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        (
+            "let x = 1;\nlet [a, b] = [1, 2, 3];",
+            RTError::new("array pattern expects 2 element(s), got 3", 2),
+        ),
+        (
+            "let [a, ...b] = [];",
+            RTError::new("array pattern expects at least 1 element(s), got 0", 1),
+        ),
+        (
+            "let [a] = 1;",
+            RTError::new("array pattern requires an array", 1),
+        ),
+        (
+            r#"let {a, b} = {"a": 1};"#,
+            RTError::new("map pattern key 'b' not found", 1),
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("no error for '{}'. got={}", input, evaluated),
+            Err(err) => {
+                assert_eq!(err.msg, expected.msg, "{}", input);
+                assert_eq!(err.line, expected.line, "{}", input);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_default_and_rest_parameters() {
+    let tests = [
+        ("let f = fn(a, b = 10) { a + b }; f(1)", 11),
+        ("let f = fn(a, b = 10) { a + b }; f(1, 2)", 3),
+        ("let n = 1; let f = fn(a, b = a + n) { b }; n = 5; f(1)", 6),
+        (
+            "let f = fn(a = [], ...rest) { len(a) * 10 + len(rest) }; f() + f([1, 2], 3, 4)",
+            22,
+        ),
+        ("let f = fn(...rest) { rest[1] }; f(1, 2)", 2),
+        (
+            "let f = fn(x) { fn(y = x, ...z) { y + len(z) } }; f(5)() + f(5)(1, 0, 0)",
+            8,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        (
+            "fn(a, b = 2) { a }(1, 2, 3)",
+            "wrong number of arguments: want=1..2, got=3",
+        ),
+        (
+            "fn(a, b, ...c) { a }(1)",
+            "wrong number of arguments: want=2.., got=1",
+        ),
+        ("fn(a) { a }()", "wrong number of arguments: want=1, got=0"),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("no error for '{}'. got={}", input, evaluated),
+            Err(err) => assert_eq!(err.msg, expected, "{}", input),
+        }
+    }
+}
+
+#[test]
+fn test_spread_expressions() {
+    let tests = [
+        (
+            "let a = [1, 2]; let b = [5]; let c = [...a, 3, 4, ...b, 6]; c[4] * 10 + len(c)",
+            56,
+        ),
+        ("len([...[], ...[]])", 0),
+        (
+            "let f = fn(a, b, c) { a * 100 + b * 10 + c }; f(...[1, 2], 3)",
+            123,
+        ),
+        (
+            "let f = fn(a, ...rest) { len(rest) }; f(0, ...[1, 2, 3])",
+            3,
+        ),
+        (r#"len(...["four"])"#, 4),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    match test_eval("[1, ...2]") {
+        Ok(evaluated) => panic!("no error for spreading a number. got={}", evaluated),
+        Err(err) => assert_eq!(err.msg, "spread operand must be an array"),
+    }
+}
+
+#[test]
+fn test_slice_expressions() {
+    let tests = [
+        ("len([1, 2, 3, 4, 5][1:3])", 2),
+        ("[1, 2, 3, 4, 5][-2:][0]", 4),
+        ("[1, 2, 3, 4, 5][::-1][0]", 5),
+        ("[1, 2, 3, 4, 5][3:0:-2][1]", 2),
+        ("len([1, 2, 3][-10:10])", 3),
+        ("len([1, 2, 3][2:1])", 0),
+        ("let a = [1, 2]; let b = a[:]; b[0] = 3; a[0]", 1),
+        ("let a = [1, 2, 3]; a[-1] = 4; a[2]", 4),
+        ("len([1, 2, 3][::9223372036854775807])", 1),
+        (r#"len("héllo")"#, 5),
+        ("[1, 2][1.0]", 2),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        (r#""héllo"[1:3]"#, "él"),
+        (r#""héllo"[::-1]"#, "olléh"),
+        (r#""héllo"[1]"#, "é"),
+        (r#""héllo"[-1]"#, "o"),
+        (r#""abc"[::-9223372036854775807]"#, "c"),
+        (r#"let s = "héllo"; s[len(s) - 1]"#, "o"),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_string_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    let tests = [
+        ("[1, 2][::0]", "slice step cannot be zero"),
+        ("[1, 2][0.5:]", "slice indices must be integers"),
+        ("[1, 2][0.5]", "invalid index to array object"),
+        (r#""héllo"[1.7]"#, "invalid index to string object"),
+        ("{1: 2}[1:]", "slice operator not supported"),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("no error for '{}'. got={}", input, evaluated),
+            Err(err) => assert_eq!(err.msg, expected, "{}", input),
+        }
+    }
+}
+
+#[test]
+fn test_property_and_method_calls() {
+    let tests = [
+        (r#"let config = {"port": 80}; config.port"#, 80),
+        (r#"let m = {"a": {"b": [1, 2]}}; m.a.b[1]"#, 2),
+        (r#"let m = {}; m.x = 5; m["x"]"#, 5),
+        (r#""héllo".split("").len()"#, 5),
+        ("[1, 2].push(3).len()", 3),
+        (r#"let m = {"double": fn(x) { x * 2 }}; m.double(4)"#, 8),
+        (r#"let m = {"len": fn() { 42 }}; m.len()"#, 42),
+        (r#"let args = [","]; "x,y".split(...args).len()"#, 2),
+        (
+            "let map = fn(arr, f) { let out = []; for (x in arr) { out = out.push(f(x)) } out };
+             [1, 2, 3].map(fn(x) { x * 2 })[2]",
+            6,
+        ),
+        (
+            "let f = fn() { let sum = fn(arr, n) { arr.len() + n }; fn() { [1, 2].sum(...[10]) } }; f()()",
+            12,
+        ),
+        ("let len = fn(arr) { 42 }; [1].len()", 42),
+        ("let len = 5; [1, 2].len()", 2),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    match test_eval(r#""a,b,c".split(",")[2]"#) {
+        Ok(evaluated) => test_string_object(evaluated, "c"),
+        Err(e) => panic!("{}", e),
+    }
+    match test_eval(r#"{"a": 1}.b"#) {
+        Ok(evaluated) => test_nil_object(evaluated),
+        Err(e) => panic!("{}", e),
+    }
+
+    let tests = [
+        ("[1].foo", "property access not supported"),
+        ("[1].foo()", "undefined method 'foo'"),
+        (r#"let m = {"f": 1}; m.f()"#, "calling non-function"),
+        (r#""a".split(1)"#, "split: unsupported argument"),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("no error for '{}'. got={}", input, evaluated),
+            Err(err) => assert_eq!(err.msg, expected, "{}", input),
+        }
+    }
+}
+
+#[test]
+fn test_nil_operators() {
+    let tests = [
+        ("nil ?? 5", 5),
+        ("nil ?? nil ?? 3", 3),
+        (r#"let cfg = {"db": {"port": 80}}; cfg?.db?.port"#, 80),
+        (r#"let cfg = {}; cfg.db?.port ?? 8080"#, 8080),
+        (r#"let cfg = {"ports": [1, 2]}; cfg.ports?.[-1]"#, 2),
+        (
+            "let n = 0; let f = fn() { n = n + 1 }; 1 ?? f(); nil?.[f()]; nil?.x(f()); n",
+            0,
+        ),
+        // a '?.' that finds nil skips the rest of the chain
+        (
+            "let n = 0; let f = fn() { n = n + 1 }; let a = nil; a?.b.c(f())[f()]; n",
+            0,
+        ),
+        ("let a = nil; len([a?.b.c, 1]) + (a?.b.c ?? 5)", 7),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    for input in ["nil", "let a = nil; a?.[0:1]", "nil?.a", "nil?.a.b"] {
+        match test_eval(input) {
+            Ok(evaluated) => test_nil_object(evaluated),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    for (input, expected) in [("false ?? 5", false), ("let a = nil; a == nil", true)] {
+        match test_eval(input) {
+            Ok(evaluated) => test_boolean_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    // only the object of the '?.' is tested for nil
+    for input in ["nil.a?.b", r#"{"a": nil}?.a.b"#] {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("no error for '{}'. got={}", input, evaluated),
+            Err(err) => assert_eq!(err.msg, "property access not supported"),
+        }
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let tests = [
+        ("[1, 2, 3] |> len", 3),
+        ("let sub = fn(a, b) { a - b }; 10 |> sub(3)", 7),
+        (
+            "let double = fn(x) { x * 2 }; let add = fn(a, b) { a + b }; 5 |> double |> add(1)",
+            11,
+        ),
+        (r#""a,b,c" |> split(",") |> len()"#, 3),
+        ("3 |> fn(x) { x * x }", 9),
+        (r#"let m = {"sub": fn(a, b) { a - b }}; 10 |> m.sub(4)"#, 6),
+        (
+            "let f = fn(a, ...rest) { a + len(rest) }; 1 + 1 |> f(...[0, 0])",
+            4,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test]
+fn test_match_expression() {
+    let input = r#"
+    let f = fn(x) {
+        match (x) {
+            0 => "zero",
+            -1 => "minus one",
+            "s" => "string",
+            nil => "nil",
+            [] => "empty",
+            [a, [b, c], ...rest] if a > 0 => "nested " + str(a + b + c + len(rest)),
+            [_, _] => "pair",
+            {"k": 1, name} => "name " + name,
+            {"k": k} => "k " + str(k),
+            n if n == 10 => { let m = n * 2; "ten " + str(m) },
+            _ => "other"
+        }
+    };
+    let args = [0, -1, "s", nil, [], [1, [2, 3], 4, 5], [0, [2, 3]], {"k": 1, "name": "bob"}, {"k": 2}, 10, 5];
+    let result = "";
+    for (arg in args) {
+        result = result + f(arg) + ";";
+    }
+    result
+    "#;
+    match test_eval(input) {
+        Ok(evaluated) => test_string_object(
+            evaluated,
+            "zero;minus one;string;nil;empty;nested 8;pair;name bob;k 2;ten 20;other;",
+        ),
+        Err(e) => panic!("{}", e),
+    }
+    let tests = [
+        ("let x = match ([1, 2]) { [a, b] => a + b }; x", 3),
+        (
+            "let f = fn(p) { match (p) { [x, y] if x == y => x, [x, _] => -x } }; f([2, 2]) + f([3, 1])",
+            -1,
+        ),
+        // only false and nil fail a guard
+        ("match (5) { n if 0 => 1, _ => 2 }", 1),
+        ("match (5) { n if nil => 1, _ => 2 }", 2),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    match test_eval("let x = 3;\nmatch (x) { 1 => 1, [a] => a }") {
+        Ok(evaluated) => panic!("expected an error. got={}", evaluated),
+        Err(err) => {
+            assert_eq!(err.msg, "no match arm for 3");
+            assert_eq!(err.line, 2);
+        }
+    }
+}
+
+#[test]
+fn test_try_and_throw() {
+    let tests = [
+        ("let x = 0; try { throw 5; } catch (e) { x = e; } x", 5),
+        ("let m = 0;\ntry {\n  len(1);\n} catch (e) { m = e.line; }\nm", 3),
+        (
+            r#"let f = fn(n) { if (n == 0) { throw 1; } f(n - 1) }; let m = 0; try { f(20); } catch (e) { m = e; } m"#,
+            1,
+        ),
+        (
+            "let s = 0; for (i in [1, 2, 3]) { try { if (i == 2) { throw i; } s = s + i; } catch (e) { s = s + 10 * e; } } s",
+            24,
+        ),
+        ("let f = fn() { try { throw 1; } finally { return 2; } }; f()", 2),
+        (
+            "let l = 0;\ntry {\n  try { 1 / 0; }\n  catch (e) { throw e; }\n} catch (e) { l = e.line; }\nl",
+            3,
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_integer_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    let tests = [
+        (
+            r#"let m = ""; try { len(1); } catch (e) { m = e.message; } m"#,
+            "len: unsupported argument",
+        ),
+        (
+            r#"let m = ""; try { 1(); } catch (e) { m = e.message; } m"#,
+            "calling non-function",
+        ),
+        (
+            r#"let log = ""; let f = fn() { try { log = log + "t"; return "r"; } finally { log = log + "f"; } }; f() + log"#,
+            "rtf",
+        ),
+        (
+            r#"let log = ""; try { try { throw "x"; } finally { log = log + "f"; } } catch (e) { log = log + e; } log"#,
+            "fx",
+        ),
+        (
+            r#"let log = ""; try { try { throw "a"; } catch (e) { throw e + "b"; } finally { log = log + "f"; } } catch (e) { log = log + e; } log"#,
+            "fab",
+        ),
+        (
+            r#"let log = ""; let f = fn() { try { throw 1; } catch (e) { return e + 1; } finally { log = "f"; } }; str(f()) + log"#,
+            "2f",
+        ),
+        (
+            r#"let f = fn() { try { return 1; } catch (e) { return 3; } finally { throw "f"; } }; let m = ""; try { f(); } catch (e) { m = e; } m"#,
+            "f",
+        ),
+    ];
+    for (input, expected) in tests {
+        match test_eval(input) {
+            Ok(evaluated) => test_string_object(evaluated, expected),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    for (input, expected, line) in [
+        (r#"throw "oops";"#, "oops", 1),
+        (
+            "try { 1 / 0; } catch (e) {\n throw e; }",
+            "division by zero",
+            1,
+        ),
+        ("let x = 1;\ntry { throw 1; } finally { 2 }", "1", 2),
+    ] {
+        match test_eval(input) {
+            Ok(evaluated) => panic!("expected an error. got={}", evaluated),
+            Err(err) => {
+                assert_eq!(err.msg, expected);
+                assert_eq!(err.line, line);
+            }
+        }
+    }
+}
+
+// Write the files of a program to a directory of its own
+#[cfg(test)]
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("monkey-eval-{}-{}", name, process::id()));
+    for (file, source) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    dir.canonicalize().unwrap()
+}
+
+#[cfg(test)]
+fn eval_file(file: &Path) -> Result<Rc<Object>, RTError> {
+    let source = fs::read_to_string(file).unwrap();
+    let scanner = Scanner::new(&source);
+    let mut parser = Parser::new(scanner);
+    let program = parser.parse_program();
+    check_parse_errors(&parser);
+    let environment = Rc::new(RefCell::new(Environment::default()));
+    let mut evaluator = Evaluator::new();
+    evaluator.set_file(file.to_path_buf());
+    evaluator.eval_program(&environment, program)
+}
+
+#[test]
+fn test_import() {
+    let dir = write_files(
+        "import",
+        &[
+            (
+                "main.mky",
+                r#"import "lib/math.mky" as math;
+                import "lib/util.mky" as util;
+                let f = fn() { import "lib/math.mky" as m; m.counter() };
+                [math.square(4), math.pi, math._hidden, util.twice(3), math.counter(), util.count(), f(), math.n]"#,
+            ),
+            (
+                "lib/math.mky",
+                "let pi = 3; let _hidden = 1; let n = 0;
+                let square = fn(x) { x * x };
+                let counter = fn() { n = n + 1; n };",
+            ),
+            (
+                "lib/util.mky",
+                r#"import "math.mky" as math;
+                let twice = fn(x) { math.square(x) * 2 };
+                let count = fn() { math.counter() };"#,
+            ),
+        ],
+    );
+    let evaluated = eval_file(&dir.join("main.mky")).unwrap();
+    let Object::Arr(arr) = evaluated.as_ref() else {
+        panic!("object is not an array. got={}", evaluated);
+    };
+    let elements = arr.elements.borrow();
+    // the names starting with an underscore are not exported
+    test_nil_object(elements[2].clone());
+    for (i, expected) in [(0, 16), (1, 3), (3, 18), (4, 1), (5, 2), (6, 3), (7, 0)] {
+        test_integer_object(elements[i].clone(), expected);
+    }
+}
+
+#[test]
+fn test_import_errors() {
+    let dir = write_files(
+        "import-errors",
+        &[
+            ("main.mky", "let x = 1;\nimport \"a.mky\" as a;"),
+            ("a.mky", "import \"b.mky\" as b;"),
+            ("b.mky", "\nimport \"main.mky\" as m;"),
+            ("missing.mky", "import \"nope.mky\" as n;"),
+            ("defer.mky", "defer 1;"),
+            ("import-defer.mky", "import \"defer.mky\" as d;"),
+            (
+                "catch.mky",
+                "let m = \"\"; try { import \"nope.mky\" as n; } catch (e) { m = e.message; } m",
+            ),
+        ],
+    );
+    let tests = [
+        (
+            "main.mky",
+            "import cycle: main.mky -> a.mky -> b.mky -> main.mky",
+            2,
+        ),
+        ("missing.mky", "module 'nope.mky' not found", 1),
+        (
+            "import-defer.mky",
+            "defer is only allowed inside a function",
+            1,
+        ),
+    ];
+    for (file, expected, line) in tests {
+        match eval_file(&dir.join(file)) {
+            Ok(evaluated) => panic!("expected an error for {}. got={}", file, evaluated),
+            Err(err) => {
+                assert_eq!(err.msg, expected);
+                assert_eq!(err.line, line);
+            }
+        }
+    }
+    match eval_file(&dir.join("catch.mky")) {
+        Ok(evaluated) => test_string_object(evaluated, "module 'nope.mky' not found"),
+        Err(e) => panic!("{}", e),
+    }
+}
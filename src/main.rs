@@ -4,6 +4,7 @@ use std::env;
 use std::fs;
 use std::io;
 use std::io::{BufRead, Write};
+use std::path::PathBuf;
 use std::process;
 use std::rc::Rc;
 
@@ -98,11 +99,16 @@ pub fn run_prompt() {
                         }
                     }
                 } else {
-                    let mut compiler = Compiler::new_with_state(symtab, constants);
+                    // A line that does not compile, such as one redefining a
+                    // constant, is rejected and the state of the earlier
+                    // lines is kept
+                    let mut compiler = Compiler::new_with_state(symtab.clone(), constants.clone());
 
                     if let Err(e) = compiler.compile(program) {
                         eprintln!("Compilation error: {}", e);
-                        return;
+                        print!(">> ");
+                        io::stdout().flush().unwrap();
+                        continue;
                     }
                     let bytecode = compiler.bytecode();
                     let mut vm = VM::new_with_global_store(bytecode, globals);
@@ -133,8 +139,11 @@ pub fn run_file(path: &str) {
         return;
     }
     let buf = buf.unwrap();
+    // Imports are relative to the directory of the file
+    let file = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
     let environment = Rc::new(RefCell::new(Environment::default()));
     let mut evaluator = Evaluator::new();
+    evaluator.set_file(file.clone());
     let constants = vec![];
     let mut symtab = SymbolTable::default();
     for (i, sym) in BUILTINS.iter().enumerate() {
@@ -163,6 +172,7 @@ pub fn run_file(path: &str) {
             }
         } else {
             let mut compiler = Compiler::new_with_state(symtab, constants);
+            compiler.set_file(file);
 
             if let Err(e) = compiler.compile(program) {
                 eprintln!("Compilation error: {}", e);
@@ -1,12 +1,16 @@
 use super::stmt::*;
+use crate::common::bigint::BigInt;
 use crate::scanner::token::*;
 use std::fmt;
 
 #[derive(Clone, Debug)]
 pub enum Expression {
     Ident(Identifier),
+    Integer(IntegerLiteral),
+    BigInt(BigIntLiteral),
     Number(NumberLiteral),
     Str(StringLiteral),
+    Interpolated(InterpolatedStr),
     Unary(UnaryExpr),
     Binary(BinaryExpr),
     Bool(BooleanExpr),
@@ -16,6 +20,14 @@ pub enum Expression {
     Array(ArrayLiteral),
     Hash(HashLiteral),
     Index(IndexExpr),
+    Slice(SliceExpr),
+    Property(PropertyExpr),
+    MethodCall(MethodCallExpr),
+    Match(MatchExpr),
+    While(WhileExpr),
+    For(ForExpr),
+    Assign(AssignExpr),
+    Spread(SpreadExpr),
     Nil,
 }
 
@@ -43,6 +55,50 @@ impl fmt::Display for StringLiteral {
     }
 }
 
+// A string with embedded expressions such as "Hello ${name}!". The parts
+// are string literals and the expressions in the order of their appearance.
+#[derive(Clone, Debug)]
+pub struct InterpolatedStr {
+    pub token: Token, // the first part of the string
+    pub parts: Vec<Expression>,
+}
+
+impl fmt::Display for InterpolatedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Expression::Str(s) => write!(f, "{}", s)?,
+                _ => write!(f, "${{{}}}", part)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IntegerLiteral {
+    pub token: Token,
+    pub value: i64,
+}
+
+impl fmt::Display for IntegerLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BigIntLiteral {
+    pub token: Token,
+    pub value: BigInt,
+}
+
+impl fmt::Display for BigIntLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct NumberLiteral {
     pub token: Token,
@@ -112,26 +168,120 @@ impl fmt::Display for IfExpr {
     }
 }
 
+// Match expression looks like
+// 'match (<value>) { <pattern> if <guard> => <body>, ... }'
+// The arms are tried in order and the body of the first arm whose pattern
+// matches and whose guard is truthy is the result.
+#[derive(Clone, Debug)]
+pub struct MatchExpr {
+    pub token: Token, // match token
+    pub subject: Box<Expression>,
+    pub arms: Vec<MatchArm>,
+}
+
+impl fmt::Display for MatchExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arms: Vec<String> = self.arms.iter().map(|a| a.to_string()).collect();
+        write!(f, "match ({}) {{ {} }}", self.subject, arms.join(", "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchArm {
+    pub pattern: MatchPattern,
+    pub guard: Option<Expression>,
+    pub body: BlockStatement, // an expression body is a block of one statement
+}
+
+impl fmt::Display for MatchArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, " => {{ {} }}", self.body)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum MatchPattern {
+    // '_' matches anything
+    Wildcard,
+    // A name matches anything and binds the value to it
+    Binding(Identifier),
+    // A number, a string, a boolean or nil matches an equal value
+    Literal(Expression),
+    // '[a, b, ...rest]' matches an array with exactly as many elements as
+    // patterns, or at least as many if there is a rest pattern
+    Array(Vec<MatchPattern>, Option<Box<MatchPattern>>),
+    // '{"key": pattern, name}' matches a map that has all the keys, where
+    // 'name' is short for '"name": name'. Other keys are ignored.
+    Map(Vec<(String, MatchPattern)>),
+}
+
+impl fmt::Display for MatchPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchPattern::Wildcard => write!(f, "_"),
+            MatchPattern::Binding(name) => write!(f, "{}", name),
+            MatchPattern::Literal(value) => write!(f, "{}", value),
+            MatchPattern::Array(elements, rest) => {
+                let mut elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                if let Some(rest) = rest {
+                    elements.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", elements.join(", "))
+            }
+            MatchPattern::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, pattern)| format!("{}: {}", key, pattern))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FunctionLiteral {
     pub name: String, // name of the function
     pub token: Token,
     pub params: Vec<Identifier>,
+    pub defaults: Vec<Expression>, // default values of the last parameters
+    pub rest: Option<Identifier>,  // collects the remaining arguments
     pub body: BlockStatement,
+    pub doc: Option<String>, // doc comment of the 'let' defining the function
 }
 
 impl fmt::Display for FunctionLiteral {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let params_str = self
-            .params
-            .iter()
-            .map(|p| format!("{}, ", p))
-            .collect::<String>();
-        let params_str = params_str.trim_end_matches(|c| c == ' ' || c == ',');
+        let params_str = format_params(&self.params, &self.defaults, &self.rest);
         write!(f, "{} ({}) {}", self.token, params_str, self.body)
     }
 }
 
+// Format function parameters as 'a, b = 10, ...rest'
+pub fn format_params(
+    params: &[Identifier],
+    defaults: &[Expression],
+    rest: &Option<Identifier>,
+) -> String {
+    let first_default = params.len() - defaults.len();
+    let mut params: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| match i.checked_sub(first_default) {
+            Some(d) => format!("{} = {}", p, defaults[d]),
+            None => p.to_string(),
+        })
+        .collect();
+    if let Some(rest) = rest {
+        params.push(format!("...{}", rest));
+    }
+    params.join(", ")
+}
+
 #[derive(Clone, Debug)]
 pub struct CallExpr {
     pub token: Token,          // The '(' Token
@@ -146,7 +296,7 @@ impl fmt::Display for CallExpr {
             .iter()
             .map(|p| format!("{}, ", p))
             .collect::<String>();
-        let args_str = args_str.trim_end_matches(|c| c == ' ' || c == ',');
+        let args_str = args_str.trim_end_matches([' ', ',']);
         write!(f, "{}({})", self.func, args_str)
     }
 }
@@ -164,7 +314,7 @@ impl fmt::Display for ArrayLiteral {
             .iter()
             .map(|p| format!("{}, ", p))
             .collect::<String>();
-        let elements_str = elements_str.trim_end_matches(|c| c == ' ' || c == ',');
+        let elements_str = elements_str.trim_end_matches([' ', ',']);
         write!(f, "[{}]", elements_str)
     }
 }
@@ -185,22 +335,168 @@ impl fmt::Display for HashLiteral {
             .iter()
             .map(|p| format!("{}: {}, ", p.0, p.1))
             .collect::<String>();
-        let pairs_str = pairs_str.trim_end_matches(|c| c == ' ' || c == ',');
+        let pairs_str = pairs_str.trim_end_matches([' ', ',']);
         write!(f, "{{{}}}", pairs_str)
     }
 }
 
-// Index expression looks like '<expr>[<expr>]'
+// Index expression looks like '<expr>[<expr>]'. The optional index
+// '<expr>?.[<expr>]' is nil without evaluating the index if <expr> is nil.
 #[derive(Clone, Debug)]
 pub struct IndexExpr {
     pub token: Token, // [
     pub left: Box<Expression>,
     pub index: Box<Expression>,
+    pub optional: bool,
 }
 
 impl fmt::Display for IndexExpr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}[{}])", self.left, self.index)
+        let optional = if self.optional { "?." } else { "" };
+        write!(f, "({}{}[{}])", self.left, optional, self.index)
+    }
+}
+
+// Slice expression looks like '<left>[<start>:<end>:<step>]' where each
+// of the bounds may be omitted
+#[derive(Clone, Debug)]
+pub struct SliceExpr {
+    pub token: Token, // [
+    pub left: Box<Expression>,
+    pub start: Option<Box<Expression>>,
+    pub end: Option<Box<Expression>>,
+    pub step: Option<Box<Expression>>,
+    pub optional: bool,
+}
+
+impl fmt::Display for SliceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |b: &Option<Box<Expression>>| b.as_ref().map(|b| b.to_string());
+        write!(
+            f,
+            "({}{}[{}:{}",
+            self.left,
+            if self.optional { "?." } else { "" },
+            bound(&self.start).unwrap_or_default(),
+            bound(&self.end).unwrap_or_default()
+        )?;
+        if let Some(step) = bound(&self.step) {
+            write!(f, ":{}", step)?;
+        }
+        write!(f, "])")
+    }
+}
+
+// While expression looks like 'while (<condition>) { <body> }'
+#[derive(Clone, Debug)]
+pub struct WhileExpr {
+    pub token: Token, // while token
+    pub condition: Box<Expression>,
+    pub body: BlockStatement,
+}
+
+impl fmt::Display for WhileExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "while ({}) {{ {} }}", self.condition, self.body)
+    }
+}
+
+// For expression looks like 'for (<ident> in <expr>) { <body> }' or
+// 'for (<ident>, <ident> in <expr>) { <body> }'
+#[derive(Clone, Debug)]
+pub struct ForExpr {
+    pub token: Token, // for token
+    pub idents: Vec<Identifier>,
+    pub iterable: Box<Expression>,
+    pub body: BlockStatement,
+}
+
+impl fmt::Display for ForExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let idents_str = self
+            .idents
+            .iter()
+            .map(|p| format!("{}, ", p))
+            .collect::<String>();
+        let idents_str = idents_str.trim_end_matches([' ', ',']);
+        write!(
+            f,
+            "for ({} in {}) {{ {} }}",
+            idents_str, self.iterable, self.body
+        )
+    }
+}
+
+// Assignment expression looks like '<target> = <value>'. The target is an
+// existing binding and the value of the expression is the assigned value.
+#[derive(Clone, Debug)]
+pub struct AssignExpr {
+    pub token: Token, // '=' token
+    pub target: Box<Expression>,
+    pub value: Box<Expression>,
+}
+
+impl fmt::Display for AssignExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} = {})", self.target, self.value)
+    }
+}
+
+// Property access '<left>.<name>' which is sugar for '<left>["<name>"]'.
+// The optional access '<left>?.<name>' is nil if <left> is nil.
+#[derive(Clone, Debug)]
+pub struct PropertyExpr {
+    pub token: Token, // '.' or '?.' token
+    pub left: Box<Expression>,
+    pub name: Identifier,
+    pub optional: bool,
+}
+
+impl fmt::Display for PropertyExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}{}{})", self.left, self.token.literal, self.name)
+    }
+}
+
+// Method call '<receiver>.<name>(<args>)'. It calls the function stored
+// under 'name' if the receiver is a map that has one. Otherwise it calls
+// the builtin 'name' with the receiver as the first argument. The optional
+// call '<receiver>?.<name>(<args>)' is nil without evaluating the arguments
+// if the receiver is nil.
+#[derive(Clone, Debug)]
+pub struct MethodCallExpr {
+    pub token: Token, // '.' or '?.' token
+    pub receiver: Box<Expression>,
+    pub name: Identifier,
+    pub args: Vec<Expression>,
+    pub optional: bool,
+}
+
+impl fmt::Display for MethodCallExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self.args.iter().map(|a| a.to_string()).collect();
+        write!(
+            f,
+            "{}{}{}({})",
+            self.receiver,
+            self.token.literal,
+            self.name,
+            args.join(", ")
+        )
+    }
+}
+
+// Spread expression '...<expr>' that expands an array into the elements of
+// an array literal or into the arguments of a call
+#[derive(Clone, Debug)]
+pub struct SpreadExpr {
+    pub token: Token, // '...' token
+    pub value: Box<Expression>,
+}
+
+impl fmt::Display for SpreadExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "...{}", self.value)
     }
 }
 
@@ -209,8 +505,11 @@ impl Expression {
     fn token_literal(&self) -> String {
         match &self {
             Expression::Ident(ident) => ident.token.literal.clone(),
+            Expression::Integer(num) => num.token.literal.clone(),
+            Expression::BigInt(num) => num.token.literal.clone(),
             Expression::Number(num) => num.token.literal.clone(),
             Expression::Str(s) => s.token.literal.clone(),
+            Expression::Interpolated(s) => s.token.literal.clone(),
             Expression::Unary(unary) => unary.token.literal.clone(),
             Expression::Binary(binary) => binary.token.literal.clone(),
             Expression::Bool(b) => b.token.literal.clone(),
@@ -220,6 +519,14 @@ impl Expression {
             Expression::Array(s) => s.token.literal.clone(),
             Expression::Hash(h) => h.token.literal.clone(),
             Expression::Index(idx) => idx.token.literal.clone(),
+            Expression::Slice(s) => s.token.literal.clone(),
+            Expression::Property(p) => p.token.literal.clone(),
+            Expression::MethodCall(m) => m.token.literal.clone(),
+            Expression::Match(m) => m.token.literal.clone(),
+            Expression::While(w) => w.token.literal.clone(),
+            Expression::For(fr) => fr.token.literal.clone(),
+            Expression::Assign(a) => a.token.literal.clone(),
+            Expression::Spread(s) => s.token.literal.clone(),
             Expression::Nil => "nil".to_string(),
         }
     }
@@ -229,8 +536,11 @@ impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
             Expression::Ident(ident) => write!(f, "{}", ident),
+            Expression::Integer(num) => write!(f, "{}", num),
+            Expression::BigInt(num) => write!(f, "{}", num),
             Expression::Number(num) => write!(f, "{}", num),
             Expression::Str(s) => write!(f, "{}", s),
+            Expression::Interpolated(s) => write!(f, "{}", s),
             Expression::Unary(prefix) => write!(f, "{}", prefix),
             Expression::Binary(binary) => write!(f, "{}", binary),
             Expression::Bool(b) => write!(f, "{}", b),
@@ -240,6 +550,14 @@ impl fmt::Display for Expression {
             Expression::Array(s) => write!(f, "{}", s),
             Expression::Hash(h) => write!(f, "{}", h),
             Expression::Index(idx) => write!(f, "{}", idx),
+            Expression::Slice(s) => write!(f, "{}", s),
+            Expression::Property(p) => write!(f, "{}", p),
+            Expression::MethodCall(m) => write!(f, "{}", m),
+            Expression::Match(m) => write!(f, "{}", m),
+            Expression::While(w) => write!(f, "{}", w),
+            Expression::For(fr) => write!(f, "{}", fr),
+            Expression::Assign(a) => write!(f, "{}", a),
+            Expression::Spread(s) => write!(f, "{}", s),
             Expression::Nil => write!(f, "nil"),
         }
     }
@@ -6,16 +6,71 @@ use crate::scanner::token::*;
 #[derive(Debug, Clone)]
 pub enum Statement {
     Let(LetStmt),
+    Destructure(DestructureStmt),
     Return(ReturnStmt),
     Expr(ExpressionStmt),
+    Defer(DeferStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
+    Import(ImportStmt),
     Nil,
 }
 
 #[derive(Debug, Clone)]
 pub struct LetStmt {
-    pub token: Token,
+    pub token: Token, // the 'let' or 'const' token
     pub name: Identifier,
     pub value: Expression,
+    pub doc: Option<String>, // text of the '///' comments before the statement
+}
+
+impl LetStmt {
+    // A 'const' binding cannot be assigned, shadowed or redefined
+    pub fn is_const(&self) -> bool {
+        self.token.ttype == TokenType::Const
+    }
+}
+
+// Destructuring let statement that binds the elements of an array as in
+// 'let [a, b, ...rest] = arr;' or the values of a map with string keys as in
+// 'let {name, age} = person;'
+#[derive(Debug, Clone)]
+pub struct DestructureStmt {
+    pub token: Token, // the 'let' or 'const' token
+    pub pattern: Pattern,
+    pub value: Expression,
+}
+
+impl DestructureStmt {
+    pub fn is_const(&self) -> bool {
+        self.token.ttype == TokenType::Const
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // names of the elements and the optional name of the remaining elements
+    Array(Vec<Identifier>, Option<Identifier>),
+    // names that are also the keys of the map
+    Map(Vec<Identifier>),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Array(names, rest) => {
+                let mut names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+                if let Some(rest) = rest {
+                    names.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", names.join(", "))
+            }
+            Pattern::Map(names) => {
+                let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+                write!(f, "{{{}}}", names.join(", "))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +79,56 @@ pub struct ReturnStmt {
     pub value: Expression,
 }
 
+// Defer statement looks like 'defer <expr>;'. The expression is evaluated
+// when the enclosing function returns.
+#[derive(Debug, Clone)]
+pub struct DeferStmt {
+    pub token: Token,
+    pub value: Expression,
+}
+
+// Throw statement looks like 'throw <expr>;'. Any value can be thrown and
+// it is the value that a 'catch' receives.
+#[derive(Debug, Clone)]
+pub struct ThrowStmt {
+    pub token: Token,
+    pub value: Expression,
+}
+
+// Try statement looks like 'try { } catch (<name>) { } finally { }' where
+// at least one of the 'catch' and the 'finally' blocks is present. The
+// 'finally' block runs however the other blocks are left.
+#[derive(Debug, Clone)]
+pub struct TryStmt {
+    pub token: Token,
+    pub body: BlockStatement,
+    pub catch: Option<(Identifier, BlockStatement)>,
+    pub finally: Option<BlockStatement>,
+}
+
+impl fmt::Display for TryStmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "try {{ {} }}", self.body)?;
+        if let Some((name, body)) = &self.catch {
+            write!(f, " catch ({}) {{ {} }}", name, body)?;
+        }
+        if let Some(body) = &self.finally {
+            write!(f, " finally {{ {} }}", body)?;
+        }
+        Ok(())
+    }
+}
+
+// Import statement looks like 'import "<path>" as <name>;'. The path is
+// relative to the importing file and the name is bound to the exports of
+// the module.
+#[derive(Debug, Clone)]
+pub struct ImportStmt {
+    pub token: Token,
+    pub path: String,
+    pub name: Identifier,
+}
+
 #[derive(Debug, Clone)]
 pub struct ExpressionStmt {
     pub token: Token,
@@ -48,8 +153,13 @@ impl Statement {
     pub fn token_literal(&self) -> String {
         match &self {
             Statement::Let(stmt) => stmt.token.literal.clone(),
+            Statement::Destructure(stmt) => stmt.token.literal.clone(),
             Statement::Return(stmt) => stmt.token.literal.clone(),
             Statement::Expr(stmt) => stmt.token.literal.clone(),
+            Statement::Defer(stmt) => stmt.token.literal.clone(),
+            Statement::Throw(stmt) => stmt.token.literal.clone(),
+            Statement::Try(stmt) => stmt.token.literal.clone(),
+            Statement::Import(stmt) => stmt.token.literal.clone(),
             Statement::Nil => "nil".to_string(),
         }
     }
@@ -58,9 +168,21 @@ impl Statement {
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            Statement::Let(l) => write!(f, "let {} = {};", l.name, l.value),
+            Statement::Let(l) => {
+                for line in l.doc.iter().flat_map(|doc| doc.lines()) {
+                    writeln!(f, "/// {}", line)?;
+                }
+                write!(f, "{} {} = {};", l.token.literal, l.name, l.value)
+            }
+            Statement::Destructure(d) => {
+                write!(f, "{} {} = {};", d.token.literal, d.pattern, d.value)
+            }
             Statement::Return(r) => write!(f, "return {};", r.value),
             Statement::Expr(e) => write!(f, "{}", e.value),
+            Statement::Defer(d) => write!(f, "defer {};", d.value),
+            Statement::Throw(t) => write!(f, "throw {};", t.value),
+            Statement::Try(t) => write!(f, "{}", t),
+            Statement::Import(i) => write!(f, "import \"{}\" as {};", i.path, i.name),
             Statement::Nil => write!(f, "nil"),
         }
     }
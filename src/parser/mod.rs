@@ -21,6 +21,9 @@ pub struct Parser {
     previous: Token,
     current: Token,
     peek_next: Token,
+    // Doc comments that precede the current and the next token
+    current_doc: Option<String>,
+    peek_doc: Option<String>,
     errors: ParseErrors,
 }
 
@@ -38,7 +41,17 @@ impl Parser {
     fn next_token(&mut self) {
         self.previous = self.current.clone();
         self.current = self.peek_next.clone();
+        self.current_doc = self.peek_doc.take();
         self.peek_next = self.scanner.next_token();
+        // Consecutive doc comments are joined into a single block of text
+        while self.peek_next.ttype == TokenType::DocComment {
+            let doc = match self.peek_doc.take() {
+                Some(doc) => format!("{}\n{}", doc, self.peek_next.literal),
+                None => self.peek_next.literal.clone(),
+            };
+            self.peek_doc = Some(doc);
+            self.peek_next = self.scanner.next_token();
+        }
     }
 
     fn curr_token_is(&self, ttype: &TokenType) -> bool {
@@ -98,14 +111,26 @@ impl Parser {
 
     fn parse_statement(&mut self) -> Result<Statement, ParseError> {
         match self.current.ttype {
-            TokenType::Let => self.parse_let_statement(),
+            TokenType::Let | TokenType::Const => self.parse_let_statement(),
             TokenType::Return => self.parse_return_statement(),
+            TokenType::Defer => self.parse_defer_statement(),
+            TokenType::Throw => self.parse_throw_statement(),
+            TokenType::Try => self.parse_try_statement(),
+            TokenType::Import => self.parse_import_statement(),
             _ => self.parse_expr_statement(),
         }
     }
 
+    // Doc comments are only kept when they precede a 'let' statement. They
+    // are attached to the statement and to the function it defines, if any.
+    // A 'const' statement is parsed the same way, only its token differs.
     fn parse_let_statement(&mut self) -> Result<Statement, ParseError> {
         let token_let = self.current.clone();
+        let doc = self.current_doc.take();
+        if self.peek_token_is(&TokenType::LeftBracket) || self.peek_token_is(&TokenType::LeftBrace)
+        {
+            return self.parse_destructure_statement(token_let);
+        }
         if !self.expect_peek(&TokenType::Identifier) {
             return Ok(Statement::Nil);
         }
@@ -123,6 +148,7 @@ impl Parser {
         // functions can be parsed effectively.
         let value = if let Expression::Function(mut func) = value.clone() {
             func.name = token_ident.literal.clone();
+            func.doc = doc.clone();
             // use the updated function
             Expression::Function(func)
         } else {
@@ -142,10 +168,89 @@ impl Parser {
             token: token_let,
             name: identifier,
             value,
+            doc,
         };
         Ok(Statement::Let(let_stmt))
     }
 
+    // Parse 'let [a, b, ...rest] = <expr>;' or 'let {a, b} = <expr>;' and the
+    // same patterns after 'const'
+    fn parse_destructure_statement(&mut self, token_let: Token) -> Result<Statement, ParseError> {
+        self.next_token();
+        let pattern = if self.curr_token_is(&TokenType::LeftBracket) {
+            self.parse_array_pattern()
+        } else {
+            self.parse_map_pattern()
+        };
+        let Some(pattern) = pattern else {
+            return Ok(Statement::Nil);
+        };
+        if !self.expect_peek(&TokenType::Assign) {
+            return Ok(Statement::Nil);
+        }
+        self.next_token();
+        let value = self.parse_expression(Precedence::Lowest);
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+        Ok(Statement::Destructure(DestructureStmt {
+            token: token_let,
+            pattern,
+            value,
+        }))
+    }
+
+    // The names are separated by commas and the last one may be preceded by
+    // '...' to collect the remaining elements
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let mut names = Vec::new();
+        let mut rest = None;
+        while !self.peek_token_is(&TokenType::RightBracket) {
+            if self.peek_token_is(&TokenType::Ellipsis) {
+                self.next_token();
+                if !self.expect_peek(&TokenType::Identifier) {
+                    return None;
+                }
+                rest = Some(self.parse_pattern_name());
+                break;
+            }
+            if !self.expect_peek(&TokenType::Identifier) {
+                return None;
+            }
+            names.push(self.parse_pattern_name());
+            if !self.peek_token_is(&TokenType::RightBracket) && !self.expect_peek(&TokenType::Comma)
+            {
+                return None;
+            }
+        }
+        if !self.expect_peek(&TokenType::RightBracket) {
+            return None;
+        }
+        Some(Pattern::Array(names, rest))
+    }
+
+    fn parse_map_pattern(&mut self) -> Option<Pattern> {
+        let mut names = Vec::new();
+        while !self.peek_token_is(&TokenType::RightBrace) {
+            if !self.expect_peek(&TokenType::Identifier) {
+                return None;
+            }
+            names.push(self.parse_pattern_name());
+            if !self.peek_token_is(&TokenType::RightBrace) && !self.expect_peek(&TokenType::Comma) {
+                return None;
+            }
+        }
+        self.next_token();
+        Some(Pattern::Map(names))
+    }
+
+    fn parse_pattern_name(&self) -> Identifier {
+        Identifier {
+            token: self.current.clone(),
+            value: self.current.literal.clone(),
+        }
+    }
+
     fn parse_return_statement(&mut self) -> Result<Statement, ParseError> {
         let token_ret = self.current.clone();
         self.next_token();
@@ -160,6 +265,103 @@ impl Parser {
         Ok(Statement::Return(ret_stmt))
     }
 
+    fn parse_defer_statement(&mut self) -> Result<Statement, ParseError> {
+        let token_defer = self.current.clone();
+        self.next_token();
+        let value = self.parse_expression(Precedence::Lowest);
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+        let defer_stmt = DeferStmt {
+            token: token_defer,
+            value,
+        };
+        Ok(Statement::Defer(defer_stmt))
+    }
+
+    fn parse_throw_statement(&mut self) -> Result<Statement, ParseError> {
+        let token_throw = self.current.clone();
+        self.next_token();
+        let value = self.parse_expression(Precedence::Lowest);
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+        let throw_stmt = ThrowStmt {
+            token: token_throw,
+            value,
+        };
+        Ok(Statement::Throw(throw_stmt))
+    }
+
+    fn parse_try_statement(&mut self) -> Result<Statement, ParseError> {
+        let token_try = self.current.clone();
+        if !self.expect_peek(&TokenType::LeftBrace) {
+            return Ok(Statement::Nil);
+        }
+        let body = self.parse_block_statement();
+        let mut catch = None;
+        if self.peek_token_is(&TokenType::Catch) {
+            self.next_token();
+            if !self.expect_peek(&TokenType::LeftParen) || !self.expect_peek(&TokenType::Identifier)
+            {
+                return Ok(Statement::Nil);
+            }
+            let name = Identifier {
+                token: self.current.clone(),
+                value: self.current.literal.clone(),
+            };
+            if !self.expect_peek(&TokenType::RightParen) || !self.expect_peek(&TokenType::LeftBrace)
+            {
+                return Ok(Statement::Nil);
+            }
+            catch = Some((name, self.parse_block_statement()));
+        }
+        let mut finally = None;
+        if self.peek_token_is(&TokenType::Finally) {
+            self.next_token();
+            if !self.expect_peek(&TokenType::LeftBrace) {
+                return Ok(Statement::Nil);
+            }
+            finally = Some(self.parse_block_statement());
+        }
+        if catch.is_none() && finally.is_none() {
+            self.push_error("try requires a catch or a finally block");
+            return Ok(Statement::Nil);
+        }
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+        Ok(Statement::Try(TryStmt {
+            token: token_try,
+            body,
+            catch,
+            finally,
+        }))
+    }
+
+    fn parse_import_statement(&mut self) -> Result<Statement, ParseError> {
+        let token_import = self.current.clone();
+        if !self.expect_peek(&TokenType::Str) {
+            return Ok(Statement::Nil);
+        }
+        let path = self.current.literal.clone();
+        if !self.expect_peek(&TokenType::As) || !self.expect_peek(&TokenType::Identifier) {
+            return Ok(Statement::Nil);
+        }
+        let name = Identifier {
+            token: self.current.clone(),
+            value: self.current.literal.clone(),
+        };
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+        Ok(Statement::Import(ImportStmt {
+            token: token_import,
+            path,
+            name,
+        }))
+    }
+
     fn parse_expr_statement(&mut self) -> Result<Statement, ParseError> {
         let token_expr = self.current.clone();
         let expr = self.parse_expression(Precedence::Lowest);
@@ -233,7 +435,23 @@ impl Parser {
     }
 
     fn no_prefix_parse_error(&mut self) {
-        let msg = format!("no prefix parser is available for token '{}'", self.current);
+        // Errors found by the scanner are reported on the line of the token
+        if self.current.ttype == TokenType::Error {
+            let msg = format!("[line {}] {}", self.current.line, self.current.literal);
+            self.errors.push(msg);
+            return;
+        }
+        // The scanner returns malformed numbers as illegal tokens
+        let msg = if self.current.ttype == TokenType::Illegal
+            && self
+                .current
+                .literal
+                .starts_with(|c: char| c.is_ascii_digit())
+        {
+            format!("invalid number literal '{}'", self.current)
+        } else {
+            format!("no prefix parser is available for token '{}'", self.current)
+        };
         self.push_error(&msg);
     }
 
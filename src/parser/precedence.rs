@@ -7,14 +7,21 @@ pub enum Precedence {
     #[default]
     Lowest = 0,
     Assignment, // =
+    Pipeline,   // |>
+    Coalesce,   // ??
     Or,         // or
     And,        // and
     Equality,   // == !=
     Comparison, // < > <= >=
+    BitOr,      // |
+    BitXor,     // ^
+    BitAnd,     // &
+    Shift,      // << >>
     Term,       // + -
-    Factor,     // * /
+    Factor,     // * / % ~/
     Unary,      // ! - (Prefix)
-    Call,       // [] . ()
+    Power,      // **
+    Call,       // [] . ?. ()
     Primary,
 }
 
@@ -40,15 +47,22 @@ impl From<usize> for Precedence {
         match v {
             0 => Precedence::Lowest,
             1 => Precedence::Assignment,
-            2 => Precedence::Or,
-            3 => Precedence::And,
-            4 => Precedence::Equality,
-            5 => Precedence::Comparison,
-            6 => Precedence::Term,
-            7 => Precedence::Factor,
-            8 => Precedence::Unary,
-            9 => Precedence::Call,
-            10 => Precedence::Primary,
+            2 => Precedence::Pipeline,
+            3 => Precedence::Coalesce,
+            4 => Precedence::Or,
+            5 => Precedence::And,
+            6 => Precedence::Equality,
+            7 => Precedence::Comparison,
+            8 => Precedence::BitOr,
+            9 => Precedence::BitXor,
+            10 => Precedence::BitAnd,
+            11 => Precedence::Shift,
+            12 => Precedence::Term,
+            13 => Precedence::Factor,
+            14 => Precedence::Unary,
+            15 => Precedence::Power,
+            16 => Precedence::Call,
+            17 => Precedence::Primary,
             _ => panic!("Cannot convert {} into Precedence", v),
         }
     }
@@ -1,4 +1,5 @@
 use super::*;
+use crate::common::bigint::BigInt;
 use lazy_static::lazy_static;
 
 type PrefixParserFn = fn(&mut Parser) -> Expression;
@@ -31,10 +32,16 @@ lazy_static! {
         // Terminal expressions
         rules[TokenType::Identifier as usize] =
             ParseRule::new(Some(Parser::parse_identifier), None, Precedence::Lowest);
+        rules[TokenType::Integer as usize] =
+            ParseRule::new(Some(Parser::parse_integer), None, Precedence::Lowest);
+        rules[TokenType::BigInt as usize] =
+            ParseRule::new(Some(Parser::parse_bigint), None, Precedence::Lowest);
         rules[TokenType::Number as usize] =
             ParseRule::new(Some(Parser::parse_number), None, Precedence::Lowest);
         rules[TokenType::Str as usize] =
             ParseRule::new(Some(Parser::parse_string), None, Precedence::Lowest);
+        rules[TokenType::Interpolation as usize] =
+            ParseRule::new(Some(Parser::parse_interpolated_string), None, Precedence::Lowest);
         // Logical
         rules[TokenType::Bang as usize] = ParseRule::new(
             Some(Parser::parse_prefix_expression),
@@ -79,6 +86,57 @@ lazy_static! {
             Some(Parser::parse_infix_expression),
             Precedence::Factor,
         );
+        rules[TokenType::Percent as usize] = ParseRule::new(
+            None,
+            Some(Parser::parse_infix_expression),
+            Precedence::Factor,
+        );
+        rules[TokenType::TildeSlash as usize] = ParseRule::new(
+            None,
+            Some(Parser::parse_infix_expression),
+            Precedence::Factor,
+        );
+        rules[TokenType::Power as usize] = ParseRule::new(
+            None,
+            Some(Parser::parse_power_expression),
+            Precedence::Power,
+        );
+        // Bitwise
+        rules[TokenType::Tilde as usize] = ParseRule::new(
+            Some(Parser::parse_prefix_expression),
+            None,
+            Precedence::Lowest,
+        );
+        rules[TokenType::Pipe as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::BitOr);
+        rules[TokenType::Caret as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::BitXor);
+        rules[TokenType::Ampersand as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::BitAnd);
+        rules[TokenType::LessLess as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::Shift);
+        rules[TokenType::GreaterGreater as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::Shift);
+        // Logical operators that short-circuit
+        rules[TokenType::And as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::And);
+        rules[TokenType::Or as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::Or);
+        // Nil coalescing that short-circuits
+        rules[TokenType::QuestionQuestion as usize] =
+            ParseRule::new(None, Some(Parser::parse_infix_expression), Precedence::Coalesce);
+        // Pipeline
+        rules[TokenType::PipeGreater as usize] = ParseRule::new(
+            None,
+            Some(Parser::parse_pipe_expression),
+            Precedence::Pipeline,
+        );
+        // Assignment
+        rules[TokenType::Assign as usize] = ParseRule::new(
+            None,
+            Some(Parser::parse_assign_expression),
+            Precedence::Assignment,
+        );
         // Boolean
         rules[TokenType::True as usize] =
             ParseRule::new(Some(Parser::parse_boolean), None, Precedence::Lowest);
@@ -90,12 +148,26 @@ lazy_static! {
         // Control flow
         rules[TokenType::If as usize] =
             ParseRule::new(Some(Parser::parse_if_expr), None, Precedence::Lowest);
+        rules[TokenType::While as usize] =
+            ParseRule::new(Some(Parser::parse_while_expr), None, Precedence::Lowest);
+        rules[TokenType::For as usize] =
+            ParseRule::new(Some(Parser::parse_for_expr), None, Precedence::Lowest);
+        rules[TokenType::Match as usize] =
+            ParseRule::new(Some(Parser::parse_match_expr), None, Precedence::Lowest);
         // Function
         rules[TokenType::Function as usize] =
             ParseRule::new(Some(Parser::parse_function_literal), None, Precedence::Lowest);
         // Array literal (prefix) and index operator (infix) parser
         rules[TokenType::LeftBracket as usize] =
             ParseRule::new(Some(Parser::parse_array_literal), Some(Parser::parse_index_expression), Precedence::Call);
+        // Property access and method calls
+        rules[TokenType::Dot as usize] =
+            ParseRule::new(None, Some(Parser::parse_dot_expression), Precedence::Call);
+        rules[TokenType::QuestionDot as usize] =
+            ParseRule::new(None, Some(Parser::parse_optional_expression), Precedence::Call);
+        // Nil literal
+        rules[TokenType::Nil as usize] =
+            ParseRule::new(Some(Parser::parse_nil), None, Precedence::Lowest);
         rules[TokenType::LeftBrace as usize] =
             ParseRule::new(Some(Parser::parse_hash_literal), None, Precedence::Lowest);
         rules
@@ -117,32 +189,106 @@ impl Parser {
         })
     }
 
-    fn parse_number(&mut self) -> Expression {
-        if let Ok(value) = self.current.literal.parse() {
-            Expression::Number(NumberLiteral {
+    fn parse_integer(&mut self) -> Expression {
+        if let Some(value) = Self::parse_integer_literal(&self.current.literal) {
+            Expression::Integer(IntegerLiteral {
                 token: self.current.clone(),
                 value,
             })
         } else {
-            let msg = format!("could not parse {} as a number", self.current.literal);
+            let msg = format!("integer literal '{}' is out of range", self.current.literal);
             self.push_error(&msg);
             Expression::Nil
         }
     }
 
-    fn parse_string(&mut self) -> Expression {
-        if let Ok(value) = self.current.literal.parse() {
-            Expression::Str(StringLiteral {
+    // The scanner has already validated the digits of the literal
+    fn parse_bigint(&mut self) -> Expression {
+        let literal = self.current.literal.trim_end_matches('n').replace('_', "");
+        let (digits, radix) = match literal.get(..2) {
+            Some("0x" | "0X") => (&literal[2..], 16),
+            Some("0o" | "0O") => (&literal[2..], 8),
+            Some("0b" | "0B") => (&literal[2..], 2),
+            _ => (&literal[..], 10),
+        };
+        match BigInt::from_str_radix(digits, radix) {
+            Some(value) => Expression::BigInt(BigIntLiteral {
+                token: self.current.clone(),
+                value,
+            }),
+            None => {
+                let msg = format!("could not parse {} as a bigint", self.current.literal);
+                self.push_error(&msg);
+                Expression::Nil
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Expression {
+        if let Some(value) = Self::parse_number_literal(&self.current.literal) {
+            Expression::Number(NumberLiteral {
                 token: self.current.clone(),
                 value,
             })
         } else {
-            let msg = format!("could not parse {} as a string", self.current.literal);
+            let msg = format!("could not parse {} as a number", self.current.literal);
             self.push_error(&msg);
             Expression::Nil
         }
     }
 
+    // Convert the literal of an integer token to its value. The scanner has
+    // already validated the placement of the radix prefix and underscores.
+    // Returns None if the value does not fit in an i64.
+    fn parse_integer_literal(literal: &str) -> Option<i64> {
+        let literal = literal.replace('_', "");
+        let radix = match literal.get(..2) {
+            Some("0x" | "0X") => 16,
+            Some("0o" | "0O") => 8,
+            Some("0b" | "0B") => 2,
+            _ => return literal.parse().ok(),
+        };
+        i64::from_str_radix(&literal[2..], radix).ok()
+    }
+
+    // Convert the literal of a number token with a fraction or an exponent
+    fn parse_number_literal(literal: &str) -> Option<f64> {
+        literal.replace('_', "").parse().ok()
+    }
+
+    fn parse_string(&mut self) -> Expression {
+        Expression::Str(StringLiteral {
+            token: self.current.clone(),
+            value: self.current.literal.clone(),
+        })
+    }
+
+    // The scanner splits a string with embedded expressions into parts. Each
+    // 'Interpolation' token is followed by the tokens of an expression. The
+    // last part of the string is an ordinary string token.
+    fn parse_interpolated_string(&mut self) -> Expression {
+        let token = self.current.clone();
+        let mut parts = Vec::new();
+        loop {
+            if !self.current.literal.is_empty() {
+                parts.push(self.parse_string());
+            }
+            if self.curr_token_is(&TokenType::Str) {
+                break;
+            }
+            self.next_token();
+            parts.push(self.parse_expression(Precedence::Lowest));
+            if self.peek_token_is(&TokenType::Interpolation) || self.peek_token_is(&TokenType::Str)
+            {
+                self.next_token();
+            } else {
+                self.peek_error(&TokenType::Str);
+                return Expression::Nil;
+            }
+        }
+        Expression::Interpolated(InterpolatedStr { token, parts })
+    }
+
     // Parse unary expressions such as '-' and '!'
     fn parse_prefix_expression(&mut self) -> Expression {
         let operator = self.current.literal.clone();
@@ -175,6 +321,72 @@ impl Parser {
         })
     }
 
+    // Exponentiation is right associative and binds tighter than the
+    // prefix operators, so '-2 ** 2' is '-(2 ** 2)' and '2 ** 3 ** 2'
+    // is '2 ** (3 ** 2)'. The exponent itself may be a prefix expression.
+    fn parse_power_expression(&mut self, left: Expression) -> Expression {
+        let operator = self.current.literal.clone();
+        let token = self.current.clone();
+        self.next_token();
+        let right = self.parse_expression(Precedence::Unary);
+
+        Expression::Binary(BinaryExpr {
+            token,
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    // The pipeline 'x |> f(a)' is sugar for the call 'f(x, a)', so it is
+    // desugared here and compiled and evaluated as a regular call. The value
+    // is passed as the first argument of a call or a method call on the
+    // right, and any other expression on the right is called with the value
+    // as its only argument, so 'x |> f' is 'f(x)'.
+    fn parse_pipe_expression(&mut self, left: Expression) -> Expression {
+        let token = self.current.clone();
+        self.next_token();
+        match self.parse_expression(Precedence::Pipeline) {
+            Expression::Call(mut call) => {
+                call.args.insert(0, left);
+                Expression::Call(call)
+            }
+            Expression::MethodCall(mut call) => {
+                call.args.insert(0, left);
+                Expression::MethodCall(call)
+            }
+            Expression::Nil => Expression::Nil,
+            func => Expression::Call(CallExpr {
+                token,
+                func: Box::new(func),
+                args: vec![left],
+            }),
+        }
+    }
+
+    // Assignment is right associative, so the value is parsed with the
+    // lowest precedence. Only existing bindings and elements of arrays
+    // or maps can be assigned to.
+    fn parse_assign_expression(&mut self, target: Expression) -> Expression {
+        let token = self.current.clone();
+        if !matches!(
+            target,
+            Expression::Ident(_) | Expression::Index(_) | Expression::Property(_)
+        ) {
+            let msg = format!("invalid assignment target {}", target);
+            self.push_error(&msg);
+            return Expression::Nil;
+        }
+        self.next_token();
+        let value = self.parse_expression(Precedence::Lowest);
+
+        Expression::Assign(AssignExpr {
+            token,
+            target: Box::new(target),
+            value: Box::new(value),
+        })
+    }
+
     fn parse_boolean(&mut self) -> Expression {
         Expression::Bool(BooleanExpr {
             token: self.current.clone(),
@@ -228,7 +440,226 @@ impl Parser {
         })
     }
 
-    fn parse_block_statement(&mut self) -> BlockStatement {
+    fn parse_while_expr(&mut self) -> Expression {
+        let token = self.current.clone();
+        if !self.expect_peek(&TokenType::LeftParen) {
+            return Expression::Nil;
+        }
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest);
+        if !self.expect_peek(&TokenType::RightParen) {
+            return Expression::Nil;
+        }
+        if !self.expect_peek(&TokenType::LeftBrace) {
+            return Expression::Nil;
+        }
+        let body = self.parse_block_statement();
+
+        Expression::While(WhileExpr {
+            token,
+            condition: Box::new(condition),
+            body,
+        })
+    }
+
+    // Parse 'for (item in iterable) { .. }' or 'for (key, value in iterable) { .. }'
+    fn parse_for_expr(&mut self) -> Expression {
+        let token = self.current.clone();
+        if !self.expect_peek(&TokenType::LeftParen) {
+            return Expression::Nil;
+        }
+        let mut idents = Vec::new();
+        loop {
+            if !self.expect_peek(&TokenType::Identifier) {
+                return Expression::Nil;
+            }
+            idents.push(Identifier {
+                token: self.current.clone(),
+                value: self.current.literal.clone(),
+            });
+            if idents.len() == 2 || !self.peek_token_is(&TokenType::Comma) {
+                break;
+            }
+            self.next_token();
+        }
+        if !self.expect_peek(&TokenType::In) {
+            return Expression::Nil;
+        }
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::Lowest);
+        if !self.expect_peek(&TokenType::RightParen) {
+            return Expression::Nil;
+        }
+        if !self.expect_peek(&TokenType::LeftBrace) {
+            return Expression::Nil;
+        }
+        let body = self.parse_block_statement();
+
+        Expression::For(ForExpr {
+            token,
+            idents,
+            iterable: Box::new(iterable),
+            body,
+        })
+    }
+
+    // Parse 'match (<value>) { <pattern> if <guard> => <body>, ... }'. The
+    // guard is optional and the body is an expression or a block. Arms are
+    // separated by commas, which may be left out after a block.
+    fn parse_match_expr(&mut self) -> Expression {
+        let token = self.current.clone();
+        if !self.expect_peek(&TokenType::LeftParen) {
+            return Expression::Nil;
+        }
+        self.next_token();
+        let subject = self.parse_expression(Precedence::Lowest);
+        if !self.expect_peek(&TokenType::RightParen) || !self.expect_peek(&TokenType::LeftBrace) {
+            return Expression::Nil;
+        }
+        let mut arms = Vec::new();
+        while !self.peek_token_is(&TokenType::RightBrace) {
+            self.next_token();
+            let Some(pattern) = self.parse_match_pattern() else {
+                return Expression::Nil;
+            };
+            let guard = if self.peek_token_is(&TokenType::If) {
+                self.next_token();
+                self.next_token();
+                Some(self.parse_expression(Precedence::Lowest))
+            } else {
+                None
+            };
+            if !self.expect_peek(&TokenType::FatArrow) {
+                return Expression::Nil;
+            }
+            self.next_token();
+            let is_block = self.curr_token_is(&TokenType::LeftBrace);
+            let body = if is_block {
+                self.parse_block_statement()
+            } else {
+                let token = self.current.clone();
+                let value = self.parse_expression(Precedence::Lowest);
+                BlockStatement {
+                    statements: vec![Statement::Expr(ExpressionStmt { token, value })],
+                }
+            };
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            if self.peek_token_is(&TokenType::Comma) {
+                self.next_token();
+            } else if !is_block {
+                break;
+            }
+        }
+        if !self.expect_peek(&TokenType::RightBrace) {
+            return Expression::Nil;
+        }
+        Expression::Match(MatchExpr {
+            token,
+            subject: Box::new(subject),
+            arms,
+        })
+    }
+
+    // Parse the pattern of a match arm starting at the current token
+    fn parse_match_pattern(&mut self) -> Option<MatchPattern> {
+        match self.current.ttype {
+            TokenType::Identifier if self.current.literal == "_" => Some(MatchPattern::Wildcard),
+            TokenType::Identifier => Some(MatchPattern::Binding(Identifier {
+                token: self.current.clone(),
+                value: self.current.literal.clone(),
+            })),
+            TokenType::Integer
+            | TokenType::BigInt
+            | TokenType::Number
+            | TokenType::Str
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil => Some(MatchPattern::Literal(
+                self.parse_expression(Precedence::Primary),
+            )),
+            TokenType::Minus
+                if matches!(
+                    self.peek_next.ttype,
+                    TokenType::Integer | TokenType::BigInt | TokenType::Number
+                ) =>
+            {
+                Some(MatchPattern::Literal(
+                    self.parse_expression(Precedence::Primary),
+                ))
+            }
+            TokenType::LeftBracket => self.parse_array_match_pattern(),
+            TokenType::LeftBrace => self.parse_map_match_pattern(),
+            _ => {
+                let msg = format!("invalid match pattern '{}'", self.current.literal);
+                self.push_error(&msg);
+                None
+            }
+        }
+    }
+
+    // Parse '[a, b, ...rest]' where the elements are patterns
+    fn parse_array_match_pattern(&mut self) -> Option<MatchPattern> {
+        let mut elements = Vec::new();
+        let mut rest = None;
+        while !self.peek_token_is(&TokenType::RightBracket) {
+            self.next_token();
+            if self.curr_token_is(&TokenType::Ellipsis) {
+                // The rest pattern must be the last one
+                if !self.expect_peek(&TokenType::Identifier) {
+                    return None;
+                }
+                rest = Some(Box::new(self.parse_match_pattern()?));
+                break;
+            }
+            elements.push(self.parse_match_pattern()?);
+            if !self.peek_token_is(&TokenType::RightBracket) && !self.expect_peek(&TokenType::Comma)
+            {
+                return None;
+            }
+        }
+        if !self.expect_peek(&TokenType::RightBracket) {
+            return None;
+        }
+        Some(MatchPattern::Array(elements, rest))
+    }
+
+    // Parse '{"key": pattern, name}' where 'name' is short for '"name": name'
+    fn parse_map_match_pattern(&mut self) -> Option<MatchPattern> {
+        let mut entries = Vec::new();
+        while !self.peek_token_is(&TokenType::RightBrace) {
+            self.next_token();
+            let key = self.current.literal.clone();
+            match self.current.ttype {
+                TokenType::Str => {
+                    if !self.expect_peek(&TokenType::Colon) {
+                        return None;
+                    }
+                    self.next_token();
+                    entries.push((key, self.parse_match_pattern()?));
+                }
+                TokenType::Identifier => {
+                    let pattern = self.parse_match_pattern()?;
+                    entries.push((key, pattern));
+                }
+                _ => {
+                    let msg = format!("invalid map pattern key '{}'", key);
+                    self.push_error(&msg);
+                    return None;
+                }
+            }
+            if !self.peek_token_is(&TokenType::RightBrace) && !self.expect_peek(&TokenType::Comma) {
+                return None;
+            }
+        }
+        self.next_token();
+        Some(MatchPattern::Map(entries))
+    }
+
+    pub fn parse_block_statement(&mut self) -> BlockStatement {
         let mut statements = Vec::new();
         self.next_token();
 
@@ -246,7 +677,7 @@ impl Parser {
         if !self.expect_peek(&TokenType::LeftParen) {
             return Expression::Nil;
         }
-        let params = self.parse_function_params();
+        let (params, defaults, rest) = self.parse_function_params();
         if !self.expect_peek(&TokenType::LeftBrace) {
             return Expression::Nil;
         }
@@ -258,40 +689,58 @@ impl Parser {
             name: String::new(),
             token,
             params,
+            defaults,
+            rest,
             body,
+            doc: None,
         })
     }
 
-    fn parse_function_params(&mut self) -> Vec<Identifier> {
+    // Parameters are identifiers separated by commas. A parameter may have
+    // a default value ('b = 10'), and then the parameters after it must have
+    // one too. The last parameter may be preceded by '...' to collect the
+    // remaining arguments into an array.
+    fn parse_function_params(&mut self) -> (Vec<Identifier>, Vec<Expression>, Option<Identifier>) {
         let mut identifiers = Vec::new();
-        if self.peek_token_is(&TokenType::RightParen) {
-            self.next_token();
-            return identifiers;
-        }
-        self.next_token();
-        let token_ident = self.current.clone();
-        let ident_value = token_ident.literal.clone();
-        identifiers.push(Identifier {
-            token: token_ident,
-            value: ident_value,
-        });
-
-        while self.peek_token_is(&TokenType::Comma) {
-            self.next_token();
-            self.next_token();
+        let mut defaults = Vec::new();
+        let mut rest = None;
+        while !self.peek_token_is(&TokenType::RightParen) {
+            let is_rest = self.peek_token_is(&TokenType::Ellipsis);
+            if is_rest {
+                self.next_token();
+            }
+            if !self.expect_peek(&TokenType::Identifier) {
+                return (Vec::new(), Vec::new(), None);
+            }
             let token_ident = self.current.clone();
             let ident_value = token_ident.literal.clone();
-            identifiers.push(Identifier {
+            let ident = Identifier {
                 token: token_ident,
                 value: ident_value,
-            });
+            };
+            if is_rest {
+                rest = Some(ident);
+                break;
+            }
+            if self.peek_token_is(&TokenType::Assign) {
+                self.next_token();
+                self.next_token();
+                defaults.push(self.parse_expression(Precedence::Lowest));
+            } else if !defaults.is_empty() {
+                let msg = format!("parameter '{}' must have a default value", ident);
+                self.push_error(&msg);
+            }
+            identifiers.push(ident);
+            if !self.peek_token_is(&TokenType::RightParen) && !self.expect_peek(&TokenType::Comma) {
+                return (Vec::new(), Vec::new(), None);
+            }
         }
 
         if !self.expect_peek(&TokenType::RightParen) {
-            return Vec::new();
+            return (Vec::new(), Vec::new(), None);
         }
 
-        identifiers
+        (identifiers, defaults, rest)
     }
 
     // Call expressions do not have new token types. A call expression is an
@@ -308,9 +757,65 @@ impl Parser {
         })
     }
 
+    // Parse the property access 'a.b' or the method call 'a.b(c)'. The
+    // name after the '.' must be an identifier. This also parses the
+    // optional forms 'a?.b' and 'a?.b(c)'.
+    fn parse_dot_expression(&mut self, left: Expression) -> Expression {
+        let token = self.current.clone();
+        let optional = token.ttype == TokenType::QuestionDot;
+        if !self.expect_peek(&TokenType::Identifier) {
+            return Expression::Nil;
+        }
+        let name = Identifier {
+            token: self.current.clone(),
+            value: self.current.literal.clone(),
+        };
+        if self.peek_token_is(&TokenType::LeftParen) {
+            self.next_token();
+            return Expression::MethodCall(MethodCallExpr {
+                token,
+                receiver: Box::new(left),
+                name,
+                args: self.parse_expression_list(TokenType::RightParen),
+                optional,
+            });
+        }
+        Expression::Property(PropertyExpr {
+            token,
+            left: Box::new(left),
+            name,
+            optional,
+        })
+    }
+
+    // Parse 'a?.b', 'a?.b(c)' or the optional index 'a?.[i]', which may
+    // also be a slice
+    fn parse_optional_expression(&mut self, left: Expression) -> Expression {
+        if !self.peek_token_is(&TokenType::LeftBracket) {
+            return self.parse_dot_expression(left);
+        }
+        self.next_token();
+        match self.parse_index_expression(left) {
+            Expression::Index(index) => Expression::Index(IndexExpr {
+                optional: true,
+                ..index
+            }),
+            Expression::Slice(slice) => Expression::Slice(SliceExpr {
+                optional: true,
+                ..slice
+            }),
+            expr => expr,
+        }
+    }
+
+    fn parse_nil(&mut self) -> Expression {
+        Expression::Nil
+    }
+
     // Generic function that parses call arguments as well as array literal
     // expression as both of those are essentially a comma separated list
-    // of expressions. The only difference is the end token that is used to
+    // of expressions, any of which may be a spread expression such as
+    // '...arr'. The only difference is the end token that is used to
     // indicate the end of the list. This token type is passed as an argument.
     fn parse_expression_list(&mut self, ttype_end: TokenType) -> Vec<Expression> {
         let mut args = Vec::new();
@@ -320,11 +825,11 @@ impl Parser {
             return args;
         }
         self.next_token();
-        args.push(self.parse_expression(Precedence::Lowest));
+        args.push(self.parse_list_element());
         while self.peek_token_is(&TokenType::Comma) {
             self.next_token();
             self.next_token();
-            args.push(self.parse_expression(Precedence::Lowest));
+            args.push(self.parse_list_element());
         }
 
         if !self.expect_peek(&ttype_end) {
@@ -333,6 +838,18 @@ impl Parser {
         args
     }
 
+    fn parse_list_element(&mut self) -> Expression {
+        if !self.curr_token_is(&TokenType::Ellipsis) {
+            return self.parse_expression(Precedence::Lowest);
+        }
+        let token = self.current.clone();
+        self.next_token();
+        Expression::Spread(SpreadExpr {
+            token,
+            value: Box::new(self.parse_expression(Precedence::Lowest)),
+        })
+    }
+
     fn parse_array_literal(&mut self) -> Expression {
         let token = self.current.clone();
 
@@ -345,23 +862,59 @@ impl Parser {
     // The index operator do not have a single operator between the operands
     // on each side. But in order to parse them, it is easier to pretend that
     // they do. The index expression 'a[0]' is treated as an infix expression
-    // with an expression 'a' on the left and an index '0' on the right.
+    // with an expression 'a' on the left and an index '0' on the right. A
+    // slice 'a[start:end:step]' is parsed the same way.
     fn parse_index_expression(&mut self, left: Expression) -> Expression {
         let token = self.current.clone();
-        // advance to the next token
+        let mut start = None;
+        if !self.peek_token_is(&TokenType::Colon) {
+            // advance to the next token
+            self.next_token();
+            let index = self.parse_expression(Precedence::Lowest);
+            if !self.peek_token_is(&TokenType::Colon) {
+                if !self.expect_peek(&TokenType::RightBracket) {
+                    return Expression::Nil;
+                }
+                return Expression::Index(IndexExpr {
+                    token,
+                    left: Box::new(left),
+                    index: Box::new(index),
+                    optional: false,
+                });
+            }
+            start = Some(Box::new(index));
+        }
+        // move to the ':' after the start
         self.next_token();
-        let index = self.parse_expression(Precedence::Lowest);
+        let end = self.parse_slice_bound();
+        let step = if self.peek_token_is(&TokenType::Colon) {
+            self.next_token();
+            self.parse_slice_bound()
+        } else {
+            None
+        };
         if !self.expect_peek(&TokenType::RightBracket) {
             return Expression::Nil;
         }
-
-        Expression::Index(IndexExpr {
+        Expression::Slice(SliceExpr {
             token,
             left: Box::new(left),
-            index: Box::new(index),
+            start,
+            end,
+            step,
+            optional: false,
         })
     }
 
+    // Parse the bound of a slice that follows the current ':' if there is one
+    fn parse_slice_bound(&mut self) -> Option<Box<Expression>> {
+        if self.peek_token_is(&TokenType::Colon) || self.peek_token_is(&TokenType::RightBracket) {
+            return None;
+        }
+        self.next_token();
+        Some(Box::new(self.parse_expression(Precedence::Lowest)))
+    }
+
     fn parse_hash_literal(&mut self) -> Expression {
         let token = self.current.clone();
         let mut pairs = Vec::new();
@@ -38,12 +38,24 @@ fn check_parse_errors(parser: &Parser) {
 
 #[cfg(test)]
 fn test_numeric_literal(expr: &Expression, expected: f64) {
-    if let Expression::Number(num) = expr {
+    match expr {
+        Expression::Integer(num) if num.value as f64 == expected => {}
+        Expression::Number(num) if num.value == expected => {}
+        Expression::Integer(_) | Expression::Number(_) => {
+            panic!("number.value not '{}'. got='{}'", expected, expr);
+        }
+        _ => panic!("expr not an Number. got={:?}", expr),
+    }
+}
+
+#[cfg(test)]
+fn test_integer_literal(expr: &Expression, expected: i64) {
+    if let Expression::Integer(num) = expr {
         if num.value != expected {
-            panic!("number.value not '{}'. got='{}'", expected, num.value);
+            panic!("integer.value not '{}'. got='{}'", expected, num.value);
         }
     } else {
-        panic!("expr not an Number. got={:?}", expr);
+        panic!("expr not an Integer. got={:?}", expr);
     }
 }
 
@@ -117,7 +129,7 @@ fn test_prefix_expression(expression: &Expression, operator: &str, right: Litera
                 expr.operator, operator
             );
         }
-        test_literal(&*expr.right, right);
+        test_literal(&expr.right, right);
     } else {
         panic!("expr not a Prefix expression. got={:?}", expression);
     }
@@ -133,8 +145,8 @@ fn test_infix_expression(expression: &Expression, left: Literal, operator: &str,
                 expr.operator, operator
             );
         }
-        test_literal(&*expr.left, left);
-        test_literal(&*expr.right, right);
+        test_literal(&expr.left, left);
+        test_literal(&expr.right, right);
     } else {
         panic!("expr not an Infix expression. got={:?}", expression);
     }
@@ -169,7 +181,7 @@ fn test_let_statements() {
         let program = parse_test_program(test.input, 1);
 
         let stmt = &program.statements[0];
-        test_let_statement(&stmt, test.expected_id, test.expected_val);
+        test_let_statement(stmt, test.expected_id, test.expected_val);
     }
 }
 
@@ -192,6 +204,69 @@ fn test_let_statement(stmt: &Statement, expected_id: &str, expected_val: Literal
     }
 }
 
+#[test]
+fn test_destructure_statements() {
+    let tests = [
+        ("let [a, b] = arr;", "let [a, b] = arr;"),
+        (
+            "let [first, ...rest] = f(x)",
+            "let [first, ...rest] = f(x);",
+        ),
+        ("let [...all] = [1, 2]", "let [...all] = [1, 2];"),
+        ("let [] = []", "let [] = [];"),
+        ("let {name, age} = person;", "let {name, age} = person;"),
+        ("let {} = m;", "let {} = m;"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        if let Statement::Destructure(stmt) = &program.statements[0] {
+            assert_eq!(stmt.token.literal, "let");
+        } else {
+            panic!(
+                "stmt is not a destructuring let. got={}",
+                program.statements[0]
+            );
+        }
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "let [a, ...b, c] = x;",
+            "expected next token to be ], got , instead",
+        ),
+        (
+            "let [a b] = x;",
+            "expected next token to be ,, got IDENT instead",
+        ),
+        (
+            "let [1] = x;",
+            "expected next token to be IDENT, got INT instead",
+        ),
+        (
+            "let {a, ...b} = x;",
+            "expected next token to be IDENT, got ... instead",
+        ),
+        (
+            "let [a] x;",
+            "expected next token to be =, got IDENT instead",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e.ends_with(expected)),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
 #[test]
 fn test_return_statements() {
     let input = "
@@ -242,6 +317,7 @@ fn test_string_formatting() {
             token: let_token,
             name: ident_myvar1,
             value: Expression::Ident(ident_myvar2),
+            doc: None,
         })],
     };
 
@@ -289,6 +365,126 @@ fn test_numeric_literal_expression() {
     }
 }
 
+#[test]
+fn test_numeric_literal_formats() {
+    let tests = [
+        ("2.75", 2.75),
+        ("0.5", 0.5),
+        ("1e-9", 1e-9),
+        ("2.5E+3", 2500.),
+        ("1_000_000", 1_000_000.),
+        ("0xff", 255.),
+        ("0XFF", 255.),
+        ("0o17", 15.),
+        ("0b1010_1010", 170.),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        if let Statement::Expr(stmt) = &program.statements[0] {
+            test_numeric_literal(&stmt.value, expected);
+        } else {
+            panic!(
+                "program.statements[0] is not an expression statement. got={}",
+                program.statements[0]
+            );
+        }
+    }
+
+    let inputs = ["1.2.3", "let x = 1__0;", "0b12", "2 * 0x"];
+    for input in inputs {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e.contains("invalid number literal")),
+            "expected an invalid number literal error for '{}'. got={:?}",
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_integer_literals() {
+    let tests = [
+        ("5", 5),
+        ("1_000_000", 1_000_000),
+        ("0xff", 255),
+        ("0b1010_1010", 170),
+        ("9223372036854775807", i64::MAX),
+        ("0x7fff_ffff_ffff_ffff", i64::MAX),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        if let Statement::Expr(stmt) = &program.statements[0] {
+            test_integer_literal(&stmt.value, expected);
+        } else {
+            panic!(
+                "program.statements[0] is not an expression statement. got={}",
+                program.statements[0]
+            );
+        }
+    }
+
+    // A fraction or an exponent makes a float
+    for input in ["5.0", "1e3"] {
+        let program = parse_test_program(input, 1);
+        if let Statement::Expr(stmt) = &program.statements[0] {
+            assert!(
+                matches!(stmt.value, Expression::Number(_)),
+                "expr not a Number. got={:?}",
+                stmt.value
+            );
+        }
+    }
+
+    let inputs = ["9223372036854775808", "0xffff_ffff_ffff_ffff"];
+    for input in inputs {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        let expected = format!("integer literal '{}' is out of range", input);
+        assert!(
+            errors.iter().any(|e| e.contains(&expected)),
+            "expected an out of range error for '{}'. got={:?}",
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_bigint_literals() {
+    let tests = [
+        ("0n", "0"),
+        (
+            "123456789012345678901234567890n",
+            "123456789012345678901234567890",
+        ),
+        ("1_000_000n", "1000000"),
+        ("0xffff_ffff_ffff_ffff_ffffn", "1208925819614629174706175"),
+        ("0b1010n", "10"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        if let Statement::Expr(stmt) = &program.statements[0] {
+            if let Expression::BigInt(num) = &stmt.value {
+                assert_eq!(num.value.to_string(), expected);
+                assert_eq!(num.to_string(), input);
+            } else {
+                panic!("expr not a BigInt. got={:?}", stmt.value);
+            }
+        } else {
+            panic!(
+                "program.statements[0] is not an expression statement. got={}",
+                program.statements[0]
+            );
+        }
+    }
+}
+
 #[test]
 fn test_string_literal_expression() {
     let input = r#""hello world";"#;
@@ -305,6 +501,83 @@ fn test_string_literal_expression() {
     }
 }
 
+#[test]
+fn test_interpolated_string_expression() {
+    let input = r#""a ${x + 1} b ${y}""#;
+    let program = parse_test_program(input, 1);
+
+    let stmt = &program.statements[0];
+    if let Statement::Expr(stmt) = stmt {
+        if let Expression::Interpolated(expr) = &stmt.value {
+            assert_eq!(expr.parts.len(), 4, "wrong number of parts");
+            test_string_literal(&expr.parts[0], "a ");
+            test_infix_expression(&expr.parts[1], Literal::Str("x"), "+", Literal::Numeric(1.));
+            test_string_literal(&expr.parts[2], " b ");
+            test_identifier(&expr.parts[3], "y");
+            assert_eq!(stmt.value.to_string(), "a ${(x + 1)} b ${y}");
+        } else {
+            panic!(
+                "stmt.expr is not an Interpolated string. got={}",
+                stmt.value
+            );
+        }
+    } else {
+        panic!(
+            "program.statements[0] is not an expression statement. got={}",
+            stmt
+        );
+    }
+}
+
+#[test]
+fn test_string_errors() {
+    let tests = [
+        ("let a = \"abc", "[line 1] unterminated string"),
+        (
+            "let a = 1;\nlet b = \"x\ny\";\nlet c = \"abc",
+            "[line 4] unterminated string",
+        ),
+        ("let a = \"\\z\";", "[line 1] invalid escape sequence '\\z'"),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e == expected),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_block_comment_errors() {
+    let tests = [
+        ("let a = 1; /* abc", "[line 1] unterminated block comment"),
+        (
+            "let a = 1;\n/* x /* y */\n*/ let b = 2 + /* z",
+            "[line 3] unterminated block comment",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e == expected),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
 #[test]
 fn test_parsing_prefix_expressions() {
     struct PrefixTest {
@@ -589,6 +862,101 @@ fn test_parsing_operator_precedence() {
             expected: "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
             num_stmts: 1,
         },
+        PrecedenceTest {
+            input: "a = b = c + d * e",
+            expected: "(a = (b = (c + (d * e))))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a = b == c",
+            expected: "(a = (b == c))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a[i + 1] = b[i]",
+            expected: "((a[(i + 1)]) = (b[i]))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a || b && c",
+            expected: "(a || (b && c))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a == b && c < d || !e",
+            expected: "(((a == b) && (c < d)) || (!e))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "x = a || b",
+            expected: "(x = (a || b))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a + b % c ~/ d",
+            expected: "(a + ((b % c) ~/ d))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a * b ** c",
+            expected: "(a * (b ** c))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a ** b ** c",
+            expected: "(a ** (b ** c))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "-a ** b",
+            expected: "(-(a ** b))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a ** -b",
+            expected: "(a ** (-b))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a ** b[c]",
+            expected: "(a ** (b[c]))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a | b ^ c & d",
+            expected: "(a | (b ^ (c & d)))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a & b == c",
+            expected: "((a & b) == c)",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a << b + c & d >> e",
+            expected: "((a << (b + c)) & (d >> e))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "~a & -b | !c",
+            expected: "(((~a) & (-b)) | (!c))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a | b && c ^ d",
+            expected: "((a | b) && (c ^ d))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "x = a ?? b || c ?? d",
+            expected: "(x = ((a ?? (b || c)) ?? d))",
+            num_stmts: 1,
+        },
+        PrecedenceTest {
+            input: "a?.b.c ?? -d?.[e]",
+            expected: "(((a?.b).c) ?? (-(d?.[e])))",
+            num_stmts: 1,
+        },
     ];
 
     for test in precedence_tests {
@@ -698,6 +1066,120 @@ fn test_if_then_else_expression() {
     }
 }
 
+#[test]
+fn test_while_expression() {
+    let input = "while (x < y) { x }";
+    let program = parse_test_program(input, 1);
+
+    let stmt = &program.statements[0];
+    if let Statement::Expr(stmt) = stmt {
+        if let Expression::While(expr) = &stmt.value {
+            test_infix_expression(&expr.condition, Literal::Str("x"), "<", Literal::Str("y"));
+            let num_stmts = expr.body.statements.len();
+            assert_eq!(num_stmts, 1, "body count not 1. got={}", num_stmts);
+            if let Statement::Expr(expr) = &expr.body.statements[0] {
+                test_identifier(&expr.value, "x");
+            } else {
+                panic!(
+                    "body.statements[0] is not an expression statement. got={}",
+                    expr.body.statements[0]
+                );
+            }
+        } else {
+            panic!("stmt.expr is not a While expression. got={}", stmt.value);
+        }
+    } else {
+        panic!(
+            "program.statements[0] is not an expression statement. got={}",
+            stmt
+        );
+    }
+}
+
+#[test]
+fn test_for_expression() {
+    let tests = vec![
+        ("for (x in arr) { x }", vec!["x"], "x"),
+        ("for (k, v in map) { v }", vec!["k", "v"], "v"),
+    ];
+
+    for (input, expected_idents, expected_body) in tests {
+        let program = parse_test_program(input, 1);
+        let stmt = &program.statements[0];
+        if let Statement::Expr(stmt) = stmt {
+            if let Expression::For(expr) = &stmt.value {
+                assert_eq!(
+                    expr.idents.len(),
+                    expected_idents.len(),
+                    "wrong number of loop variables. got={}",
+                    expr.idents.len()
+                );
+                for (ident, expected) in expr.idents.iter().zip(expected_idents) {
+                    test_ident_token_literal(ident, expected);
+                }
+                let num_stmts = expr.body.statements.len();
+                assert_eq!(num_stmts, 1, "body count not 1. got={}", num_stmts);
+                if let Statement::Expr(body) = &expr.body.statements[0] {
+                    test_identifier(&body.value, expected_body);
+                } else {
+                    panic!(
+                        "body.statements[0] is not an expression statement. got={}",
+                        expr.body.statements[0]
+                    );
+                }
+            } else {
+                panic!("stmt.expr is not a For expression. got={}", stmt.value);
+            }
+        } else {
+            panic!(
+                "program.statements[0] is not an expression statement. got={}",
+                stmt
+            );
+        }
+    }
+}
+
+#[test]
+fn test_assign_expression() {
+    let input = "x = y + 1";
+    let program = parse_test_program(input, 1);
+
+    let stmt = &program.statements[0];
+    if let Statement::Expr(stmt) = stmt {
+        if let Expression::Assign(expr) = &stmt.value {
+            test_identifier(&expr.target, "x");
+            test_infix_expression(&expr.value, Literal::Str("y"), "+", Literal::Numeric(1.));
+        } else {
+            panic!("stmt.expr is not an Assign expression. got={}", stmt.value);
+        }
+    } else {
+        panic!(
+            "program.statements[0] is not an expression statement. got={}",
+            stmt
+        );
+    }
+}
+
+#[test]
+fn test_invalid_assign_targets() {
+    let inputs = ["1 = 2", "a + b = c", "f() = 1"];
+
+    for input in inputs {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("invalid assignment target")),
+            "expected an invalid assignment target error for '{}'. got={:?}",
+            input,
+            errors
+        );
+    }
+}
+
 #[test]
 fn test_parsing_function_literal() {
     let input = "fn(x, y) { x + y; }";
@@ -997,6 +1479,366 @@ fn test_parsing_hash_literals_with_exprs() {
     }
 }
 
+#[test]
+fn test_default_and_rest_parameters() {
+    let tests = [
+        ("fn(a, b = 10) {}", 2, 1, None),
+        ("fn(a = 1, b = a * 2) {}", 2, 2, None),
+        ("fn(...rest) {}", 0, 0, Some("rest")),
+        ("fn(a, b = [1], ...rest) {}", 2, 1, Some("rest")),
+    ];
+    for (input, num_params, num_defaults, rest) in tests {
+        let program = parse_test_program(input, 1);
+        if let Statement::Expr(stmt) = &program.statements[0] {
+            if let Expression::Function(func) = &stmt.value {
+                assert_eq!(func.params.len(), num_params, "{}", input);
+                assert_eq!(func.defaults.len(), num_defaults, "{}", input);
+                assert_eq!(func.rest.as_ref().map(|r| r.value.as_str()), rest);
+            } else {
+                panic!("stmt.value is not a FunctionLiteral. got={}", stmt.value);
+            }
+        }
+    }
+    let program = parse_test_program("fn(a, b = a + 1, ...c) { a }", 1);
+    assert_eq!(program.to_string(), "fn (a, b = (a + 1), ...c) a");
+
+    let tests = [
+        ("fn(a = 1, b) {}", "parameter 'b' must have a default value"),
+        (
+            "fn(...a, b) {}",
+            "expected next token to be ), got , instead",
+        ),
+        (
+            "fn(a b) {}",
+            "expected next token to be ,, got IDENT instead",
+        ),
+        (
+            "fn(1) {}",
+            "expected next token to be IDENT, got INT instead",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e.ends_with(expected)),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_spread_expressions() {
+    let tests = [
+        ("[...a, 1, ...b]", "[...a, 1, ...b]"),
+        ("[...f(x) + [1]]", "[...(f(x) + [1])]"),
+        ("f(...args)", "f(...args)"),
+        ("f(a, ...[b, c])", "f(a, ...[b, c])"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+    let program = parse_test_program("[1, ...a]", 1);
+    if let Statement::Expr(stmt) = &program.statements[0] {
+        if let Expression::Array(arr) = &stmt.value {
+            assert!(matches!(arr.elements[1], Expression::Spread(_)));
+        } else {
+            panic!("stmt.value is not an array literal. got={}", stmt.value);
+        }
+    }
+}
+
+#[test]
+fn test_slice_expressions() {
+    let tests = [
+        ("a[1:2]", "(a[1:2])"),
+        ("a[1:]", "(a[1:])"),
+        ("a[:-1]", "(a[:(-1)])"),
+        ("a[:]", "(a[:])"),
+        ("a[::-1]", "(a[::(-1)])"),
+        ("a[i + 1:len(a):2]", "(a[(i + 1):len(a):2])"),
+        ("a[1:][0]", "((a[1:])[0])"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+    let program = parse_test_program("a[1:]", 1);
+    if let Statement::Expr(stmt) = &program.statements[0] {
+        if let Expression::Slice(slice) = &stmt.value {
+            assert!(slice.start.is_some());
+            assert!(slice.end.is_none());
+            assert!(slice.step.is_none());
+        } else {
+            panic!("stmt.value is not a slice expression. got={}", stmt.value);
+        }
+    }
+}
+
+#[test]
+fn test_property_and_method_calls() {
+    let tests = [
+        ("config.host", "(config.host)"),
+        ("a.b.c", "((a.b).c)"),
+        ("a.b[0]", "((a.b)[0])"),
+        ("-a.b", "(-(a.b))"),
+        ("s.split(sep, 2)", "s.split(sep, 2)"),
+        ("arr.push(1).len()", "arr.push(1).len()"),
+        ("m.f(...args)", "m.f(...args)"),
+        ("a.b(c)(d)", "a.b(c)(d)"),
+        ("a.b = 1 + 2", "((a.b) = (1 + 2))"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+    let program = parse_test_program("s.split(sep)", 1);
+    if let Statement::Expr(stmt) = &program.statements[0] {
+        if let Expression::MethodCall(call) = &stmt.value {
+            test_identifier(&call.receiver, "s");
+            assert_eq!(call.name.value, "split");
+            assert_eq!(call.args.len(), 1);
+        } else {
+            panic!("stmt.value is not a method call. got={}", stmt.value);
+        }
+    }
+
+    for input in ["a.1", "a.(b)", "a."] {
+        let mut parser = Parser::new(Scanner::new(input));
+        parser.parse_program();
+        assert!(
+            parser
+                .parse_errors()
+                .iter()
+                .any(|e| e.contains("expected next token to be IDENT")),
+            "expected an error for '{}'",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let tests = [
+        ("x |> f", "f(x)"),
+        ("x |> f(a, b)", "f(x, a, b)"),
+        ("x |> f |> g(1)", "g(f(x), 1)"),
+        ("x |> s.split(a)", "s.split(x, a)"),
+        ("x |> f(...a)", "f(x, ...a)"),
+        ("x |> fn(y) { y }", "fn (y) y(x)"),
+        ("a + 1 |> f", "f((a + 1))"),
+        ("a ?? b |> f", "f((a ?? b))"),
+        ("y = x |> f", "(y = f(x))"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_match_expression() {
+    let tests = [
+        ("match (x) { 1 => a }", "match (x) { 1 => { a } }"),
+        (
+            "match (x) { -1 => a, nil => b, _ => c, }",
+            "match (x) { (-1) => { a }, nil => { b }, _ => { c } }",
+        ),
+        (
+            "match (x) { [a, [b], ...r] if a > b => { a } _ => 0 }",
+            "match (x) { [a, [b], ...r] if (a > b) => { a }, _ => { 0 } }",
+        ),
+        (
+            r#"match (x) { {"k": 1, name} => name }"#,
+            "match (x) { {k: 1, name: name} => { name } }",
+        ),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "match (x) { a + 1 => 1 }",
+            "[line 1] expected next token to be =>, got + instead",
+        ),
+        (
+            "match (x) { (a) => 1 }",
+            "[line 1] invalid match pattern '('",
+        ),
+        (
+            "match (x) { [...r, a] => 1 }",
+            "[line 1] expected next token to be ], got , instead",
+        ),
+        (
+            "match (x) { {1: a} => 1 }",
+            "[line 1] invalid map pattern key '1'",
+        ),
+        (
+            "match (x) { 1 => a 2 => b }",
+            "[line 1] expected next token to be }, got INT instead",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e == expected),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_try_and_throw() {
+    let tests = [
+        ("throw x + 1;", "throw (x + 1);"),
+        (
+            "try { f(); } catch (e) { g(e) }",
+            "try { f() } catch (e) { g(e) }",
+        ),
+        ("try { f() } finally { g() }", "try { f() } finally { g() }"),
+        (
+            "try { f() } catch (e) { throw e; } finally { g() }",
+            "try { f() } catch (e) { throw e; } finally { g() }",
+        ),
+        (
+            "try { f() } catch (e) { g(e) };",
+            "try { f() } catch (e) { g(e) }",
+        ),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "try { f() }",
+            "[line 1] try requires a catch or a finally block",
+        ),
+        (
+            "try { f() } catch { g() }",
+            "[line 1] expected next token to be (, got { instead",
+        ),
+        (
+            "try { f() } catch (1) { g() }",
+            "[line 1] expected next token to be IDENT, got INT instead",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e == expected),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_const_statement() {
+    let tests = [
+        ("const x = 5;", "const x = 5;", true),
+        ("const [a, ...b] = c", "const [a, ...b] = c;", true),
+        ("const {a, b} = c;", "const {a, b} = c;", true),
+        ("let x = 5;", "let x = 5;", false),
+    ];
+    for (input, expected, is_const) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+        match &program.statements[0] {
+            Statement::Let(stmt) => assert_eq!(stmt.is_const(), is_const),
+            Statement::Destructure(stmt) => assert_eq!(stmt.is_const(), is_const),
+            stmt => panic!("not a let statement. got={}", stmt),
+        }
+    }
+}
+
+#[test]
+fn test_import_statement() {
+    let tests = [
+        (r#"import "lib.mky" as lib;"#, r#"import "lib.mky" as lib;"#),
+        (r#"import "a/b.mky" as b"#, r#"import "a/b.mky" as b;"#),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "import lib;",
+            "[line 1] expected next token to be STRING, got IDENT instead",
+        ),
+        (
+            r#"import "lib.mky";"#,
+            "[line 1] expected next token to be AS, got ; instead",
+        ),
+        (
+            r#"import "lib.mky" as 1;"#,
+            "[line 1] expected next token to be IDENT, got INT instead",
+        ),
+    ];
+    for (input, expected) in tests {
+        let scanner = Scanner::new(input);
+        let mut parser = Parser::new(scanner);
+        parser.parse_program();
+        let errors = parser.parse_errors();
+        assert!(
+            errors.iter().any(|e| e == expected),
+            "expected error '{}' for '{}'. got={:?}",
+            expected,
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn test_optional_chaining() {
+    let tests = [
+        ("a?.b", "(a?.b)"),
+        ("a?.b?.c", "((a?.b)?.c)"),
+        ("a?.[k]", "(a?.[k])"),
+        ("a?.[1:]", "(a?.[1:])"),
+        ("a?.b(c)", "a?.b(c)"),
+        ("a ?? nil", "(a ?? nil)"),
+    ];
+    for (input, expected) in tests {
+        let program = parse_test_program(input, 1);
+        assert_eq!(program.to_string(), expected);
+    }
+    let program = parse_test_program("a?.[0]", 1);
+    if let Statement::Expr(stmt) = &program.statements[0] {
+        match &stmt.value {
+            Expression::Index(index) => assert!(index.optional),
+            expr => panic!("stmt.value is not an index expression. got={}", expr),
+        }
+    }
+    let program = parse_test_program("nil", 1);
+    if let Statement::Expr(stmt) = &program.statements[0] {
+        assert!(matches!(stmt.value, Expression::Nil));
+    }
+}
+
 #[test]
 fn test_function_literal_with_name() {
     let input = "let myfunc = fn() { }";
@@ -1036,3 +1878,49 @@ fn test_function_literal_with_name() {
         );
     }
 }
+
+#[test]
+fn test_doc_comments() {
+    let input = "
+/// Add two numbers.
+///
+/// Returns their sum.
+let add = fn(a, b) { a + b };
+/// Not attached to an expression
+add(1, 2);
+/* not a doc comment */
+let x = 1;
+/// The answer
+let y = 42;
+";
+    let program = parse_test_program(input, 4);
+    let docs: Vec<Option<&str>> = program
+        .statements
+        .iter()
+        .map(|stmt| match stmt {
+            Statement::Let(stmt) => stmt.doc.as_deref(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        docs,
+        [
+            Some("Add two numbers.\n\nReturns their sum."),
+            None,
+            None,
+            Some("The answer")
+        ]
+    );
+
+    if let Statement::Let(stmt) = &program.statements[0] {
+        if let Expression::Function(func) = &stmt.value {
+            assert_eq!(func.doc, stmt.doc, "doc comment not attached to function");
+        } else {
+            panic!("stmt.value is not a FunctionLiteral. got={}", stmt.value);
+        }
+    }
+    assert_eq!(
+        program.statements[3].to_string(),
+        "/// The answer\nlet y = 42;"
+    );
+}
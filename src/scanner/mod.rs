@@ -10,12 +10,25 @@ lazy_static! {
     static ref KEYWORDS: HashMap<String, TokenType> = {
         let mut m = HashMap::new();
         m.insert("let".into(), TokenType::Let);
+        m.insert("const".into(), TokenType::Const);
         m.insert("fn".into(), TokenType::Function);
         m.insert("true".into(), TokenType::True);
         m.insert("false".into(), TokenType::False);
         m.insert("if".into(), TokenType::If);
         m.insert("else".into(), TokenType::Else);
         m.insert("return".into(), TokenType::Return);
+        m.insert("while".into(), TokenType::While);
+        m.insert("for".into(), TokenType::For);
+        m.insert("in".into(), TokenType::In);
+        m.insert("defer".into(), TokenType::Defer);
+        m.insert("nil".into(), TokenType::Nil);
+        m.insert("match".into(), TokenType::Match);
+        m.insert("throw".into(), TokenType::Throw);
+        m.insert("try".into(), TokenType::Try);
+        m.insert("catch".into(), TokenType::Catch);
+        m.insert("finally".into(), TokenType::Finally);
+        m.insert("import".into(), TokenType::Import);
+        m.insert("as".into(), TokenType::As);
         m
     };
 }
@@ -27,6 +40,8 @@ pub struct Scanner {
     read_position: usize,
     ch: char,
     line: usize,
+    // Number of open braces within each '${ .. }' of the strings being read
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
@@ -37,6 +52,7 @@ impl Scanner {
             read_position: 0,
             ch: '\0',
             line: 1,
+            interpolations: Vec::new(),
         };
         scanner.read_char();
         scanner
@@ -56,7 +72,7 @@ impl Scanner {
     }
 
     // peek_char() does a lookahead in the input for the next character
-    fn peek_char(&mut self) -> char {
+    fn peek_char(&self) -> char {
         if self.read_position >= self.input.len() {
             '\0'
         } else {
@@ -69,28 +85,76 @@ impl Scanner {
     }
 
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
-        self.skip_comments();
+        // Doc comments and unterminated block comments are returned as tokens
+        if let Some(token) = self.skip_comments() {
+            return token;
+        }
 
         let token = match self.ch {
             '\0' => self.make_token(TokenType::Eof, ""),
             ';' => self.make_token_ch(TokenType::Semicolon),
             ',' => self.make_token_ch(TokenType::Comma),
+            '.' if self.peek_char() == '.'
+                && self.input.get(self.read_position + 1) == Some(&'.') =>
+            {
+                self.read_char();
+                self.read_char();
+                self.make_token(TokenType::Ellipsis, "...")
+            }
+            '.' => self.make_token_ch(TokenType::Dot),
             ':' => self.make_token_ch(TokenType::Colon),
             '(' => self.make_token_ch(TokenType::LeftParen),
             ')' => self.make_token_ch(TokenType::RightParen),
-            '{' => self.make_token_ch(TokenType::LeftBrace),
-            '}' => self.make_token_ch(TokenType::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.make_token_ch(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // The end of an interpolated expression resumes the string
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.read_string()
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.make_token_ch(TokenType::RightBrace)
+                }
+                None => self.make_token_ch(TokenType::RightBrace),
+            },
             '[' => self.make_token_ch(TokenType::LeftBracket),
             ']' => self.make_token_ch(TokenType::RightBracket),
             '+' => self.make_token_ch(TokenType::Plus),
             '-' => self.make_token_ch(TokenType::Minus),
-            '*' => self.make_token_ch(TokenType::Asterisk),
+            '*' => self.make_token_twin('*', TokenType::Asterisk, TokenType::Power),
             '/' => self.make_token_ch(TokenType::Slash),
+            '%' => self.make_token_ch(TokenType::Percent),
+            // '//' starts a comment, so floor division is spelled '~/'
+            '~' => self.make_token_twin('/', TokenType::Tilde, TokenType::TildeSlash),
+            '^' => self.make_token_ch(TokenType::Caret),
+            '=' if self.peek_char() == '>' => {
+                self.make_token_twin('>', TokenType::Assign, TokenType::FatArrow)
+            }
             '=' => self.make_token_twin('=', TokenType::Assign, TokenType::Equal),
             '!' => self.make_token_twin('=', TokenType::Bang, TokenType::BangEqual),
+            '<' if self.peek_char() == '<' => {
+                self.make_token_twin('<', TokenType::Less, TokenType::LessLess)
+            }
+            '>' if self.peek_char() == '>' => {
+                self.make_token_twin('>', TokenType::Greater, TokenType::GreaterGreater)
+            }
             '<' => self.make_token_twin('=', TokenType::Less, TokenType::LessEqual),
             '>' => self.make_token_twin('=', TokenType::Greater, TokenType::GreaterEqual),
+            '&' => self.make_token_twin('&', TokenType::Ampersand, TokenType::And),
+            '|' if self.peek_char() == '>' => {
+                self.make_token_twin('>', TokenType::Pipe, TokenType::PipeGreater)
+            }
+            '|' => self.make_token_twin('|', TokenType::Pipe, TokenType::Or),
+            '?' if self.peek_char() == '.' => {
+                self.make_token_twin('.', TokenType::Illegal, TokenType::QuestionDot)
+            }
+            '?' => self.make_token_twin('?', TokenType::Illegal, TokenType::QuestionQuestion),
             '"' => self.read_string(),
             _ => {
                 if Self::is_identifier_first(self.ch) {
@@ -137,27 +201,171 @@ impl Scanner {
         self.make_token(ttype, &identifier)
     }
 
+    // Numbers are decimal integers or fractions with an optional exponent
+    // ('1', '3.14', '1e-9', '2.5E3') or integers with a radix prefix ('0xff',
+    // '0o17', '0b101'). Digits may be separated by single underscores
+    // ('1_000_000'). Integers are returned as 'Integer' tokens and numbers
+    // with a fraction or an exponent as 'Number' tokens. An integer with an
+    // 'n' suffix ('12n', '0xffn') is a big integer. A malformed number such
+    // as '1.2.3' or '0b12' is returned as an illegal token that spans the
+    // whole literal.
     fn read_number(&mut self) -> Token {
         let position = self.position;
-        while self.ch.is_ascii_digit() {
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'o' | 'O') => 8,
+            ('0', 'b' | 'B') => 2,
+            _ => 10,
+        };
+        let mut valid;
+        let mut is_float = false;
+        if radix != 10 {
+            // skip over the radix prefix
+            self.read_char();
+            self.read_char();
+            valid = self.read_digits(radix);
+        } else {
+            valid = self.read_digits(10);
+            if self.ch == '.' && self.peek_char().is_ascii_digit() {
+                self.read_char();
+                valid &= self.read_digits(10);
+                is_float = true;
+            }
+            if self.ch == 'e' || self.ch == 'E' {
+                self.read_char();
+                is_float = true;
+                if self.ch == '+' || self.ch == '-' {
+                    self.read_char();
+                }
+                valid &= self.read_digits(10);
+            }
+        }
+        let is_big =
+            !is_float && self.ch == 'n' && !Self::is_identifier_remaining(self.peek_char());
+        if is_big {
             self.read_char();
         }
+        // A number cannot be immediately followed by another fraction
+        // or by letters, so consume the rest of the malformed literal
+        if (self.ch == '.' && self.peek_char().is_ascii_digit())
+            || Self::is_identifier_remaining(self.ch)
+        {
+            valid = false;
+            while self.ch == '.' || Self::is_identifier_remaining(self.ch) {
+                self.read_char();
+            }
+        }
         let number: String = self.input[position..self.position].iter().collect();
-        self.make_token(TokenType::Number, &number)
+        if !valid {
+            self.make_token(TokenType::Illegal, &number)
+        } else if is_big {
+            self.make_token(TokenType::BigInt, &number)
+        } else if is_float {
+            self.make_token(TokenType::Number, &number)
+        } else {
+            self.make_token(TokenType::Integer, &number)
+        }
     }
 
+    // Read a sequence of digits in the given radix that may be separated by
+    // underscores. Return false if there are no digits or if an underscore
+    // is not placed between two digits.
+    fn read_digits(&mut self, radix: u32) -> bool {
+        let position = self.position;
+        while self.ch.is_digit(radix) || self.ch == '_' {
+            self.read_char();
+        }
+        let digits: String = self.input[position..self.position].iter().collect();
+        !digits.is_empty()
+            && !digits.starts_with('_')
+            && !digits.ends_with('_')
+            && !digits.contains("__")
+    }
+
+    // Read a string literal and translate the escape sequences in it. The
+    // token is reported on the line where the string starts. Unterminated
+    // strings and invalid escape sequences are returned as error tokens.
+    // A string with embedded expressions such as "a ${x} b" is returned as
+    // an 'Interpolation' token ("a "), followed by the tokens of the
+    // expression and the rest of the string (" b") once the closing brace
+    // is reached.
     fn read_string(&mut self) -> Token {
-        // move past the opening quotes (") character
-        let position = self.position + 1;
+        let line = self.line;
+        let mut the_str = String::new();
+        let mut error: Option<String> = None;
         loop {
+            // move past the opening quotes (") or the last character read
             self.read_char();
+            match self.ch {
+                '"' => break,
+                '$' if self.peek_char() == '{' => {
+                    // stop at the '{' so that it is skipped like a closing quote
+                    self.read_char();
+                    self.interpolations.push(0);
+                    return match error {
+                        Some(msg) => Token::new(TokenType::Error, &msg, line),
+                        None => Token::new(TokenType::Interpolation, &the_str, line),
+                    };
+                }
+                '\0' => {
+                    return Token::new(TokenType::Error, "unterminated string", line);
+                }
+                '\n' => {
+                    self.line += 1;
+                    the_str.push(self.ch);
+                }
+                '\\' => {
+                    self.read_char();
+                    if self.ch == '\n' {
+                        self.line += 1;
+                    }
+                    match self.read_escape() {
+                        Ok(ch) => the_str.push(ch),
+                        // keep going to find the end of the string
+                        Err(msg) => error = error.or(Some(msg)),
+                    }
+                }
+                _ => the_str.push(self.ch),
+            }
+        }
+        match error {
+            Some(msg) => Token::new(TokenType::Error, &msg, line),
+            None => Token::new(TokenType::Str, &the_str, line),
+        }
+    }
 
-            if self.ch == '"' || self.ch == '\0' {
-                break;
+    // Translate the escape sequence following a backslash
+    fn read_escape(&mut self) -> Result<char, String> {
+        match self.ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '$' => Ok('$'),
+            'u' => {
+                // unicode code point with 1 to 6 hex digits: '\u{1F600}'
+                if self.peek_char() != '{' {
+                    return Err(String::from("invalid unicode escape sequence"));
+                }
+                self.read_char();
+                let mut digits = String::new();
+                while self.peek_char().is_ascii_hexdigit() {
+                    self.read_char();
+                    digits.push(self.ch);
+                }
+                if self.peek_char() != '}' {
+                    return Err(String::from("invalid unicode escape sequence"));
+                }
+                self.read_char();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid unicode code point '{}'", digits))
             }
+            ch => Err(format!("invalid escape sequence '\\{}'", ch)),
         }
-        let the_str: String = self.input[position..self.position].iter().collect();
-        self.make_token(TokenType::Str, &the_str)
     }
 
     fn is_identifier_first(ch: char) -> bool {
@@ -192,20 +400,64 @@ impl Scanner {
         }
     }
 
-    // skip single line comments
-    fn skip_comments(&mut self) {
+    // Skip whitespace, line comments ('// ...') and block comments
+    // ('/* ... */') which may be nested. A doc comment ('/// ...') is
+    // returned as a 'DocComment' token holding the text after the slashes.
+    // An unterminated block comment is returned as an error token reported
+    // on the line where the comment starts.
+    fn skip_comments(&mut self) -> Option<Token> {
         loop {
-            if self.ch == '/' && self.peek_char() == '/' {
-                loop {
+            self.skip_whitespace();
+            match (self.ch, self.peek_char()) {
+                ('/', '/') => {
+                    let position = self.position;
+                    while self.ch != '\n' && self.ch != '\0' {
+                        self.read_char();
+                    }
+                    let comment: String = self.input[position..self.position].iter().collect();
+                    // '////...' is an ordinary comment, often used as a separator
+                    if let Some(doc) = comment.strip_prefix("///") {
+                        if !doc.starts_with('/') {
+                            let doc = doc.strip_prefix(' ').unwrap_or(doc).trim_end();
+                            return Some(self.make_token(TokenType::DocComment, doc));
+                        }
+                    }
+                }
+                ('/', '*') => {
+                    let line = self.line;
+                    if !self.skip_block_comment() {
+                        let msg = "unterminated block comment";
+                        return Some(Token::new(TokenType::Error, msg, line));
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    // Skip a block comment along with any comments nested in it. Return
+    // false if the end of the input is reached before the comment is closed.
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match (self.ch, self.peek_char()) {
+                ('/', '*') => {
+                    depth += 1;
                     self.read_char();
-                    if self.ch == '\n' || self.ch == '\0' {
-                        break;
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    if depth == 0 {
+                        self.read_char();
+                        return true;
                     }
                 }
-                self.skip_whitespace();
-            } else {
-                break;
+                ('\n', _) => self.line += 1,
+                ('\0', _) => return false,
+                _ => {}
             }
+            self.read_char();
         }
     }
 }
@@ -13,7 +13,7 @@ fn test_next_token() {
             }
             let result = add(five, ten);
 
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -34,12 +34,12 @@ fn test_next_token() {
         ExpectedToken(TokenType::Let, "let"),
         ExpectedToken(TokenType::Identifier, "five"),
         ExpectedToken(TokenType::Assign, "="),
-        ExpectedToken(TokenType::Number, "5"),
+        ExpectedToken(TokenType::Integer, "5"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::Let, "let"),
         ExpectedToken(TokenType::Identifier, "ten"),
         ExpectedToken(TokenType::Assign, "="),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::Let, "let"),
         ExpectedToken(TokenType::Identifier, "add"),
@@ -70,19 +70,19 @@ fn test_next_token() {
         ExpectedToken(TokenType::Minus, "-"),
         ExpectedToken(TokenType::Slash, "/"),
         ExpectedToken(TokenType::Asterisk, "*"),
-        ExpectedToken(TokenType::Number, "5"),
+        ExpectedToken(TokenType::Integer, "5"),
         ExpectedToken(TokenType::Semicolon, ";"),
-        ExpectedToken(TokenType::Number, "5"),
+        ExpectedToken(TokenType::Integer, "5"),
         ExpectedToken(TokenType::Less, "<"),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::Greater, ">"),
-        ExpectedToken(TokenType::Number, "5"),
+        ExpectedToken(TokenType::Integer, "5"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::If, "if"),
         ExpectedToken(TokenType::LeftParen, "("),
-        ExpectedToken(TokenType::Number, "5"),
+        ExpectedToken(TokenType::Integer, "5"),
         ExpectedToken(TokenType::Less, "<"),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::RightParen, ")"),
         ExpectedToken(TokenType::LeftBrace, "{"),
         ExpectedToken(TokenType::Return, "return"),
@@ -95,20 +95,20 @@ fn test_next_token() {
         ExpectedToken(TokenType::False, "false"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::RightBrace, "}"),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::Equal, "=="),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::Semicolon, ";"),
-        ExpectedToken(TokenType::Number, "10"),
+        ExpectedToken(TokenType::Integer, "10"),
         ExpectedToken(TokenType::BangEqual, "!="),
-        ExpectedToken(TokenType::Number, "9"),
+        ExpectedToken(TokenType::Integer, "9"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::Str, "foobar"),
         ExpectedToken(TokenType::Str, "foo bar"),
         ExpectedToken(TokenType::LeftBracket, "["),
-        ExpectedToken(TokenType::Number, "1"),
+        ExpectedToken(TokenType::Integer, "1"),
         ExpectedToken(TokenType::Comma, ","),
-        ExpectedToken(TokenType::Number, "2"),
+        ExpectedToken(TokenType::Integer, "2"),
         ExpectedToken(TokenType::RightBracket, "]"),
         ExpectedToken(TokenType::Semicolon, ";"),
         ExpectedToken(TokenType::LeftBrace, "{"),
@@ -137,3 +137,430 @@ fn test_next_token() {
         }
     }
 }
+
+#[test]
+fn test_logical_operators() {
+    let input = "a && b || c & d";
+    let tests = [
+        (TokenType::Identifier, "a"),
+        (TokenType::And, "&&"),
+        (TokenType::Identifier, "b"),
+        (TokenType::Or, "||"),
+        (TokenType::Identifier, "c"),
+        (TokenType::Ampersand, "&"),
+        (TokenType::Identifier, "d"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_arithmetic_operators() {
+    let input = "a % b ~/ c ** d * e ~ f";
+    let tests = [
+        (TokenType::Identifier, "a"),
+        (TokenType::Percent, "%"),
+        (TokenType::Identifier, "b"),
+        (TokenType::TildeSlash, "~/"),
+        (TokenType::Identifier, "c"),
+        (TokenType::Power, "**"),
+        (TokenType::Identifier, "d"),
+        (TokenType::Asterisk, "*"),
+        (TokenType::Identifier, "e"),
+        (TokenType::Tilde, "~"),
+        (TokenType::Identifier, "f"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let input = "a & b | c ^ ~d << 2 >> 1 <= e >= f";
+    let tests = [
+        (TokenType::Identifier, "a"),
+        (TokenType::Ampersand, "&"),
+        (TokenType::Identifier, "b"),
+        (TokenType::Pipe, "|"),
+        (TokenType::Identifier, "c"),
+        (TokenType::Caret, "^"),
+        (TokenType::Tilde, "~"),
+        (TokenType::Identifier, "d"),
+        (TokenType::LessLess, "<<"),
+        (TokenType::Integer, "2"),
+        (TokenType::GreaterGreater, ">>"),
+        (TokenType::Integer, "1"),
+        (TokenType::LessEqual, "<="),
+        (TokenType::Identifier, "e"),
+        (TokenType::GreaterEqual, ">="),
+        (TokenType::Identifier, "f"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_bigint_literals() {
+    let input = "12n 1_000n 0xffn 1.5n 12nx 1e3n n";
+    let tests = [
+        (TokenType::BigInt, "12n"),
+        (TokenType::BigInt, "1_000n"),
+        (TokenType::BigInt, "0xffn"),
+        (TokenType::Illegal, "1.5n"),
+        (TokenType::Illegal, "12nx"),
+        (TokenType::Illegal, "1e3n"),
+        (TokenType::Identifier, "n"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_numeric_literals() {
+    let input = "3.14 1e-9 2.5E+3 1_000_000 0xff 0o17 0b101 1.2.3 1__0 1_ 0x 0b12 12ab 1e 1.foo";
+    let tests = [
+        (TokenType::Number, "3.14"),
+        (TokenType::Number, "1e-9"),
+        (TokenType::Number, "2.5E+3"),
+        (TokenType::Integer, "1_000_000"),
+        (TokenType::Integer, "0xff"),
+        (TokenType::Integer, "0o17"),
+        (TokenType::Integer, "0b101"),
+        (TokenType::Illegal, "1.2.3"),
+        (TokenType::Illegal, "1__0"),
+        (TokenType::Illegal, "1_"),
+        (TokenType::Illegal, "0x"),
+        (TokenType::Illegal, "0b12"),
+        (TokenType::Illegal, "12ab"),
+        (TokenType::Illegal, "1e"),
+        // A fraction needs digits after the '.'
+        (TokenType::Integer, "1"),
+        (TokenType::Dot, "."),
+        (TokenType::Identifier, "foo"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_string_escapes() {
+    let input =
+        r#""a\tb\nc" "\"quoted\"" "back\\slash" "\r" "\u{41}\u{1F600}" "x\qy" "\u{110000}" "\u41""#;
+    let tests = [
+        (TokenType::Str, "a\tb\nc"),
+        (TokenType::Str, "\"quoted\""),
+        (TokenType::Str, "back\\slash"),
+        (TokenType::Str, "\r"),
+        (TokenType::Str, "A\u{1F600}"),
+        (TokenType::Error, "invalid escape sequence '\\q'"),
+        (TokenType::Error, "invalid unicode code point '110000'"),
+        (TokenType::Error, "invalid unicode escape sequence"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_string_lines() {
+    let input = "\"one\ntwo\" x\n\"three";
+    let mut scanner = Scanner::new(input);
+
+    let token = scanner.next_token();
+    assert_eq!(token.ttype, TokenType::Str);
+    assert_eq!(token.literal, "one\ntwo");
+    assert_eq!(token.line, 1, "strings are reported on their first line");
+    let token = scanner.next_token();
+    assert_eq!(token.ttype, TokenType::Identifier);
+    assert_eq!(token.line, 2, "newlines in strings must be counted");
+    let token = scanner.next_token();
+    assert_eq!(token.ttype, TokenType::Error);
+    assert_eq!(token.literal, "unterminated string");
+    assert_eq!(token.line, 3);
+}
+
+#[test]
+fn test_ellipsis() {
+    let input = "[a, ...rest] .. . a.b";
+    let tests = [
+        (TokenType::LeftBracket, "["),
+        (TokenType::Identifier, "a"),
+        (TokenType::Comma, ","),
+        (TokenType::Ellipsis, "..."),
+        (TokenType::Identifier, "rest"),
+        (TokenType::RightBracket, "]"),
+        (TokenType::Dot, "."),
+        (TokenType::Dot, "."),
+        (TokenType::Dot, "."),
+        (TokenType::Identifier, "a"),
+        (TokenType::Dot, "."),
+        (TokenType::Identifier, "b"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_pipeline_operator() {
+    let input = "a |> f(b) | c || d";
+    let tests = [
+        (TokenType::Identifier, "a"),
+        (TokenType::PipeGreater, "|>"),
+        (TokenType::Identifier, "f"),
+        (TokenType::LeftParen, "("),
+        (TokenType::Identifier, "b"),
+        (TokenType::RightParen, ")"),
+        (TokenType::Pipe, "|"),
+        (TokenType::Identifier, "c"),
+        (TokenType::Or, "||"),
+        (TokenType::Identifier, "d"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_match_tokens() {
+    let input = "match (x) { 1 => a, _ => b } == >=";
+    let tests = [
+        (TokenType::Match, "match"),
+        (TokenType::LeftParen, "("),
+        (TokenType::Identifier, "x"),
+        (TokenType::RightParen, ")"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::Integer, "1"),
+        (TokenType::FatArrow, "=>"),
+        (TokenType::Identifier, "a"),
+        (TokenType::Comma, ","),
+        (TokenType::Identifier, "_"),
+        (TokenType::FatArrow, "=>"),
+        (TokenType::Identifier, "b"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::Equal, "=="),
+        (TokenType::GreaterEqual, ">="),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_exception_keywords() {
+    let input = "try { throw e; } catch (e) { } finally { }";
+    let tests = [
+        (TokenType::Try, "try"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::Throw, "throw"),
+        (TokenType::Identifier, "e"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::Catch, "catch"),
+        (TokenType::LeftParen, "("),
+        (TokenType::Identifier, "e"),
+        (TokenType::RightParen, ")"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::Finally, "finally"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_const_keyword() {
+    let input = "const x = 1;";
+    let tests = [
+        (TokenType::Const, "const"),
+        (TokenType::Identifier, "x"),
+        (TokenType::Assign, "="),
+        (TokenType::Integer, "1"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_import_keywords() {
+    let input = r#"import "lib.mky" as lib;"#;
+    let tests = [
+        (TokenType::Import, "import"),
+        (TokenType::Str, "lib.mky"),
+        (TokenType::As, "as"),
+        (TokenType::Identifier, "lib"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_nil_operators() {
+    let input = "a?.b ?? nil ? c";
+    let tests = [
+        (TokenType::Identifier, "a"),
+        (TokenType::QuestionDot, "?."),
+        (TokenType::Identifier, "b"),
+        (TokenType::QuestionQuestion, "??"),
+        (TokenType::Nil, "nil"),
+        (TokenType::Illegal, "?"),
+        (TokenType::Identifier, "c"),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
+
+#[test]
+fn test_comments() {
+    let input = "a // line comment
+/* block
+   comment */ b /* nested /* block */ comment */ c
+//// separator
+///  Doc comment
+let";
+    let tests = [
+        (TokenType::Identifier, "a", 1),
+        (TokenType::Identifier, "b", 3),
+        (TokenType::Identifier, "c", 3),
+        (TokenType::DocComment, " Doc comment", 5),
+        (TokenType::Let, "let", 6),
+        (TokenType::Eof, "", 6),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal, line)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+        assert_eq!(token.line, *line, "tests[{}] - line wrong", i);
+    }
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let input = "x\n/* one /* two */\n three";
+    let mut scanner = Scanner::new(input);
+
+    let token = scanner.next_token();
+    assert_eq!(token.ttype, TokenType::Identifier);
+    let token = scanner.next_token();
+    assert_eq!(token.ttype, TokenType::Error);
+    assert_eq!(token.literal, "unterminated block comment");
+    assert_eq!(
+        token.line, 2,
+        "reported on the line where the comment starts"
+    );
+    assert_eq!(scanner.next_token().ttype, TokenType::Eof);
+}
+
+#[test]
+fn test_string_interpolation() {
+    let input = r#""a ${x + 1} b ${ {"k": "v"}["k"] }" "${"in ${y}"}""#;
+    let tests = [
+        (TokenType::Interpolation, "a "),
+        (TokenType::Identifier, "x"),
+        (TokenType::Plus, "+"),
+        (TokenType::Integer, "1"),
+        (TokenType::Interpolation, " b "),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::Str, "k"),
+        (TokenType::Colon, ":"),
+        (TokenType::Str, "v"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::LeftBracket, "["),
+        (TokenType::Str, "k"),
+        (TokenType::RightBracket, "]"),
+        (TokenType::Str, ""),
+        (TokenType::Interpolation, ""),
+        (TokenType::Interpolation, "in "),
+        (TokenType::Identifier, "y"),
+        (TokenType::Str, ""),
+        (TokenType::Str, ""),
+        (TokenType::Eof, ""),
+    ];
+
+    let mut scanner = Scanner::new(input);
+    for (i, (ttype, literal)) in tests.iter().enumerate() {
+        let token = scanner.next_token();
+        assert_eq!(token.ttype, *ttype, "tests[{}] - tokentype wrong", i);
+        assert_eq!(token.literal, *literal, "tests[{}] - literal wrong", i);
+    }
+}
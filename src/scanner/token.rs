@@ -38,11 +38,16 @@ impl Default for Token {
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub enum TokenType {
     Illegal = 0,
+    Error, // literal holds the error message reported by the scanner
     Eof,
     // Identifiers + literals
     Identifier,
+    Integer,
+    BigInt,
     Number,
     Str,
+    Interpolation, // part of a string that is followed by '${'
+    DocComment,    // text of a '///' comment
     // Operators
     Assign,
     Plus,
@@ -50,13 +55,30 @@ pub enum TokenType {
     Bang,
     Asterisk,
     Slash,
+    Percent,
+    Power,
+    TildeSlash,
     Less,
     LessEqual,
     Greater,
     GreaterEqual,
     Equal,
+    FatArrow,
     BangEqual,
+    And,
+    Or,
+    QuestionQuestion,
+    Ampersand,
+    Pipe,
+    PipeGreater,
+    Caret,
+    Tilde,
+    LessLess,
+    GreaterGreater,
     // Delimiters
+    Ellipsis,
+    Dot,
+    QuestionDot,
     Comma,
     Colon,
     Semicolon,
@@ -69,11 +91,24 @@ pub enum TokenType {
     // Keywords
     Function,
     Let,
+    Const,
     True,
     False,
     If,
     Else,
     Return,
+    While,
+    For,
+    In,
+    Defer,
+    Nil,
+    Match,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    As,
     NumberOfTokens,
 }
 
@@ -81,22 +116,44 @@ impl From<TokenType> for &'static str {
     fn from(ttype: TokenType) -> &'static str {
         match ttype {
             TokenType::Illegal => "ILLEGAL",
+            TokenType::Error => "ERROR",
             TokenType::Eof => "EOF",
             TokenType::Identifier => "IDENT",
-            TokenType::Number => "INT",
+            TokenType::Integer => "INT",
+            TokenType::BigInt => "BIGINT",
+            TokenType::Number => "FLOAT",
             TokenType::Str => "STRING",
+            TokenType::Interpolation => "INTERPOLATION",
+            TokenType::DocComment => "DOC_COMMENT",
             TokenType::Assign => "=",
             TokenType::Plus => "+",
             TokenType::Minus => "-",
             TokenType::Bang => "!",
             TokenType::Asterisk => "*",
             TokenType::Slash => "/",
+            TokenType::Percent => "%",
+            TokenType::Power => "**",
+            TokenType::TildeSlash => "~/",
             TokenType::Less => "<",
             TokenType::LessEqual => "<=",
             TokenType::Greater => ">",
             TokenType::GreaterEqual => ">=",
             TokenType::Equal => "==",
+            TokenType::FatArrow => "=>",
             TokenType::BangEqual => "!=",
+            TokenType::And => "&&",
+            TokenType::Or => "||",
+            TokenType::QuestionQuestion => "??",
+            TokenType::Ampersand => "&",
+            TokenType::Pipe => "|",
+            TokenType::PipeGreater => "|>",
+            TokenType::Caret => "^",
+            TokenType::Tilde => "~",
+            TokenType::LessLess => "<<",
+            TokenType::GreaterGreater => ">>",
+            TokenType::Ellipsis => "...",
+            TokenType::Dot => ".",
+            TokenType::QuestionDot => "?.",
             TokenType::Comma => ",",
             TokenType::Colon => ":",
             TokenType::Semicolon => ";",
@@ -108,11 +165,24 @@ impl From<TokenType> for &'static str {
             TokenType::RightBracket => "]",
             TokenType::Function => "FUNCTION",
             TokenType::Let => "LET",
+            TokenType::Const => "CONST",
             TokenType::True => "TRUE",
             TokenType::False => "FALSE",
             TokenType::If => "IF",
             TokenType::Else => "ELSE",
             TokenType::Return => "RETURN",
+            TokenType::While => "WHILE",
+            TokenType::For => "FOR",
+            TokenType::In => "IN",
+            TokenType::Defer => "DEFER",
+            TokenType::Nil => "NIL",
+            TokenType::Match => "MATCH",
+            TokenType::Throw => "THROW",
+            TokenType::Try => "TRY",
+            TokenType::Catch => "CATCH",
+            TokenType::Finally => "FINALLY",
+            TokenType::Import => "IMPORT",
+            TokenType::As => "AS",
             TokenType::NumberOfTokens => "",
         }
     }
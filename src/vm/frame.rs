@@ -1,18 +1,47 @@
 use std::rc::Rc;
 
 use crate::code::definitions::Instructions;
+use crate::common::error::RTError;
 use crate::common::object::Closure;
 
 #[derive(Debug, Clone, Default)]
 pub struct Frame {
     pub closure: Rc<Closure>,
-    pub ip: usize, // instruction pointer
-    pub bp: usize, // base pointer
+    pub ip: usize,              // instruction pointer
+    pub bp: usize,              // base pointer
+    pub handlers: Vec<Handler>, // exception handlers of the enclosing try blocks
+    pub deferred: Vec<usize>,   // positions of the registered deferred expressions
+    pub resume: Option<Resume>, // where to continue after a deferred expression
+}
+
+// A function that is left evaluates its deferred expressions one at a time,
+// the last registered first. After each one, the VM either executes the
+// return instruction again, which evaluates the next one, or passes on
+// the error that is leaving the function.
+#[derive(Debug, Clone)]
+pub enum Resume {
+    Return(usize),
+    Error(RTError),
+}
+
+// An error raised in a try block continues at 'ip' with the stack pointer
+// restored to 'sp', the stack pointer when the try block was entered
+#[derive(Debug, Clone, Copy)]
+pub struct Handler {
+    pub ip: usize,
+    pub sp: usize,
 }
 
 impl Frame {
     pub fn new(closure: Rc<Closure>, bp: usize) -> Frame {
-        Frame { closure, ip: 0, bp }
+        Frame {
+            closure,
+            ip: 0,
+            bp,
+            handlers: Vec::new(),
+            deferred: Vec::new(),
+            resume: None,
+        }
     }
 
     pub fn instructions(&self) -> &Rc<Instructions> {
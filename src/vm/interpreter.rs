@@ -1,19 +1,25 @@
 use byteorder::BigEndian;
 use byteorder::ByteOrder;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::code::opcode::Opcode;
+use crate::common::builtins::lookup_method;
 use crate::common::builtins::BUILTINS;
 use crate::common::error::RTError;
+use crate::common::object::check_arity;
 use crate::common::object::Array;
 use crate::common::object::BuiltinFunction;
 use crate::common::object::Closure;
 use crate::common::object::CompiledFunction;
 use crate::common::object::HMap;
+use crate::common::object::Iter;
 use crate::common::object::Object;
 use crate::compiler::Bytecode;
 use crate::vm::frame::Frame;
+use crate::vm::frame::Handler;
+use crate::vm::frame::Resume;
 
 const STACK_SIZE: usize = 4096;
 const MAX_FRAMES: usize = 4096;
@@ -39,6 +45,9 @@ enum BinaryOperation {
     Sub,
     Mul,
     Div,
+    Mod,
+    FloorDiv,
+    Pow,
     Greater,
 }
 
@@ -137,12 +146,47 @@ impl VM {
         println!();
     }
 
+    // Run the program. An error that is raised in a try block continues at
+    // its handler, and any other error stops the program.
+    pub fn run(&mut self) -> Result<(), RTError> {
+        while let Err(err) = self.execute() {
+            self.unwind(err)?;
+        }
+        Ok(())
+    }
+
+    // Transfer control to the innermost exception handler, dropping the
+    // frames of the functions that have no handler. The handler finds the
+    // stack as it was when its try block was entered, with the error on top.
+    fn unwind(&mut self, err: RTError) -> Result<(), RTError> {
+        for index in (0..self.frames_index).rev() {
+            let frame = &mut self.frames[index];
+            if let Some(handler) = frame.handlers.pop() {
+                self.frames_index = index + 1;
+                frame.ip = handler.ip;
+                self.sp = handler.sp;
+                let line = err.line;
+                return self.push(err.into_object(), line);
+            }
+            // A function evaluates its deferred expressions before the error
+            // leaves it. An error raised by one of them replaces this one.
+            if let Some(ip) = frame.deferred.pop() {
+                self.frames_index = index + 1;
+                frame.ip = ip;
+                frame.resume = Some(Resume::Error(err));
+                self.sp = frame.bp + frame.closure.func.num_locals;
+                return Ok(());
+            }
+        }
+        Err(err)
+    }
+
     /*
      * The main run loop for the interpreter. Since this is the hot path,
      * do not use functions such as lookup() or read_operands() for decoding
      * instructions and operands.
      */
-    pub fn run(&mut self) -> Result<(), RTError> {
+    fn execute(&mut self) -> Result<(), RTError> {
         while self.current_frame().ip < self.current_frame().instructions().len() {
             // Helpers
             let ip = self.current_frame().ip;
@@ -171,16 +215,25 @@ impl VM {
                     self.pop(line)?;
                 }
                 Opcode::Add => {
-                    self.binary_op(BinaryOperation::Add, |a, b| a + b, line)?;
+                    self.binary_op(BinaryOperation::Add, |a, b| a.checked_add(b), line)?;
                 }
                 Opcode::Sub => {
-                    self.binary_op(BinaryOperation::Sub, |a, b| a - b, line)?;
+                    self.binary_op(BinaryOperation::Sub, |a, b| a.checked_sub(b), line)?;
                 }
                 Opcode::Mul => {
-                    self.binary_op(BinaryOperation::Mul, |a, b| a * b, line)?;
+                    self.binary_op(BinaryOperation::Mul, |a, b| a.checked_mul(b), line)?;
                 }
                 Opcode::Div => {
-                    self.binary_op(BinaryOperation::Div, |a, b| a / b, line)?;
+                    self.binary_op(BinaryOperation::Div, |a, b| a.checked_div(b), line)?;
+                }
+                Opcode::Mod => {
+                    self.binary_op(BinaryOperation::Mod, |a, b| a.checked_rem(b), line)?;
+                }
+                Opcode::FloorDiv => {
+                    self.binary_op(BinaryOperation::FloorDiv, |a, b| a.floor_div(b), line)?;
+                }
+                Opcode::Pow => {
+                    self.binary_op(BinaryOperation::Pow, |a, b| a.pow(b), line)?;
                 }
                 Opcode::True => self.push(Rc::new(Object::Bool(true)), line)?,
                 Opcode::False => self.push(Rc::new(Object::Bool(false)), line)?,
@@ -195,16 +248,131 @@ impl VM {
                     self.push(Rc::new(Object::Bool(a != b)), line)?;
                 }
                 Opcode::Greater => {
-                    self.binary_op(BinaryOperation::Greater, |a, b| Object::Bool(a > b), line)?;
+                    self.binary_op(
+                        BinaryOperation::Greater,
+                        |a, b| Ok(Object::Bool(a > b)),
+                        line,
+                    )?;
                 }
                 Opcode::Minus => {
                     if !self.peek(0).is_number() {
                         return Err(RTError::new("Operand must be a number", line));
                     }
-                    let obj = self.pop(line)?.clone();
-                    let val = -&*obj;
+                    let obj = self.pop(line)?;
+                    let val = obj.checked_neg().map_err(|msg| RTError::new(&msg, line))?;
                     self.push(Rc::new(val), line)?;
                 }
+                Opcode::BitAnd => self.bitwise_op(|a, b| Some(a & b), line)?,
+                Opcode::BitOr => self.bitwise_op(|a, b| Some(a | b), line)?,
+                Opcode::BitXor => self.bitwise_op(|a, b| Some(a ^ b), line)?,
+                Opcode::ShiftLeft => {
+                    self.bitwise_op(
+                        |a, b| u32::try_from(b).ok().and_then(|b| a.checked_shl(b)),
+                        line,
+                    )?;
+                }
+                Opcode::ShiftRight => {
+                    self.bitwise_op(
+                        |a, b| u32::try_from(b).ok().and_then(|b| a.checked_shr(b)),
+                        line,
+                    )?;
+                }
+                Opcode::UnpackArray => {
+                    // Decode first operand (number of names before the rest name)
+                    let count = BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    // Decode second operand (whether the pattern has a rest name)
+                    let rest = instructions.code[ip + 3] != 0;
+                    self.current_frame().ip += 3;
+                    let obj = self.pop(line)?;
+                    let values = obj
+                        .unpack_array(count, rest)
+                        .map_err(|e| RTError::new(&e, line))?;
+                    for value in values {
+                        self.push(value, line)?;
+                    }
+                }
+                Opcode::UnpackMap => {
+                    // The keys are on top of the stack and the map is below them
+                    let count = BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    self.current_frame().ip += 2;
+                    let mut keys = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        keys.push(self.pop(line)?);
+                    }
+                    keys.reverse();
+                    let obj = self.pop(line)?;
+                    let values = obj.unpack_map(&keys).map_err(|e| RTError::new(&e, line))?;
+                    for value in values {
+                        self.push(value, line)?;
+                    }
+                }
+                Opcode::MatchArray => {
+                    // Test if the value has the shape of an array pattern
+                    let count = BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    let rest = instructions.code[ip + 3] != 0;
+                    self.current_frame().ip += 3;
+                    let obj = self.pop(line)?;
+                    let matched = obj.unpack_array(count, rest).is_ok();
+                    self.push(Rc::new(Object::Bool(matched)), line)?;
+                }
+                Opcode::MatchMap => {
+                    // Test if the map below the keys has all of them
+                    let count = BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    self.current_frame().ip += 2;
+                    let mut keys = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        keys.push(self.pop(line)?);
+                    }
+                    let obj = self.pop(line)?;
+                    let matched = obj.unpack_map(&keys).is_ok();
+                    self.push(Rc::new(Object::Bool(matched)), line)?;
+                }
+                Opcode::SetupTry => {
+                    let handler_ip =
+                        BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    self.current_frame().ip += 2;
+                    let sp = self.sp;
+                    self.current_frame()
+                        .handlers
+                        .push(Handler { ip: handler_ip, sp });
+                }
+                Opcode::PopTry => {
+                    self.current_frame().handlers.pop();
+                }
+                Opcode::Defer => {
+                    // Register the expression that follows and skip over it
+                    let after_pos =
+                        BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    let frame = self.current_frame();
+                    frame.deferred.push(ip + 3);
+                    frame.ip = after_pos;
+                    continue;
+                }
+                Opcode::EndDefer => match self.current_frame().resume.take() {
+                    Some(Resume::Return(ret_pos)) => {
+                        self.current_frame().ip = ret_pos;
+                        continue;
+                    }
+                    Some(Resume::Error(err)) => return Err(err),
+                    None => return Err(RTError::new("no deferred expression to end", line)),
+                },
+                Opcode::Throw => {
+                    let value = self.pop(line)?;
+                    return Err(RTError::thrown(value, line));
+                }
+                Opcode::NoMatch => {
+                    let obj = self.pop(line)?;
+                    return Err(RTError::new(&format!("no match arm for {}", obj), line));
+                }
+                Opcode::BitNot => {
+                    let obj = self.pop(line)?;
+                    match obj.as_integer() {
+                        Some(n) => self.push(Rc::new(Object::Integer(!n)), line)?,
+                        None => {
+                            return Err(RTError::new("bitwise operand must be an integer", line));
+                        }
+                    }
+                }
                 Opcode::Bang => {
                     let obj = self.pop(line)?;
                     self.push(Rc::new(Object::Bool(obj.is_falsey())), line)?;
@@ -212,7 +380,10 @@ impl VM {
                 Opcode::Jump => {
                     let bytes = &instructions.code[ip + 1..ip + 3];
                     // decode the operand (jump address) right after the opcode
-                    self.current_frame().ip = u16::from_be_bytes([bytes[0], bytes[1]]) as usize - 1;
+                    self.current_frame().ip = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+                    // 'ip' already points to the target which may be the very first
+                    // instruction (e.g. a loop at the start), so do not increment it
+                    continue;
                 }
                 Opcode::JumpIfFalse => {
                     let bytes = &instructions.code[ip + 1..ip + 3];
@@ -222,7 +393,31 @@ impl VM {
                     self.current_frame().ip += 2;
                     let condition = self.pop(line)?;
                     if condition.is_falsey() {
-                        self.current_frame().ip = pos - 1;
+                        self.current_frame().ip = pos;
+                        continue;
+                    }
+                }
+                Opcode::JumpIfTrue => {
+                    let bytes = &instructions.code[ip + 1..ip + 3];
+                    // decode the operand (jump address) right after the opcode
+                    let pos: usize = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+                    // skip over the two bytes of the operand in the next cycle
+                    self.current_frame().ip += 2;
+                    let condition = self.pop(line)?;
+                    if !condition.is_falsey() {
+                        self.current_frame().ip = pos;
+                        continue;
+                    }
+                }
+                Opcode::JumpIfNil | Opcode::JumpIfNotNil => {
+                    let bytes = &instructions.code[ip + 1..ip + 3];
+                    let pos: usize = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+                    self.current_frame().ip += 2;
+                    // The value is left on the stack as the result if the jump is taken
+                    let is_nil = matches!(*self.stack[self.sp - 1], Object::Nil);
+                    if is_nil == (op == Opcode::JumpIfNil) {
+                        self.current_frame().ip = pos;
+                        continue;
                     }
                 }
                 Opcode::Nil => {
@@ -249,29 +444,121 @@ impl VM {
                     // pop 'num_elements' off the stack
                     self.sp -= num_elements;
                     // Push the array back onto the stack as an object
-                    self.push(Rc::new(Object::Arr(Rc::new(Array { elements }))), line)?;
+                    self.push(Rc::new(Object::Arr(Rc::new(Array::new(elements)))), line)?;
                     // skip over the two bytes of the operand in the next cycle
                     self.current_frame().ip += 2;
                 }
+                Opcode::Concat => {
+                    // Read the first operand i.e. the number of parts of the string
+                    let num_parts = BigEndian::read_u16(&instructions.code[ip + 1..]) as usize;
+                    let the_str: String = self.stack[self.sp - num_parts..self.sp]
+                        .iter()
+                        .map(|part| part.to_string())
+                        .collect();
+                    // pop 'num_parts' off the stack
+                    self.sp -= num_parts;
+                    self.current_frame().ip += 2;
+                    self.push(Rc::new(Object::Str(the_str)), line)?;
+                }
                 Opcode::Map => {
                     // Read the first operand i.e. the number of pairs
                     let num_elements =
                         BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    #[allow(clippy::mutable_key_type)]
                     let pairs = self.build_map(self.sp - num_elements, self.sp);
                     // pop 'num_elements' off the stack
                     self.sp -= num_elements;
                     // Push the array back onto the stack as an object
-                    self.push(Rc::new(Object::Map(Rc::new(HMap { pairs }))), line)?;
+                    self.push(Rc::new(Object::Map(Rc::new(HMap::new(pairs)))), line)?;
                     // skip over the two bytes of the operand in the next cycle
                     self.current_frame().ip += 2;
                 }
                 Opcode::Call => {
                     let num_args = instructions.code[ip + 1] as usize;
+                    // skip over the operand, the call skips over the instruction
+                    self.current_frame().ip += 1;
                     self.exec_call(num_args, line)?;
                     // Do not increment ip here since the vm is using a new frame
                     // and 'ip' should point to the first instruction in that frame
                     continue;
                 }
+                Opcode::CallSpread => {
+                    // The arguments are collected in an array on top of the stack
+                    let args = self.pop(line)?;
+                    let args = match args.as_ref() {
+                        Object::Arr(arr) => arr.elements.borrow().clone(),
+                        _ => return Err(RTError::new("arguments not found", line)),
+                    };
+                    let num_args = args.len();
+                    for arg in args {
+                        self.push(arg, line)?;
+                    }
+                    self.exec_call(num_args, line)?;
+                    continue;
+                }
+                Opcode::Invoke => {
+                    let name_index =
+                        BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    let num_args = instructions.code[ip + 3] as usize;
+                    let has_binding = instructions.code[ip + 4] == 1;
+                    // skip over the operands, the call skips over the instruction
+                    self.current_frame().ip += 4;
+                    let binding = if has_binding {
+                        Some(self.pop(line)?)
+                    } else {
+                        None
+                    };
+                    self.exec_invoke(name_index, num_args, binding, line)?;
+                    continue;
+                }
+                Opcode::InvokeSpread => {
+                    let name_index =
+                        BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    let has_binding = instructions.code[ip + 3] == 1;
+                    self.current_frame().ip += 3;
+                    let binding = if has_binding {
+                        Some(self.pop(line)?)
+                    } else {
+                        None
+                    };
+                    // The arguments are collected in an array on top of the stack
+                    let args = self.pop(line)?;
+                    let args = match args.as_ref() {
+                        Object::Arr(arr) => arr.elements.borrow().clone(),
+                        _ => return Err(RTError::new("arguments not found", line)),
+                    };
+                    let num_args = args.len();
+                    for arg in args {
+                        self.push(arg, line)?;
+                    }
+                    self.exec_invoke(name_index, num_args, binding, line)?;
+                    continue;
+                }
+                Opcode::Spread => {
+                    // Append the elements of the array on top of the stack to
+                    // the array being built below it
+                    let value = self.pop(line)?;
+                    let Object::Arr(values) = value.as_ref() else {
+                        return Err(RTError::new("spread operand must be an array", line));
+                    };
+                    match self.peek(0).as_ref() {
+                        Object::Arr(arr) => arr
+                            .elements
+                            .borrow_mut()
+                            .extend(values.elements.borrow().iter().cloned()),
+                        _ => return Err(RTError::new("array not found", line)),
+                    }
+                }
+                Opcode::ReturnValue | Opcode::Return
+                    if !self.current_frame().deferred.is_empty() =>
+                {
+                    // Evaluate the last registered deferred expression. The
+                    // return value, if any, stays on the stack meanwhile.
+                    let frame = self.current_frame();
+                    frame.resume = Some(Resume::Return(ip));
+                    frame.ip = frame.deferred.pop().unwrap_or_default();
+                    continue;
+                }
                 Opcode::ReturnValue => {
                     let ret_val = self.pop(line)?;
                     let frame = self.pop_frame();
@@ -300,12 +587,46 @@ impl VM {
                     let left = self.pop(line)?;
                     self.exec_index_expr(left, index, line)?;
                 }
+                Opcode::Slice => {
+                    // The step and the bounds are on top, the expression being sliced is below
+                    let step = self.pop(line)?;
+                    let end = self.pop(line)?;
+                    let start = self.pop(line)?;
+                    let left = self.pop(line)?;
+                    let result = left
+                        .slice(&start, &end, &step)
+                        .map_err(|msg| RTError::new(&msg, line))?;
+                    self.push(Rc::new(result), line)?;
+                }
+                Opcode::GetProperty => {
+                    let name_index =
+                        BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    self.current_frame().ip += 2;
+                    let name = self.constants[name_index].to_string();
+                    let left = self.pop(line)?;
+                    let value = left
+                        .get_property(&name)
+                        .map_err(|msg| RTError::new(&msg, line))?;
+                    self.push(value, line)?;
+                }
+                Opcode::SetIndex => {
+                    // The value is on top, the index and the object being indexed are below
+                    let value = self.pop(line)?;
+                    let index = self.pop(line)?;
+                    let left = self.pop(line)?;
+                    self.exec_set_index_expr(&left, index, value.clone(), line)?;
+                    self.push(value, line)?;
+                }
                 Opcode::GetLocal => {
                     // decode the operand (index to locals)
                     let locals_index = instructions.code[ip + 1] as usize;
                     self.current_frame().ip += 1;
                     let bp = self.current_frame().bp;
-                    let obj = self.stack[bp + locals_index].clone();
+                    // A local captured by a closure lives in a cell
+                    let obj = match self.stack[bp + locals_index].as_ref() {
+                        Object::Cell(cell) => cell.borrow().clone(),
+                        _ => self.stack[bp + locals_index].clone(),
+                    };
                     self.push(obj, line)?;
                 }
                 Opcode::SetLocal => {
@@ -313,8 +634,13 @@ impl VM {
                     let locals_index = instructions.code[ip + 1] as usize;
                     self.current_frame().ip += 1;
                     let bp = self.current_frame().bp;
-                    // Create the local binding
-                    self.stack[bp + locals_index] = self.pop(line)?;
+                    let obj = self.pop(line)?;
+                    // Write through the cell if the local was captured by a closure
+                    if let Object::Cell(cell) = self.stack[bp + locals_index].as_ref() {
+                        *cell.borrow_mut() = obj;
+                    } else {
+                        self.stack[bp + locals_index] = obj;
+                    }
                 }
                 Opcode::GetBuiltin => {
                     // decode the operand (index to built-in functions)
@@ -339,13 +665,77 @@ impl VM {
                     let free_idx = instructions.code[ip + 1] as usize;
                     self.current_frame().ip += 1;
                     let curr_closure = self.current_frame().closure.clone();
-                    self.push(curr_closure.free[free_idx].clone(), line)?;
+                    let obj = curr_closure.free[free_idx].borrow().clone();
+                    self.push(obj, line)?;
+                }
+                Opcode::SetFree => {
+                    let free_idx = instructions.code[ip + 1] as usize;
+                    self.current_frame().ip += 1;
+                    let curr_closure = self.current_frame().closure.clone();
+                    *curr_closure.free[free_idx].borrow_mut() = self.pop(line)?;
+                }
+                Opcode::CaptureLocal => {
+                    let locals_index = instructions.code[ip + 1] as usize;
+                    self.current_frame().ip += 1;
+                    let bp = self.current_frame().bp;
+                    // Move the local into a cell the first time it is captured
+                    // so that the function and its closures share the variable
+                    let slot = bp + locals_index;
+                    if !matches!(self.stack[slot].as_ref(), Object::Cell(_)) {
+                        let cell = Rc::new(RefCell::new(self.stack[slot].clone()));
+                        self.stack[slot] = Rc::new(Object::Cell(cell));
+                    }
+                    self.push(self.stack[slot].clone(), line)?;
+                }
+                Opcode::CaptureFree => {
+                    let free_idx = instructions.code[ip + 1] as usize;
+                    self.current_frame().ip += 1;
+                    let curr_closure = self.current_frame().closure.clone();
+                    let cell = curr_closure.free[free_idx].clone();
+                    self.push(Rc::new(Object::Cell(cell)), line)?;
                 }
                 Opcode::CurrClosure => {
                     let curr_closure = self.current_frame().closure.clone();
                     // push the current closure on stack
                     self.push(Rc::new(Object::Clos(curr_closure)), line)?;
                 }
+                Opcode::Iter => {
+                    let obj = self.pop(line)?;
+                    match Iter::new(&obj) {
+                        Some(iter) => self.push(Rc::new(Object::Iter(Rc::new(iter))), line)?,
+                        None => {
+                            return Err(RTError::new(
+                                &format!("object is not iterable: {}", obj),
+                                line,
+                            ))
+                        }
+                    }
+                }
+                Opcode::IterNext => {
+                    // Decode first operand (jump address once the iterator is exhausted)
+                    let pos = BigEndian::read_u16(&instructions.code[ip + 1..ip + 3]) as usize;
+                    // Decode second operand (number of loop variables)
+                    let num_vars = instructions.code[ip + 3] as usize;
+                    self.current_frame().ip += 3;
+                    // The iterator stays on the stack for the duration of the loop
+                    let mut iter = match self.peek(0).as_ref() {
+                        Object::Iter(iter) => iter.as_ref().clone(),
+                        _ => return Err(RTError::new("iterator not found", line)),
+                    };
+                    match iter.next(num_vars) {
+                        Some(values) => {
+                            self.stack[self.sp - 1] = Rc::new(Object::Iter(Rc::new(iter)));
+                            for value in values {
+                                self.push(value, line)?;
+                            }
+                        }
+                        None => {
+                            self.pop(line)?;
+                            self.current_frame().ip = pos;
+                            continue;
+                        }
+                    }
+                }
                 Opcode::Invalid => {
                     return Err(RTError::new(
                         &format!("opcode {} undefined", op as u8),
@@ -359,10 +749,29 @@ impl VM {
         Ok(())
     }
 
+    // The operands are converted to integers and the result back to a number.
+    // 'op' returns None if a shift amount is out of range.
+    fn bitwise_op(
+        &mut self,
+        op: fn(a: i64, b: i64) -> Option<i64>,
+        line: usize,
+    ) -> Result<(), RTError> {
+        let right = self.pop(line)?;
+        let left = self.pop(line)?;
+
+        match (left.as_integer(), right.as_integer()) {
+            (Some(a), Some(b)) => match op(a, b) {
+                Some(result) => self.push(Rc::new(Object::Integer(result)), line),
+                None => Err(RTError::new("shift amount out of range", line)),
+            },
+            _ => Err(RTError::new("bitwise operands must be integers", line)),
+        }
+    }
+
     fn binary_op(
         &mut self,
         optype: BinaryOperation,
-        op: fn(a: &Object, b: &Object) -> Object,
+        op: fn(a: &Object, b: &Object) -> Result<Object, String>,
         line: usize,
     ) -> Result<(), RTError> {
         // pop right before left
@@ -370,8 +779,9 @@ impl VM {
         let left = self.pop(line)?;
 
         match (&*left, &*right) {
-            (Object::Number(_), Object::Number(_)) => {
-                self.push(Rc::new(op(&left, &right)), line)?;
+            (a, b) if a.is_number() && b.is_number() => {
+                let result = op(&left, &right).map_err(|msg| RTError::new(&msg, line))?;
+                self.push(Rc::new(result), line)?;
                 Ok(())
             }
             (Object::Str(left), Object::Str(right)) => {
@@ -382,9 +792,10 @@ impl VM {
                     Err(RTError::new("Invalid operation on strings.", line))
                 }
             }
-            (Object::Str(s), Object::Number(n)) | (Object::Number(n), Object::Str(s)) => {
+            (Object::Str(s), n) | (n, Object::Str(s)) if n.is_number() => {
                 if matches!(optype, BinaryOperation::Mul) {
-                    self.push(Rc::new(Object::Str(s.repeat(*n as usize))), line)?;
+                    let n = n.as_float().unwrap() as usize;
+                    self.push(Rc::new(Object::Str(s.repeat(n))), line)?;
                     Ok(())
                 } else {
                     Err(RTError::new("Invalid operation on strings.", line))
@@ -404,6 +815,9 @@ impl VM {
     }
 
     // Build map from objects on stack
+    // Maps are keyed by 'Rc<Object>' whose containers are mutable, but
+    // only the hashable objects are used as keys at runtime.
+    #[allow(clippy::mutable_key_type)]
     fn build_map(&self, start_index: usize, end_index: usize) -> HashMap<Rc<Object>, Rc<Object>> {
         let mut elements = HashMap::with_capacity(end_index - start_index);
         for i in (start_index..end_index).step_by(2) {
@@ -421,30 +835,26 @@ impl VM {
         line: usize,
     ) -> Result<(), RTError> {
         match (&*left, &*index) {
-            (Object::Arr(arr), Object::Number(idx)) => self.exec_array_index(arr, *idx, line),
+            (Object::Arr(_) | Object::Str(_), _) => {
+                let obj = left
+                    .index_sequence(&index)
+                    .map_err(|msg| RTError::new(&msg, line))?;
+                self.push(obj, line)
+            }
             (Object::Map(map), _) => self.exec_hash_index(map, &index, line),
             _ => Err(RTError::new("index operator not supported.", line)),
         }
     }
 
-    fn exec_array_index(&mut self, arr: &Array, idx: f64, line: usize) -> Result<(), RTError> {
-        if idx < 0. || idx >= arr.elements.len() as f64 {
-            // Out of bounds
-            self.push(Rc::new(Object::Nil), line)?;
-        } else {
-            self.push(arr.elements[idx as usize].clone(), line)?;
-        }
-        Ok(())
-    }
-
     fn exec_hash_index(
         &mut self,
         map: &HMap,
         key: &Rc<Object>,
         line: usize,
     ) -> Result<(), RTError> {
-        if let Some(obj) = map.pairs.get(key) {
-            self.push(obj.clone(), line)?;
+        let obj = map.pairs.borrow().get(key).cloned();
+        if let Some(obj) = obj {
+            self.push(obj, line)?;
         } else {
             // Not found
             self.push(Rc::new(Object::Nil), line)?;
@@ -452,6 +862,50 @@ impl VM {
         Ok(())
     }
 
+    // Update an element of an array or a map in place
+    fn exec_set_index_expr(
+        &mut self,
+        left: &Object,
+        index: Rc<Object>,
+        value: Rc<Object>,
+        line: usize,
+    ) -> Result<(), RTError> {
+        match left {
+            Object::Arr(arr) => {
+                let idx = match index.as_integer() {
+                    Some(idx) => idx,
+                    _ => return Err(RTError::new("invalid index to array object", line)),
+                };
+                let mut elements = arr.elements.borrow_mut();
+                // Negative indices count from the end
+                let pos = if idx < 0 {
+                    idx + elements.len() as i64
+                } else {
+                    idx
+                };
+                if pos < 0 || pos >= elements.len() as i64 {
+                    return Err(RTError::new(
+                        &format!("array index out of bounds: {}", idx),
+                        line,
+                    ));
+                }
+                elements[pos as usize] = value;
+                Ok(())
+            }
+            Object::Map(map) => {
+                if !index.is_a_valid_key() {
+                    return Err(RTError::new(
+                        "hash key should be a numeric, a string or a boolean",
+                        line,
+                    ));
+                }
+                map.pairs.borrow_mut().insert(index, value);
+                Ok(())
+            }
+            _ => Err(RTError::new("index assignment not supported", line)),
+        }
+    }
+
     fn exec_call(&mut self, num_args: usize, line: usize) -> Result<(), RTError> {
         // Calculate the location of the function on the stack by decoding
         // the operand, 'num_args', and subtracting it from 'sp'. The additional
@@ -472,6 +926,30 @@ impl VM {
         Ok(())
     }
 
+    // Call the method whose name is the constant at 'name_index'. The
+    // receiver is on the stack below the arguments, in the slot of the
+    // function being called. A builtin method takes the receiver as the
+    // first argument, so the builtin is inserted below the receiver.
+    fn exec_invoke(
+        &mut self,
+        name_index: usize,
+        num_args: usize,
+        binding: Option<Rc<Object>>,
+        line: usize,
+    ) -> Result<(), RTError> {
+        let name = self.constants[name_index].to_string();
+        let slot = self.sp - 1 - num_args;
+        let (callee, pass_receiver) = lookup_method(&self.stack[slot], &name, binding)
+            .map_err(|msg| RTError::new(&msg, line))?;
+        if !pass_receiver {
+            self.stack[slot] = callee;
+            return self.exec_call(num_args, line);
+        }
+        self.push(callee.clone(), line)?;
+        self.stack[slot..self.sp].rotate_right(1);
+        self.exec_call(num_args + 1, line)
+    }
+
     // The stack during the execution of a function call
     // looks like the following:
     //                                  <<------ sp
@@ -486,33 +964,52 @@ impl VM {
         num_args: usize,
         line: usize,
     ) -> Result<(), RTError> {
+        let func = &closure.func;
         // Make sure that the right number of arguments is sitting on the stack
-        if num_args != closure.func.num_params {
-            return Err(RTError::new(
-                &format!(
-                    "wrong number of arguments: want={}, got={}",
-                    closure.func.num_params, num_args
-                ),
-                line,
-            ));
-        }
+        check_arity(func.min_args, func.max_args, num_args).map_err(|e| RTError::new(&e, line))?;
 
         // Save the current stack pointer before calling a function
         // The base pointer 'bp' is further down the stack and points to
         // the first argument to the function.
         let bp = self.sp - num_args;
-        let frame = Frame::new(closure.clone(), bp);
+        let mut frame = Frame::new(closure.clone(), bp);
+        if frame.bp + func.num_locals > self.stack.len() {
+            return Err(RTError::new("Stack overflow!", line));
+        }
+
+        // Arguments past the named parameters are collected into an array
+        // that is stored in the slot of the last parameter
+        let num_named = func.num_params - func.max_args.is_none() as usize;
+        let num_passed = num_args.min(num_named);
+        let rest = func
+            .max_args
+            .is_none()
+            .then(|| self.stack[bp + num_passed..self.sp].to_vec());
+        // Skip the default values of the arguments that were passed
+        frame.ip = func
+            .entries
+            .get(num_passed - func.min_args)
+            .copied()
+            .unwrap_or(0);
 
         // Allocate space for local bindings on stack starting at the base
         // pointer 'bp' with 'num_locals' slots on the stack. Note that the
         // parameters to the function are also part of the local bindings,
         // i.e. 'num_locals' is the sum of #locals and #arguments
         // In the example above, num_locals = args(2) + locals(2) = 4.
-        self.sp = frame.bp + closure.func.num_locals;
+        self.sp = frame.bp + func.num_locals;
+        // Clear the local bindings that are not arguments since the slots may
+        // still hold objects from earlier calls, so that unset locals are Nil.
+        for slot in &mut self.stack[bp + num_passed..self.sp] {
+            *slot = Rc::new(Object::Nil);
+        }
+        if let Some(rest) = rest {
+            self.stack[bp + num_named] = Rc::new(Object::Arr(Rc::new(Array::new(rest))));
+        }
 
-        // skip over the instruction and the 1-byte operand to OpCall 'before'
-        // pushing a new frame so that the callee's frame is not meddled with
-        self.current_frame().ip += 2;
+        // skip over the call instruction 'before' pushing a new frame so
+        // that the callee's frame is not meddled with
+        self.current_frame().ip += 1;
         self.push_frame(frame);
         Ok(())
     }
@@ -535,10 +1032,10 @@ impl VM {
             Err(s) => {
                 // Prefix error messaage with the function name
                 let msg = format!("{}: {}", builtin.name, s);
-                return Err(RTError::new(&msg, 1));
+                return Err(RTError::new(&msg, line));
             }
         }
-        self.current_frame().ip += 2;
+        self.current_frame().ip += 1;
         Ok(())
     }
 
@@ -554,11 +1051,16 @@ impl VM {
         if let Object::CompiledFunc(function) = constant.as_ref() {
             let mut free = Vec::with_capacity(num_free);
 
-            // Take each free variable from stack and copy it to 'free'
-            // copy in the same order they are referenced in GetFree
+            // Take each free variable from stack and move it to 'free'
+            // in the same order they are referenced in GetFree. Variables
+            // that cannot be assigned to (e.g. the current closure) are
+            // not captured as cells and get a cell of their own.
             for i in 0..num_free {
                 let idx = self.sp - num_free + i;
-                free.push(self.stack[idx].clone());
+                match self.stack[idx].as_ref() {
+                    Object::Cell(cell) => free.push(cell.clone()),
+                    _ => free.push(Rc::new(RefCell::new(self.stack[idx].clone()))),
+                }
             }
             // cleanup stack of free variables
             self.sp -= num_free;
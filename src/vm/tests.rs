@@ -1,7 +1,14 @@
 #![allow(unused_imports)]
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
 use std::rc::Rc;
 
 use super::*;
+use crate::common::bigint::BigInt;
+use crate::common::error::CompileError;
 use crate::common::object::*;
 use crate::compiler::*;
 use crate::evaluator::*;
@@ -19,6 +26,20 @@ fn check_parse_errors(parser: &Parser) {
 #[cfg(test)]
 fn test_expected_object(evaluated: Rc<Object>, expected: &Object) {
     match (evaluated.as_ref(), expected) {
+        (Object::Integer(eval), Object::Integer(exp)) => {
+            assert_eq!(
+                eval, exp,
+                "object has wrong integer value. got={}, want={}",
+                eval, exp
+            );
+        }
+        (Object::BigInt(eval), Object::BigInt(exp)) => {
+            assert_eq!(
+                eval, exp,
+                "object has wrong bigint value. got={}, want={}",
+                eval, exp
+            );
+        }
         (Object::Number(eval), Object::Number(exp)) => {
             assert_eq!(
                 eval, exp,
@@ -42,23 +63,28 @@ fn test_expected_object(evaluated: Rc<Object>, expected: &Object) {
         }
         (Object::Arr(eval), Object::Arr(exp)) => {
             assert_eq!(
-                eval.elements.len(),
-                exp.elements.len(),
+                eval.elements.borrow().len(),
+                exp.elements.borrow().len(),
                 "array object has wrong length. got={}, want={}",
-                eval.elements.len(),
-                exp.elements.len()
+                eval.elements.borrow().len(),
+                exp.elements.borrow().len()
             );
-            for (ex, ev) in exp.elements.iter().zip(eval.elements.iter()) {
+            for (ex, ev) in exp
+                .elements
+                .borrow()
+                .iter()
+                .zip(eval.elements.borrow().iter())
+            {
                 assert_eq!(ex, ev);
             }
         }
         (Object::Map(eval), Object::Map(exp)) => {
             assert_eq!(
-                eval.pairs.len(),
-                exp.pairs.len(),
+                eval.pairs.borrow().len(),
+                exp.pairs.borrow().len(),
                 "map object has wrong length. got={}, want={}",
-                eval.pairs.len(),
-                exp.pairs.len()
+                eval.pairs.borrow().len(),
+                exp.pairs.borrow().len()
             );
             assert_eq!(eval, exp);
         }
@@ -138,23 +164,23 @@ fn test_integer_arithmetic() {
     let tests = vec![
         VmTestCase {
             input: "1",
-            expected: Object::Number(1.),
+            expected: Object::Integer(1),
         },
         VmTestCase {
             input: "2",
-            expected: Object::Number(2.),
+            expected: Object::Integer(2),
         },
         VmTestCase {
             input: "1 + 2",
-            expected: Object::Number(3.),
+            expected: Object::Integer(3),
         },
         VmTestCase {
             input: "1 - 2",
-            expected: Object::Number(-1.),
+            expected: Object::Integer(-1),
         },
         VmTestCase {
             input: "1 * 2",
-            expected: Object::Number(2.),
+            expected: Object::Integer(2),
         },
         VmTestCase {
             input: "4 / 2",
@@ -166,35 +192,35 @@ fn test_integer_arithmetic() {
         },
         VmTestCase {
             input: "5 + 5 + 5 + 5 - 10",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "2 * 2 * 2 * 2 * 2",
-            expected: Object::Number(32.),
+            expected: Object::Integer(32),
         },
         VmTestCase {
             input: "5 * 2 + 10",
-            expected: Object::Number(20.),
+            expected: Object::Integer(20),
         },
         VmTestCase {
             input: "5 + 2 * 10",
-            expected: Object::Number(25.),
+            expected: Object::Integer(25),
         },
         VmTestCase {
             input: "5 * (2 + 10)",
-            expected: Object::Number(60.),
+            expected: Object::Integer(60),
         },
         VmTestCase {
             input: "-5",
-            expected: Object::Number(-5.),
+            expected: Object::Integer(-5),
         },
         VmTestCase {
             input: "-10",
-            expected: Object::Number(-10.),
+            expected: Object::Integer(-10),
         },
         VmTestCase {
             input: "-50 + 100 + -50",
-            expected: Object::Number(0.),
+            expected: Object::Integer(0),
         },
         VmTestCase {
             input: "(5 + 10 * 2 + 15 / 3) * 2 + -10",
@@ -205,6 +231,374 @@ fn test_integer_arithmetic() {
     run_vm_tests(&tests);
 }
 
+#[test]
+fn test_integer_and_float_promotion() {
+    let tests = vec![
+        VmTestCase {
+            input: "9007199254740993",
+            expected: Object::Integer(9007199254740993),
+        },
+        VmTestCase {
+            input: "9007199254740992 + 1",
+            expected: Object::Integer(9007199254740993),
+        },
+        VmTestCase {
+            input: "-9223372036854775807 - 1",
+            expected: Object::Integer(i64::MIN),
+        },
+        VmTestCase {
+            input: "1 + 2.5",
+            expected: Object::Number(3.5),
+        },
+        VmTestCase {
+            input: "7 / 2",
+            expected: Object::Number(3.5),
+        },
+        VmTestCase {
+            input: "7 ~/ 2",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "7.0 ~/ 2",
+            expected: Object::Number(3.),
+        },
+        VmTestCase {
+            input: "2 ** 62",
+            expected: Object::Integer(1 << 62),
+        },
+        VmTestCase {
+            input: "1 == 1.0",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "2.5 > 2",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "[1, 2, 3][1.0]",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: r#"let m = {1: "a"}; m[1.0]"#,
+            expected: Object::Str("a".into()),
+        },
+        VmTestCase {
+            input: r#"let m = {}; m[2.0] = "a"; m[2] = "b"; m[2.0]"#,
+            expected: Object::Str("b".into()),
+        },
+        VmTestCase {
+            input: "len(\"four\")",
+            expected: Object::Integer(4),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[cfg(test)]
+fn bigint(s: &str) -> Object {
+    Object::BigInt(BigInt::from_str_radix(s, 10).unwrap())
+}
+
+#[test]
+fn test_bigint_arithmetic() {
+    let tests = vec![
+        VmTestCase {
+            input: "9223372036854775807n + 1",
+            expected: bigint("9223372036854775808"),
+        },
+        VmTestCase {
+            input: "let f = fn(n) { if (n < 2) { 1n } else { n * f(n - 1) } }; f(25)",
+            expected: bigint("15511210043330985984000000"),
+        },
+        VmTestCase {
+            input: "2n ** 100 - 1",
+            expected: bigint("1267650600228229401496703205375"),
+        },
+        VmTestCase {
+            input: "-7n / 2",
+            expected: bigint("-4"),
+        },
+        VmTestCase {
+            input: "-7n % 2",
+            expected: bigint("1"),
+        },
+        VmTestCase {
+            input: "-(10n ** 20)",
+            expected: bigint("-100000000000000000000"),
+        },
+        VmTestCase {
+            input: r#"bigint("-123456789012345678901234567890") * -1"#,
+            expected: bigint("123456789012345678901234567890"),
+        },
+        VmTestCase {
+            input: "bigint(42)",
+            expected: bigint("42"),
+        },
+        VmTestCase {
+            input: "str(3n ** 50)",
+            expected: Object::Str("717897987691852588770249".into()),
+        },
+        VmTestCase {
+            input: "10n == 10",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "10n ** 30 > 9223372036854775807",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "2n < 2.5",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: r#"let m = {1: "a"}; m[1n]"#,
+            expected: Object::Str("a".into()),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_bigint_failures() {
+    let tests = vec![
+        VmTestCaseErr {
+            input: "1n + 1.5",
+            expected: "cannot mix bigint and float operands",
+        },
+        VmTestCaseErr {
+            input: "1n / 0",
+            expected: "division by zero",
+        },
+        VmTestCaseErr {
+            input: "1n % 0n",
+            expected: "modulo by zero",
+        },
+        VmTestCaseErr {
+            input: "2n ** -1",
+            expected: "bigint exponent must be a non-negative integer",
+        },
+        VmTestCaseErr {
+            input: r#"bigint("12x")"#,
+            expected: "bigint: invalid integer '12x'",
+        },
+        VmTestCaseErr {
+            input: "bigint(1.5)",
+            expected: "bigint: unsupported argument",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_integer_overflow() {
+    let tests = vec![
+        VmTestCaseErr {
+            input: "9223372036854775807 + 1",
+            expected: "integer overflow",
+        },
+        VmTestCaseErr {
+            input: "-9223372036854775807 - 2",
+            expected: "integer overflow",
+        },
+        VmTestCaseErr {
+            input: "3037000500 * 3037000500",
+            expected: "integer overflow",
+        },
+        VmTestCaseErr {
+            input: "2 ** 63",
+            expected: "integer overflow",
+        },
+        VmTestCaseErr {
+            input: "let x = -9223372036854775807 - 1; -x",
+            expected: "integer overflow",
+        },
+        VmTestCaseErr {
+            input: "let x = -9223372036854775807 - 1; x ~/ -1",
+            expected: "integer overflow",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_float_arithmetic() {
+    let tests = vec![
+        VmTestCase {
+            input: "1.5 * 2",
+            expected: Object::Number(3.),
+        },
+        VmTestCase {
+            input: "0.25 + 0.5",
+            expected: Object::Number(0.75),
+        },
+        VmTestCase {
+            input: "1e3 - 1_000",
+            expected: Object::Number(0.),
+        },
+        VmTestCase {
+            input: "0xff + 0o7 + 0b1",
+            expected: Object::Integer(263),
+        },
+        VmTestCase {
+            input: "2.5e-1 > 0.2",
+            expected: Object::Bool(true),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_modulo_floor_division_and_power() {
+    let tests = vec![
+        VmTestCase {
+            input: "7 % 3",
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: "-7 % 3",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: "7 % -3",
+            expected: Object::Integer(-2),
+        },
+        VmTestCase {
+            input: "5.5 % 2",
+            expected: Object::Number(1.5),
+        },
+        VmTestCase {
+            input: "7 ~/ 2",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "-7 ~/ 2",
+            expected: Object::Integer(-4),
+        },
+        VmTestCase {
+            input: "let a = -7; let b = 3; (a ~/ b) * b + a % b == a",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "2 ** 10",
+            expected: Object::Integer(1024),
+        },
+        VmTestCase {
+            input: "2 ** 3 ** 2",
+            expected: Object::Integer(512),
+        },
+        VmTestCase {
+            input: "-2 ** 2",
+            expected: Object::Integer(-4),
+        },
+        VmTestCase {
+            input: "2 ** -1",
+            expected: Object::Number(0.5),
+        },
+        VmTestCase {
+            input: "1 + 2 * 3 ** 2 % 5",
+            expected: Object::Integer(4),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_division_by_zero() {
+    let tests = vec![
+        VmTestCaseErr {
+            input: "1 / 0",
+            expected: "division by zero",
+        },
+        VmTestCaseErr {
+            input: "let x = 0; 1 ~/ x",
+            expected: "division by zero",
+        },
+        VmTestCaseErr {
+            input: "1 % 0",
+            expected: "modulo by zero",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let tests = vec![
+        VmTestCase {
+            input: "12 & 10",
+            expected: Object::Integer(8),
+        },
+        VmTestCase {
+            input: "12 | 10",
+            expected: Object::Integer(14),
+        },
+        VmTestCase {
+            input: "12 ^ 10",
+            expected: Object::Integer(6),
+        },
+        VmTestCase {
+            input: "~0",
+            expected: Object::Integer(-1),
+        },
+        VmTestCase {
+            input: "~5 & 0xff",
+            expected: Object::Integer(250),
+        },
+        VmTestCase {
+            input: "1 << 10",
+            expected: Object::Integer(1024),
+        },
+        VmTestCase {
+            input: "-16 >> 2",
+            expected: Object::Integer(-4),
+        },
+        VmTestCase {
+            input: "let flags = 0b0101; flags & (1 << 2) == 4",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "let packed = (3 << 8) | 0x2a; [packed >> 8, packed & 0xff] == [3, 42]",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "4.0 | 1",
+            expected: Object::Integer(5),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_bitwise_operator_failures() {
+    let tests = vec![
+        VmTestCaseErr {
+            input: "1.5 & 1",
+            expected: "bitwise operands must be integers",
+        },
+        VmTestCaseErr {
+            input: "1 | 1e19",
+            expected: "bitwise operands must be integers",
+        },
+        VmTestCaseErr {
+            input: r#"true ^ "1""#,
+            expected: "bitwise operands must be integers",
+        },
+        VmTestCaseErr {
+            input: "~0.5",
+            expected: "bitwise operand must be an integer",
+        },
+        VmTestCaseErr {
+            input: "1 << 64",
+            expected: "shift amount out of range",
+        },
+        VmTestCaseErr {
+            input: "1 >> -1",
+            expected: "shift amount out of range",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
 #[test]
 fn test_boolean_expressions() {
     let tests = vec![
@@ -326,31 +720,31 @@ fn test_conditionals() {
     let tests = vec![
         VmTestCase {
             input: "if (true) { 10 }",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "if (true) { 10 } else { 20 }",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "if (false) { 10 } else { 20 } ",
-            expected: Object::Number(20.),
+            expected: Object::Integer(20),
         },
         VmTestCase {
             input: "if (1) { 10 }",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "if (1 < 2) { 10 }",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "if (1 < 2) { 10 } else { 20 }",
-            expected: Object::Number(10.),
+            expected: Object::Integer(10),
         },
         VmTestCase {
             input: "if (1 > 2) { 10 } else { 20 }",
-            expected: Object::Number(20.),
+            expected: Object::Integer(20),
         },
         VmTestCase {
             input: "if (1 > 2) { 10 }",
@@ -362,27 +756,20 @@ fn test_conditionals() {
         },
         VmTestCase {
             input: "if ((if (false) { 10 })) { 10 } else { 20 }",
-            expected: Object::Number(20.),
+            expected: Object::Integer(20),
         },
-    ];
-
-    run_vm_tests(&tests);
-}
-
-#[test]
-fn test_global_let_statements() {
-    let tests = vec![
+        // branches that don't end with an expression result in nil
         VmTestCase {
-            input: "let one = 1; one",
-            expected: Object::Number(1.),
+            input: "if (true) { let x = 1; }",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: "let one = 1; let two = 2; one + two",
-            expected: Object::Number(3.),
+            input: "if (false) { 10 } else { }",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: "let one = 1; let two = one + one; one + two",
-            expected: Object::Number(3.),
+            input: "if (true) { let x = 1; } let y = 2; y",
+            expected: Object::Integer(2),
         },
     ];
 
@@ -390,651 +777,1322 @@ fn test_global_let_statements() {
 }
 
 #[test]
-fn test_string_expressions() {
+fn test_logical_operators() {
     let tests = vec![
         VmTestCase {
-            input: r#""monkey""#,
-            expected: Object::Str("monkey".to_string()),
+            input: "true && true",
+            expected: Object::Bool(true),
         },
         VmTestCase {
-            input: r#""mon" + "key""#,
-            expected: Object::Str("monkey".to_string()),
+            input: "true && false",
+            expected: Object::Bool(false),
         },
         VmTestCase {
-            input: r#""mon" + "key" + "banana""#,
-            expected: Object::Str("monkeybanana".to_string()),
+            input: "false || true",
+            expected: Object::Bool(true),
         },
-    ];
-    run_vm_tests(&tests);
-}
-
-#[test]
-fn test_array_literals() {
-    let tests = vec![
         VmTestCase {
-            input: "[]",
-            expected: Object::Arr(Rc::new(Array {
-                elements: Vec::new(),
-            })),
+            input: "false || false",
+            expected: Object::Bool(false),
         },
         VmTestCase {
-            input: "[1, 2, 3]",
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![
-                    Rc::new(Object::Number(1.0)),
-                    Rc::new(Object::Number(2.0)),
-                    Rc::new(Object::Number(3.0)),
-                ],
-            })),
+            input: "1 < 2 && 2 < 3 || false",
+            expected: Object::Bool(true),
         },
+        // Truthiness follows 'is_falsey', only 'false' and 'nil' are falsey
         VmTestCase {
-            input: "[1 + 2, 3 * 4, 5 + 6]",
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![
-                    Rc::new(Object::Number(3.0)),
-                    Rc::new(Object::Number(12.0)),
-                    Rc::new(Object::Number(11.0)),
-                ],
-            })),
+            input: r#"0 && """#,
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "if (false) { 1 } || [1][5]",
+            expected: Object::Bool(false),
+        },
+        // The right operand is not evaluated if the left decides the result
+        VmTestCase {
+            input: "false && len(1)",
+            expected: Object::Bool(false),
+        },
+        VmTestCase {
+            input: "true || len(1)",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "let x = 0; let f = fn() { x = x + 1; true }; false && f(); true || f(); x",
+            expected: Object::Integer(0),
         },
     ];
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_hash_literals() {
+fn test_while_loops() {
     let tests = vec![
         VmTestCase {
-            input: "{}",
-            expected: Object::Map(Rc::new(HMap::default())),
+            input: "while (false) { 10 }",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: "{1: 2, 2: 3}",
-            expected: Object::Map({
-                let mut map = HMap::default();
-                map.pairs.insert(
-                    Rc::new(Object::Number(1.into())),
-                    Rc::new(Object::Number(2.into())),
-                );
-                map.pairs.insert(
-                    Rc::new(Object::Number(2.into())),
-                    Rc::new(Object::Number(3.into())),
-                );
-                Rc::new(map)
-            }),
+            input: "while (1 > 2) { 10 }; 20",
+            expected: Object::Integer(20),
         },
         VmTestCase {
-            input: "{1 + 1: 2 * 2, 3 + 3: 4 * 4}",
-            expected: Object::Map({
-                let mut map = HMap::default();
-                map.pairs.insert(
-                    Rc::new(Object::Number(2.into())),
-                    Rc::new(Object::Number(4.into())),
-                );
-                map.pairs.insert(
-                    Rc::new(Object::Number(6.into())),
-                    Rc::new(Object::Number(16.into())),
-                );
-                Rc::new(map)
-            }),
+            input: "let f = fn() { while (true) { return 10; } }; f()",
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: "let f = fn(x) { while (x) { return 10; }; 20 }; f(false)",
+            expected: Object::Integer(20),
+        },
+        VmTestCase {
+            input: "let i = 0; let n = 0; while (i) { n = n + 1; i = nil; }; n",
+            expected: Object::Integer(1),
         },
     ];
+
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_index_expressions() {
+fn test_for_loops() {
     let tests = vec![
         VmTestCase {
-            input: "[1, 2, 3][1]",
-            expected: Object::Number(2.),
-        },
-        VmTestCase {
-            input: "[1, 2, 3][0 + 2]",
-            expected: Object::Number(3.),
-        },
-        VmTestCase {
-            input: "[[1, 1, 1]][0][0]",
-            expected: Object::Number(1.),
+            input: "for (x in []) { x }",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: "[][0]",
-            expected: Object::Nil,
+            input: "for (x in [1, 2, 3]) { x }; x",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: "[1, 2, 3][99]",
-            expected: Object::Nil,
+            input: "let f = fn(a) { for (x in a) { if (x > 2) { return x; } } }; f([1, 2, 3, 4])",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: "[1][-1]",
-            expected: Object::Nil,
+            input: "let f = fn() { for (i, x in [5, 6]) { }; [i, x] }; f()",
+            expected: Object::Arr(Rc::new(Array::new(vec![Rc::new(Object::Integer(1)), Rc::new(Object::Integer(6))]))),
         },
         VmTestCase {
-            input: "{1: 1, 2: 2}[1]",
-            expected: Object::Number(1.),
+            input: r#"for (c in "abc") { c }; c"#,
+            expected: Object::Str("c".to_string()),
         },
         VmTestCase {
-            input: "{1: 1, 2: 2}[2]",
-            expected: Object::Number(2.),
+            input: r#"let f = fn(s) { for (i, c in s) { if (i == 1) { return c; } } }; f("héllo")"#,
+            expected: Object::Str("é".to_string()),
         },
         VmTestCase {
-            input: "{1: 1}[0]",
-            expected: Object::Nil,
+            input: r#"for (k in {"b": 2, "c": 3, "a": 1}) { }; k"#,
+            expected: Object::Str("c".to_string()),
         },
         VmTestCase {
-            input: "{}[0]",
-            expected: Object::Nil,
+            input: r#"let f = fn(m) { for (k, v in m) { return [k, v]; } }; f({"b": 2, "a": 1})"#,
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                    Rc::new(Object::Str("a".to_string())),
+                    Rc::new(Object::Integer(1)),
+                ]))),
         },
         VmTestCase {
-            input: r#"{"one": 1, "two": 2, "three": 3}["o" + "ne"]"#,
-            expected: Object::Number(1.),
+            input: "let f = fn(a) { for (x in a) { for (y in a) { if (x + y == 5) { return x * y; } } } }; f([1, 2, 3])",
+            expected: Object::Integer(6),
         },
     ];
+
     run_vm_tests(&tests);
+
+    let tests: Vec<VmTestCaseErr> = vec![VmTestCaseErr {
+        input: "for (x in 5) { x }",
+        expected: "object is not iterable: 5",
+    }];
+
+    run_vm_negative_tests(&tests);
 }
 
 #[test]
-fn test_calling_functions_without_args() {
+fn test_global_let_statements() {
     let tests = vec![
         VmTestCase {
-            input: r#"
-            let fivePlusTen = fn() { 5 + 10; };
-            fivePlusTen();
-            "#,
-            expected: Object::Number(15.),
+            input: "let one = 1; one",
+            expected: Object::Integer(1),
         },
         VmTestCase {
-            input: r#"
-            let f1 = fn() { 1 };
-            let f2 = fn() { 2 };
-            f1() + f2()
-            "#,
-            expected: Object::Number(3.),
+            input: "let one = 1; let two = 2; one + two",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: r#"
-            let f1 = fn() { 1 };
-            let f2 = fn() { f1() + 1 };
-            let f3 = fn() { f2() + 1 };
-            f3()
-            "#,
-            expected: Object::Number(3.),
+            input: "let one = 1; let two = one + one; one + two",
+            expected: Object::Integer(3),
         },
     ];
+
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_calling_functions_with_return() {
+fn test_destructure_statements() {
     let tests = vec![
         VmTestCase {
-            input: r#"
-            let earlyExit = fn() { return 99; 100; };
-            earlyExit();
-            "#,
-            expected: Object::Number(99.),
+            input: "let [a, b] = [1, 2]; a * 10 + b",
+            expected: Object::Integer(12),
         },
         VmTestCase {
-            input: r#"
-            let earlyExit = fn() { return 99; return 100; };
-            earlyExit();
-            "#,
-            expected: Object::Number(99.),
+            input: "let [a, ...rest] = [1, 2, 3]; rest",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(2)),
+                Rc::new(Object::Integer(3)),
+            ]))),
         },
-    ];
-    run_vm_tests(&tests);
-}
-
-#[test]
-fn test_calling_functions_without_return() {
-    let tests = vec![
         VmTestCase {
-            input: r#"
-            let noReturn = fn() { };
-            noReturn();
-            "#,
-            expected: Object::Nil,
+            input: "let [a, b, ...rest] = [1, 2]; len(rest)",
+            expected: Object::Integer(0),
         },
         VmTestCase {
-            input: r#"
-            let noReturn1 = fn() { };
-            let noReturn2 = fn() { noReturn1(); };
-            noReturn1();
-            noReturn2();
-            "#,
-            expected: Object::Nil,
+            input: r#"let {name, age} = {"age": 30, "name": "Ann", "x": 1}; name + str(age)"#,
+            expected: Object::Str(String::from("Ann30")),
         },
-    ];
-    run_vm_tests(&tests);
-}
-
-#[test]
-fn test_first_class_functions_() {
-    let tests = vec![
         VmTestCase {
-            input: r#"
-                let returnsOne = fn() { 1; };
-                let returnsOneReturner = fn() { returnsOne; };
-                returnsOneReturner()();
-            "#,
-            expected: Object::Number(1.),
+            // the value is evaluated before the names are bound
+            input: "let a = 1; let b = 2; let [a, b] = [b, a]; a - b",
+            expected: Object::Integer(1),
         },
         VmTestCase {
-            input: r#"
-                let returnsOneReturner = fn() {
-                let returnsOne = fn() { 1; };
-                returnsOne;
-                };
-                returnsOneReturner()();
-            "#,
-            expected: Object::Number(1.),
+            input: "let f = fn(pair) { let [x, y] = pair; x * y }; f([6, 7])",
+            expected: Object::Integer(42),
+        },
+        VmTestCase {
+            input: "let f = fn() { let [x, ...y] = [1, 2]; fn() { x + y[0] } }; f()()",
+            expected: Object::Integer(3),
         },
     ];
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_calling_functions_with_bindings() {
+fn test_destructure_failures() {
     let tests = vec![
-        VmTestCase {
-            input: r#"
-                let one = fn() { let one = 1; one };
-                one();
-            "#,
-            expected: Object::Number(1.),
+        VmTestCaseErr {
+            input: "let [a, b] = [1, 2, 3];",
+            expected: "array pattern expects 2 element(s), got 3",
         },
-        VmTestCase {
-            input: r#"
-                let one_and_two = fn() { let one = 1; let two = 2; one + two; };
-                one_and_two();
-            "#,
-            expected: Object::Number(3.),
+        VmTestCaseErr {
+            input: "let [a, b, ...c] = [1];",
+            expected: "array pattern expects at least 2 element(s), got 1",
         },
-        VmTestCase {
-            input: r#"
-                let one_and_two = fn() { let one = 1; let two = 2; one + two; };
-                let three_and_four = fn() { let three = 3; let four = 4; three + four; };
-                one_and_two() + three_and_four();
-            "#,
-            expected: Object::Number(10.),
+        VmTestCaseErr {
+            input: r#"let [a] = {"a": 1};"#,
+            expected: "array pattern requires an array",
         },
-        VmTestCase {
-            input: r#"
-                let first_foobar = fn() { let foobar = 50; foobar; };
-                let second_foobar = fn() { let foobar = 100; foobar; };
-                first_foobar() + second_foobar();
-            "#,
-            expected: Object::Number(150.),
+        VmTestCaseErr {
+            input: r#"let {a, b} = {"a": 1};"#,
+            expected: "map pattern key 'b' not found",
         },
-        VmTestCase {
-            input: r#"
-                let global_seed = 50;
-                let minus_one = fn() {
-                    let num = 1;
-                    global_seed - num;
-                };
-                let minus_two = fn() {
-                    let num = 2;
-                    global_seed - num;
-                };
-                minus_one() + minus_two();
-            "#,
-            expected: Object::Number(97.),
+        VmTestCaseErr {
+            input: "let {a} = [1];",
+            expected: "map pattern requires a map",
         },
     ];
-    run_vm_tests(&tests);
+    run_vm_negative_tests(&tests);
 }
 
 #[test]
-fn test_calling_functions_with_args_and_bindings() {
+fn test_default_and_rest_parameters() {
     let tests = vec![
         VmTestCase {
-            input: r#"
-                let identity = fn(a) { a; };
-                identity(4);
-            "#,
-            expected: Object::Number(4.),
+            input: "let f = fn(a, b = 10) { a + b }; f(1)",
+            expected: Object::Integer(11),
         },
         VmTestCase {
-            input: r#"
-                let sum = fn(a, b) { a + b; };
-                sum(1, 2);
-            "#,
-            expected: Object::Number(3.),
+            input: "let f = fn(a, b = 10) { a + b }; f(1, 2)",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: r#"
-                let sum = fn(a, b) { let c = a + b; c; };
-                sum(1, 2);
-            "#,
-            expected: Object::Number(3.),
+            // defaults are evaluated at call time and can use earlier parameters
+            input: "let n = 1; let f = fn(a, b = a + n) { b }; n = 5; f(1)",
+            expected: Object::Integer(6),
         },
         VmTestCase {
-            input: r#"
-                let sum = fn(a, b) { let c = a + b; c; };
-                sum(1, 2) + sum(3, 4);
-            "#,
-            expected: Object::Number(10.),
+            input: "let f = fn(a = [], ...rest) { len(a) * 10 + len(rest) }; f() + f([1, 2], 3, 4)",
+            expected: Object::Integer(22),
         },
         VmTestCase {
-            input: r#"
-                let sum = fn(a, b) { let c = a + b; c; };
-                let outer = fn() { sum(1, 2) + sum(3, 4); };
-                outer();
-            "#,
-            expected: Object::Number(10.),
+            input: "let f = fn(...rest) { rest }; f(1, 2)",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(1)),
+                Rc::new(Object::Integer(2)),
+            ]))),
         },
         VmTestCase {
-            input: r#"
-                let globalNum = 10;
-                let sum = fn(a, b) {
-                    let c = a + b;
-                    c + globalNum;
-                };
-                let outer = fn() {
-                    sum(1, 2) + sum(3, 4) + globalNum;
-                };
-                outer() + globalNum;
-            "#,
-            expected: Object::Number(50.),
+            // locals are cleared even when defaults are skipped
+            input: "let f = fn(a = 1) { let b = a; b }; f(2) + f()",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "let f = fn(x) { fn(y = x, ...z) { y + len(z) } }; f(5)() + f(5)(1, 0, 0)",
+            expected: Object::Integer(8),
         },
     ];
     run_vm_tests(&tests);
-}
 
-#[test]
-fn test_wrong_number_of_arguments() {
-    let tests: Vec<VmTestCaseErr> = vec![
+    let tests = vec![
         VmTestCaseErr {
-            input: "fn() { 1; }(1);",
-            expected: "wrong number of arguments: want=0, got=1",
+            input: "fn(a, b = 2) { a }(1, 2, 3)",
+            expected: "wrong number of arguments: want=1..2, got=3",
         },
         VmTestCaseErr {
-            input: "fn(a) { a; }();",
-            expected: "wrong number of arguments: want=1, got=0",
+            input: "fn(a, b = 2) { a }()",
+            expected: "wrong number of arguments: want=1..2, got=0",
         },
         VmTestCaseErr {
-            input: "fn(a, b) { a + b; }(1);",
-            expected: "wrong number of arguments: want=2, got=1",
+            input: "fn(a, b, ...c) { a }(1)",
+            expected: "wrong number of arguments: want=2.., got=1",
         },
     ];
-
     run_vm_negative_tests(&tests);
 }
 
+#[cfg(test)]
+fn integer_array(values: &[i64]) -> Object {
+    let elements = values
+        .iter()
+        .map(|v| Rc::new(Object::Integer(*v)))
+        .collect();
+    Object::Arr(Rc::new(Array::new(elements)))
+}
+
 #[test]
-fn test_builtin_functions() {
+fn test_spread_expressions() {
     let tests = vec![
         VmTestCase {
-            input: r#"len("")"#,
-            expected: Object::Number(0.),
+            input: "let a = [1, 2]; let b = [5]; [...a, 3, 4, ...b, 6]",
+            expected: integer_array(&[1, 2, 3, 4, 5, 6]),
         },
         VmTestCase {
-            input: r#"len("four")"#,
-            expected: Object::Number(4.0),
+            input: "[...[], ...[]]",
+            expected: integer_array(&[]),
         },
         VmTestCase {
-            input: r#"len("hello world")"#,
-            expected: Object::Number(11.0),
+            // spreading copies the elements into a new array
+            input: "let a = [1]; let b = [...a]; b[0] = 2; a",
+            expected: integer_array(&[1]),
         },
         VmTestCase {
-            input: r#"len([1, 2, 3])"#,
-            expected: Object::Number(3.0),
+            input: "let f = fn(a, b, c) { a * 100 + b * 10 + c }; f(...[1, 2], 3)",
+            expected: Object::Integer(123),
         },
         VmTestCase {
-            input: r#"len([])"#,
-            expected: Object::Number(0.),
+            input: "let f = fn(a, ...rest) { len(rest) }; let args = [1, 2, 3]; f(0, ...args)",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: r#"first([1, 2, 3])"#,
-            expected: Object::Number(1.0),
+            input: r#"len(...["four"])"#,
+            expected: Object::Integer(4),
         },
         VmTestCase {
-            input: r#"first([])"#,
-            expected: Object::Nil,
+            // calls with spread arguments continue after the call
+            input: "let f = fn(x) { x * 2 }; let y = f(...[2]) + f(...[3]); y",
+            expected: Object::Integer(10),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: "[...1]",
+            expected: "spread operand must be an array",
+        },
+        VmTestCaseErr {
+            input: "fn(a) { a }(...[1, 2])",
+            expected: "wrong number of arguments: want=1, got=2",
         },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_assignments() {
+    let tests = vec![
         VmTestCase {
-            input: r#"last([1, 2, 3])"#,
-            expected: Object::Number(3.0),
+            input: "let x = 1; x = 2; x",
+            expected: Object::Integer(2),
         },
         VmTestCase {
-            input: r#"last([])"#,
-            expected: Object::Nil,
+            input: "let x = 1; let y = x = 5; x + y",
+            expected: Object::Integer(10),
         },
         VmTestCase {
-            input: r#"rest([1, 2, 3])"#,
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![Rc::new(Object::Number(2.0)), Rc::new(Object::Number(3.0))],
-            })),
+            input: "let x = 0; while (x < 10) { x = x + 1 }; x",
+            expected: Object::Integer(10),
         },
         VmTestCase {
-            input: r#"rest([])"#,
-            expected: Object::Nil,
+            input: "let sum = 0; for (x in [1, 2, 3]) { sum = sum + x }; sum",
+            expected: Object::Integer(6),
         },
         VmTestCase {
-            input: r#"push([], 1)"#,
-            expected: Object::Arr(Rc::new(Array {
-                elements: vec![Rc::new(Object::Number(1.0))],
-            })),
+            input: "let f = fn() { let a = 1; a = a + 41; a }; f()",
+            expected: Object::Integer(42),
         },
         VmTestCase {
-            input: r#"
-                let array = [1, 2, 3];
-                first(rest(push(array, 4)));
-            "#,
-            expected: Object::Number(2.0),
+            input: "let f = fn(a) { a = a * 2; a }; f(21)",
+            expected: Object::Integer(42),
         },
         VmTestCase {
-            // rest([]) is Nil
-            input: "str(rest([]))",
-            expected: Object::Str(String::from("nil")),
+            input: "let x = 1; let f = fn() { x = x + 1 }; f(); f(); x",
+            expected: Object::Integer(3),
         },
+    ];
+
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_const_bindings() {
+    let tests = vec![
         VmTestCase {
-            input: r#"str("hello")"#,
-            expected: Object::Str(String::from("hello")),
+            input: "const x = 1; const f = fn() { x + 1 }; f()",
+            expected: Object::Integer(2),
         },
         VmTestCase {
-            input: "str(999)",
-            expected: Object::Str(String::from("999")),
+            input: "const f = fn(n) { if (n < 2) { n } else { f(n - 1) + f(n - 2) } }; f(10)",
+            expected: Object::Integer(55),
         },
         VmTestCase {
-            input: "str(str(999))",
-            expected: Object::Str(String::from("999")),
+            input: "let sum = 0; for (x in [1, 2, 3]) { const y = x * 2; sum = sum + y }; sum",
+            expected: Object::Integer(12),
         },
         VmTestCase {
-            input: "str(true)",
-            expected: Object::Str(String::from("true")),
+            input: "const [a, ...b] = [1, 2, 3]; const {c} = {\"c\": 4}; a + len(b) + c",
+            expected: Object::Integer(7),
         },
+        // the array of a constant can still be updated in place
         VmTestCase {
-            input: "str([1, 2, 3333, 4, 5])",
-            expected: Object::Str(String::from("[1, 2, 3333, 4, 5]")),
+            input: "const a = [1]; a[0] = 2; a[0]",
+            expected: Object::Integer(2),
         },
+        // a constant may be declared again once the block declaring it ends
         VmTestCase {
-            // maps are unordered so use only a single item
-            input: r#"str({"a": 1})"#,
-            expected: Object::Str(String::from(r#"{"a": 1}"#)),
+            input: "if (true) { const x = 1; x } else { const x = 2; x }",
+            expected: Object::Integer(1),
         },
         VmTestCase {
-            input: "str({})",
-            expected: Object::Str(String::from("{}")),
+            input: "match (2) { 1 => { const x = 1; x }, _ => { const x = 2; x } }",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: "if (true) { const x = 1; } const x = 2; x",
+            expected: Object::Integer(2),
+        },
+        // constants declared after a function don't restrict its bindings
+        VmTestCase {
+            input: "let f = fn(x) { let y = 3; x + y }; const x = 1; const y = 2; f(7)",
+            expected: Object::Integer(10),
         },
     ];
+
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_builtin_functions_display() {
+fn test_string_expressions() {
     let tests = vec![
         VmTestCase {
-            input: r#"puts("hello", "world!")"#,
-            expected: Object::Nil,
+            input: r#""monkey""#,
+            expected: Object::Str("monkey".to_string()),
         },
         VmTestCase {
-            input: r#"print("Hello, World!")"#,
-            expected: Object::Number(13.0),
+            input: r#""mon" + "key""#,
+            expected: Object::Str("monkey".to_string()),
         },
         VmTestCase {
-            input: r#"println("Hello, World!")"#,
-            expected: Object::Number(14.0),
+            input: r#""mon" + "key" + "banana""#,
+            expected: Object::Str("monkeybanana".to_string()),
         },
         VmTestCase {
-            input: r#"
-                format("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
-                "Hello", 1, true, 10, 65535
-            )"#,
-            expected: Object::Str("Hello     ,00001,true,1010,12,ffff,FFFF".to_string()),
+            input: r#"let name = "monkey"; "Hello ${name}!""#,
+            expected: Object::Str("Hello monkey!".to_string()),
         },
         VmTestCase {
-            input: r#"
-                print("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
-                "Hello", 1, true, 10, 65535
-            )"#,
-            expected: Object::Number(39.0),
+            input: r#"let f = fn() { }; "${1 + 2} ${[1, "a"]} ${ {"k": true}["k"] } ${f()}""#,
+            expected: Object::Str("3 [1, a] true nil".to_string()),
         },
         VmTestCase {
-            input: r#"
-                println("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
-                "Hello", 1, true, 10, 65535
-            )"#,
-            expected: Object::Number(40.0),
+            input: r#""${"in ${"side"}"}""#,
+            expected: Object::Str("in side".to_string()),
+        },
+        VmTestCase {
+            input: r#""\${x}""#,
+            expected: Object::Str("${x}".to_string()),
         },
     ];
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_builtin_function_failures() {
-    let tests: Vec<VmTestCaseErr> = vec![
-        VmTestCaseErr {
-            input: r#"len(1)"#,
-            expected: "len: unsupported argument",
+fn test_array_literals() {
+    let tests = vec![
+        VmTestCase {
+            input: "[]",
+            expected: Object::Arr(Rc::new(Array::new(Vec::new()))),
         },
-        VmTestCaseErr {
-            input: r#"len("one", "two")"#,
-            expected: "len: takes one argument. got=2",
+        VmTestCase {
+            input: "[1, 2, 3]",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(1)),
+                Rc::new(Object::Integer(2)),
+                Rc::new(Object::Integer(3)),
+            ]))),
         },
-        VmTestCaseErr {
-            input: r#"first(1)"#,
-            expected: "first: unsupported argument",
+        VmTestCase {
+            input: "[1 + 2, 3 * 4, 5 + 6]",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(3)),
+                Rc::new(Object::Integer(12)),
+                Rc::new(Object::Integer(11)),
+            ]))),
         },
-        VmTestCaseErr {
-            input: r#"last(1)"#,
-            expected: "last: unsupported argument",
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_hash_literals() {
+    let tests = vec![
+        VmTestCase {
+            input: "{}",
+            expected: Object::Map(Rc::new(HMap::default())),
         },
-        VmTestCaseErr {
-            input: r#"push(1, 1)"#,
-            expected: "push: unsupported argument",
+        VmTestCase {
+            input: "{1: 2, 2: 3}",
+            expected: Object::Map({
+                let map = HMap::default();
+                map.pairs.borrow_mut().insert(
+                    Rc::new(Object::Number(1.into())),
+                    Rc::new(Object::Number(2.into())),
+                );
+                map.pairs.borrow_mut().insert(
+                    Rc::new(Object::Number(2.into())),
+                    Rc::new(Object::Number(3.into())),
+                );
+                Rc::new(map)
+            }),
         },
-        VmTestCaseErr {
-            input: "str(fn() {})",
-            expected: "str: unsupported argument",
+        VmTestCase {
+            input: "{1 + 1: 2 * 2, 3 + 3: 4 * 4}",
+            expected: Object::Map({
+                let map = HMap::default();
+                map.pairs.borrow_mut().insert(
+                    Rc::new(Object::Number(2.into())),
+                    Rc::new(Object::Number(4.into())),
+                );
+                map.pairs.borrow_mut().insert(
+                    Rc::new(Object::Number(6.into())),
+                    Rc::new(Object::Number(16.into())),
+                );
+                Rc::new(map)
+            }),
         },
     ];
-
-    run_vm_negative_tests(&tests);
+    run_vm_tests(&tests);
 }
 
 #[test]
-fn test_closures() {
-    let tests: Vec<VmTestCase> = vec![
-        // newClosure returns a closure that closes over a free variable,
-        // the parameter 'a' of newClosure
+fn test_index_expressions() {
+    let tests = vec![
         VmTestCase {
-            input: r#"
-                let newClosure = fn(a) {
-                    fn() { a; }
-                }
-                let closure = newClosure(99);
-                closure();
-                "#,
-            expected: Object::Number(99.),
+            input: "[1, 2, 3][1]",
+            expected: Object::Integer(2),
         },
         VmTestCase {
-            input: r#"
-                let newAdder = fn(a, b) {
-                    fn(c) { a + b + c; }
-                }
-                let adder = newAdder(1, 2);
-                adder(8);
-                "#,
-            expected: Object::Number(11.),
+            input: "[1, 2, 3][0 + 2]",
+            expected: Object::Integer(3),
         },
         VmTestCase {
-            input: r#"
-                let newAdder = fn(a, b) {
-                    let c = a + b;
-                    fn(d) { c + d; }
-                }
-                let adder = newAdder(1, 2);
-                adder(8);
-                "#,
-            expected: Object::Number(11.),
+            input: "[[1, 1, 1]][0][0]",
+            expected: Object::Integer(1),
         },
         VmTestCase {
-            input: r#"
-                let newAdderOuter = fn(a, b) {
-                    let c = a + b;
-                    fn(d) {
-                        let e = d + c;
-                        fn(f) { e + f; };
-                    };
-                };
-                let newAdderInner = newAdderOuter(1, 2);
-                let adder = newAdderInner(3);
-                adder(8);
-                "#,
-            expected: Object::Number(14.),
+            input: "[][0]",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: r#"
-                let a = 1;
-                let newAdderOuter = fn(b) {
-                    fn(c) {
-                        fn(d) { a + b + c + d };
-                    };
-                };
-                let newAdderInner = newAdderOuter(2);
-                let adder = newAdderInner(3);
-                adder(8);
-                "#,
-            expected: Object::Number(14.),
+            input: "[1, 2, 3][99]",
+            expected: Object::Nil,
         },
         VmTestCase {
-            input: r#"
-                let newClosure = fn(a, b) {
-                    let one = fn() { a; };
-                    let two = fn() { b; };
-                    fn() { one() + two(); };
-                };
-                let closure = newClosure(9, 90);
-                closure();
-                "#,
-            expected: Object::Number(99.),
+            input: "[1][-1]",
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: "{1: 1, 2: 2}[1]",
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: "{1: 1, 2: 2}[2]",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: "{1: 1}[0]",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: "{}[0]",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"{"one": 1, "two": 2, "three": 3}["o" + "ne"]"#,
+            expected: Object::Integer(1),
         },
     ];
-
     run_vm_tests(&tests);
 }
 
 #[test]
-fn test_recursive_functions() {
-    let tests: Vec<VmTestCase> = vec![
+fn test_index_assignments() {
+    let tests = vec![
         VmTestCase {
-            input: r#"
-                let countDown = fn(x) {
-                    if (x == 0) {
-                        return 0;
-                    } else {
-                        countDown(x - 1);
-                    }
-                };
-                countDown(1);
-                "#,
-            expected: Object::Number(0.),
+            input: "let a = [1, 2, 3]; a[1] = 5; a",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(1)),
+                Rc::new(Object::Integer(5)),
+                Rc::new(Object::Integer(3)),
+            ]))),
         },
         VmTestCase {
-            input: r#"
-                let countDown = fn(x) {
+            input: r#"let m = {}; m["a"] = 1; m["a"]"#,
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: r#"let m = {"x": 1}; m["x"] = m["x"] + 1"#,
+            expected: Object::Integer(2),
+        },
+        // Arrays and maps are shared by reference
+        VmTestCase {
+            input: "let a = [1, 2]; let b = a; b[0] = 9; a[0]",
+            expected: Object::Integer(9),
+        },
+        VmTestCase {
+            input: "let a = [[1], [2]]; a[1][0] = 5; a[1][0]",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "let f = fn(arr) { arr[0] = 10 }; let a = [1]; f(a); a[0]",
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: "let f = fn() { let m = {}; for (x in [1, 2, 3]) { m[x] = x * x }; m[3] }; f()",
+            expected: Object::Integer(9),
+        },
+        // 'push' returns a new array
+        VmTestCase {
+            input: "let a = [1, 2]; let b = push(a, 3); b[0] = 7; a[0] + len(a)",
+            expected: Object::Integer(3),
+        },
+        // An array or a map that holds itself is printed and compared once
+        VmTestCase {
+            input: "let a = [1]; a[0] = a; str(a)",
+            expected: Object::Str("[[...]]".to_string()),
+        },
+        VmTestCase {
+            input: r#"let m = {"k": 1}; m["k"] = m; "${m}""#,
+            expected: Object::Str(r#"{"k": {...}}"#.to_string()),
+        },
+        VmTestCase {
+            input: "let a = [1]; a[0] = a; let b = [1]; b[0] = b; [a == a, a == b, a == [1]]",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Bool(true)),
+                Rc::new(Object::Bool(true)),
+                Rc::new(Object::Bool(false)),
+            ]))),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: "let a = [1]; a[1] = 2",
+            expected: "array index out of bounds: 1",
+        },
+        VmTestCaseErr {
+            input: "let a = [1]; a[-2] = 2",
+            expected: "array index out of bounds: -2",
+        },
+        VmTestCaseErr {
+            input: r#"let a = [1]; a["x"] = 2"#,
+            expected: "invalid index to array object",
+        },
+        VmTestCaseErr {
+            input: "let a = [1]; a[1 / 2] = 2",
+            expected: "invalid index to array object",
+        },
+        VmTestCaseErr {
+            input: "let m = {}; m[[1]] = 2",
+            expected: "hash key should be a numeric, a string or a boolean",
+        },
+        VmTestCaseErr {
+            input: r#"let s = "abc"; s[0] = "x""#,
+            expected: "index assignment not supported",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_calling_functions_without_args() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+            let fivePlusTen = fn() { 5 + 10; };
+            fivePlusTen();
+            "#,
+            expected: Object::Integer(15),
+        },
+        VmTestCase {
+            input: r#"
+            let f1 = fn() { 1 };
+            let f2 = fn() { 2 };
+            f1() + f2()
+            "#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"
+            let f1 = fn() { 1 };
+            let f2 = fn() { f1() + 1 };
+            let f3 = fn() { f2() + 1 };
+            f3()
+            "#,
+            expected: Object::Integer(3),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_calling_functions_with_return() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+            let earlyExit = fn() { return 99; 100; };
+            earlyExit();
+            "#,
+            expected: Object::Integer(99),
+        },
+        VmTestCase {
+            input: r#"
+            let earlyExit = fn() { return 99; return 100; };
+            earlyExit();
+            "#,
+            expected: Object::Integer(99),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_calling_functions_without_return() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+            let noReturn = fn() { };
+            noReturn();
+            "#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"
+            let noReturn1 = fn() { };
+            let noReturn2 = fn() { noReturn1(); };
+            noReturn1();
+            noReturn2();
+            "#,
+            expected: Object::Nil,
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_first_class_functions_() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+                let returnsOne = fn() { 1; };
+                let returnsOneReturner = fn() { returnsOne; };
+                returnsOneReturner()();
+            "#,
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: r#"
+                let returnsOneReturner = fn() {
+                let returnsOne = fn() { 1; };
+                returnsOne;
+                };
+                returnsOneReturner()();
+            "#,
+            expected: Object::Integer(1),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_calling_functions_with_bindings() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+                let one = fn() { let one = 1; one };
+                one();
+            "#,
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: r#"
+                let one_and_two = fn() { let one = 1; let two = 2; one + two; };
+                one_and_two();
+            "#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"
+                let one_and_two = fn() { let one = 1; let two = 2; one + two; };
+                let three_and_four = fn() { let three = 3; let four = 4; three + four; };
+                one_and_two() + three_and_four();
+            "#,
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: r#"
+                let first_foobar = fn() { let foobar = 50; foobar; };
+                let second_foobar = fn() { let foobar = 100; foobar; };
+                first_foobar() + second_foobar();
+            "#,
+            expected: Object::Integer(150),
+        },
+        VmTestCase {
+            input: r#"
+                let global_seed = 50;
+                let minus_one = fn() {
+                    let num = 1;
+                    global_seed - num;
+                };
+                let minus_two = fn() {
+                    let num = 2;
+                    global_seed - num;
+                };
+                minus_one() + minus_two();
+            "#,
+            expected: Object::Integer(97),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_calling_functions_with_args_and_bindings() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"
+                let identity = fn(a) { a; };
+                identity(4);
+            "#,
+            expected: Object::Integer(4),
+        },
+        VmTestCase {
+            input: r#"
+                let sum = fn(a, b) { a + b; };
+                sum(1, 2);
+            "#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"
+                let sum = fn(a, b) { let c = a + b; c; };
+                sum(1, 2);
+            "#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"
+                let sum = fn(a, b) { let c = a + b; c; };
+                sum(1, 2) + sum(3, 4);
+            "#,
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: r#"
+                let sum = fn(a, b) { let c = a + b; c; };
+                let outer = fn() { sum(1, 2) + sum(3, 4); };
+                outer();
+            "#,
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: r#"
+                let globalNum = 10;
+                let sum = fn(a, b) {
+                    let c = a + b;
+                    c + globalNum;
+                };
+                let outer = fn() {
+                    sum(1, 2) + sum(3, 4) + globalNum;
+                };
+                outer() + globalNum;
+            "#,
+            expected: Object::Integer(50),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_wrong_number_of_arguments() {
+    let tests: Vec<VmTestCaseErr> = vec![
+        VmTestCaseErr {
+            input: "fn() { 1; }(1);",
+            expected: "wrong number of arguments: want=0, got=1",
+        },
+        VmTestCaseErr {
+            input: "fn(a) { a; }();",
+            expected: "wrong number of arguments: want=1, got=0",
+        },
+        VmTestCaseErr {
+            input: "fn(a, b) { a + b; }(1);",
+            expected: "wrong number of arguments: want=2, got=1",
+        },
+    ];
+
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_deferred_expressions() {
+    let tests = vec![
+        VmTestCase {
+            input: "fn() { defer 10; 5 }()",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "fn() { return 5; defer len(1); }()",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "fn() { if (false) { defer len(1); } 5 }()",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "let f = fn(x) { if (x) { defer len(1); } 5 }; f(false); f(false)",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "fn() { defer 10; }()",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"let log = ""; let f = fn() { defer log = log + "a"; defer log = log + "b"; 5 }; f(); log"#,
+            expected: Object::Str("ba".into()),
+        },
+        VmTestCase {
+            // the deferred expressions of the functions left by an error
+            // are evaluated before it is caught
+            input: r#"let log = ""; let f = fn() { defer log = log + "d"; throw "x"; }; try { f(); } catch (e) { log = log + e; } log"#,
+            expected: Object::Str("dx".into()),
+        },
+        VmTestCase {
+            input: r#"let log = "";
+                let g = fn() { defer log = log + "g"; len(1); };
+                let f = fn() { defer log = log + "f"; g(); };
+                try { f(); } catch (e) { log = log + e.message; }
+                log"#,
+            expected: Object::Str("gflen: unsupported argument".into()),
+        },
+        VmTestCase {
+            // a failing deferred expression doesn't stop the earlier ones
+            input: r#"let log = ""; let f = fn() { defer log = log + "a"; defer len(1); 5 }; try { f(); } catch (e) { log = log + e.message; } log"#,
+            expected: Object::Str("alen: unsupported argument".into()),
+        },
+    ];
+
+    run_vm_tests(&tests);
+
+    let tests: Vec<VmTestCaseErr> = vec![
+        VmTestCaseErr {
+            input: "fn() { defer len(1); 5 }()",
+            expected: "len: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: "fn() { defer len(1); return 5; }()",
+            expected: "len: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: "fn() { defer len(1); if (true) { return 5; } 10 }()",
+            expected: "len: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: "fn() { defer len(1); }()",
+            expected: "len: unsupported argument",
+        },
+        // Deferred expressions are evaluated in reverse order and the
+        // error of the last one to fail is passed on
+        VmTestCaseErr {
+            input: "fn() { defer len(1); defer first(1); 5 }()",
+            expected: "len: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: r#"fn() { defer 1; throw "x"; }()"#,
+            expected: "x",
+        },
+    ];
+
+    run_vm_negative_tests(&tests);
+
+    let tests = vec![
+        VmTestCase {
+            input: "let n = 0; for (x in [1, 2]) { let f = fn() { defer n = n + x; }; f() }; n",
+            expected: Object::Integer(3),
+        },
+        // a defer in a loop registers its expression each time it is reached
+        VmTestCase {
+            input: r#"let log = "";
+                let f = fn() {
+                    defer log = log + "a";
+                    for (x in [1, 2, 3]) { if (x != 2) { defer log = log + "d"; } }
+                    log = log + "r";
+                };
+                f(); f(); log"#,
+            expected: Object::Str("rddardda".into()),
+        },
+        VmTestCase {
+            input: r#"let log = ""; let f = fn() { let i = 0; while (i < 2) { defer log = log + "d"; i = i + 1; } throw "x"; }; try { f(); } catch (e) { log = log + e; } log"#,
+            expected: Object::Str("ddx".into()),
+        },
+    ];
+
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_builtin_functions() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"len("")"#,
+            expected: Object::Integer(0),
+        },
+        VmTestCase {
+            input: r#"len("four")"#,
+            expected: Object::Integer(4),
+        },
+        VmTestCase {
+            input: r#"len("hello world")"#,
+            expected: Object::Integer(11),
+        },
+        VmTestCase {
+            input: r#"len([1, 2, 3])"#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"len([])"#,
+            expected: Object::Integer(0),
+        },
+        VmTestCase {
+            input: r#"first([1, 2, 3])"#,
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: r#"first([])"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"last([1, 2, 3])"#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"last([])"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"rest([1, 2, 3])"#,
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Integer(2)),
+                Rc::new(Object::Integer(3)),
+            ]))),
+        },
+        VmTestCase {
+            input: r#"rest([])"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"push([], 1)"#,
+            expected: Object::Arr(Rc::new(Array::new(vec![Rc::new(Object::Integer(1))]))),
+        },
+        VmTestCase {
+            input: r#"
+                let array = [1, 2, 3];
+                first(rest(push(array, 4)));
+            "#,
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            // rest([]) is Nil
+            input: "str(rest([]))",
+            expected: Object::Str(String::from("nil")),
+        },
+        VmTestCase {
+            input: r#"str("hello")"#,
+            expected: Object::Str(String::from("hello")),
+        },
+        VmTestCase {
+            input: "str(999)",
+            expected: Object::Str(String::from("999")),
+        },
+        VmTestCase {
+            input: "str(str(999))",
+            expected: Object::Str(String::from("999")),
+        },
+        VmTestCase {
+            input: "str(true)",
+            expected: Object::Str(String::from("true")),
+        },
+        VmTestCase {
+            input: "str([1, 2, 3333, 4, 5])",
+            expected: Object::Str(String::from("[1, 2, 3333, 4, 5]")),
+        },
+        VmTestCase {
+            // maps are unordered so use only a single item
+            input: r#"str({"a": 1})"#,
+            expected: Object::Str(String::from(r#"{"a": 1}"#)),
+        },
+        VmTestCase {
+            input: "str({})",
+            expected: Object::Str(String::from("{}")),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_builtin_functions_display() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"puts("hello", "world!")"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"print("Hello, World!")"#,
+            expected: Object::Integer(13),
+        },
+        VmTestCase {
+            input: r#"println("Hello, World!")"#,
+            expected: Object::Integer(14),
+        },
+        VmTestCase {
+            input: r#"
+                format("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
+                "Hello", 1, true, 10, 65535
+            )"#,
+            expected: Object::Str("Hello     ,00001,true,1010,12,ffff,FFFF".to_string()),
+        },
+        VmTestCase {
+            input: r#"
+                print("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
+                "Hello", 1, true, 10, 65535
+            )"#,
+            expected: Object::Integer(39),
+        },
+        VmTestCase {
+            input: r#"
+                println("{0:<10},{1:0>5},{2},{3:b},{3:o},{4:x},{4:X}",
+                "Hello", 1, true, 10, 65535
+            )"#,
+            expected: Object::Integer(40),
+        },
+    ];
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_builtin_function_failures() {
+    let tests: Vec<VmTestCaseErr> = vec![
+        VmTestCaseErr {
+            input: r#"len(1)"#,
+            expected: "len: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: r#"len("one", "two")"#,
+            expected: "len: takes one argument. got=2",
+        },
+        VmTestCaseErr {
+            input: r#"first(1)"#,
+            expected: "first: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: r#"last(1)"#,
+            expected: "last: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: r#"push(1, 1)"#,
+            expected: "push: unsupported argument",
+        },
+        VmTestCaseErr {
+            input: "str(fn() {})",
+            expected: "str: unsupported argument",
+        },
+    ];
+
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_closures() {
+    let tests: Vec<VmTestCase> = vec![
+        // newClosure returns a closure that closes over a free variable,
+        // the parameter 'a' of newClosure
+        VmTestCase {
+            input: r#"
+                let newClosure = fn(a) {
+                    fn() { a; }
+                }
+                let closure = newClosure(99);
+                closure();
+                "#,
+            expected: Object::Integer(99),
+        },
+        VmTestCase {
+            input: r#"
+                let newAdder = fn(a, b) {
+                    fn(c) { a + b + c; }
+                }
+                let adder = newAdder(1, 2);
+                adder(8);
+                "#,
+            expected: Object::Integer(11),
+        },
+        VmTestCase {
+            input: r#"
+                let newAdder = fn(a, b) {
+                    let c = a + b;
+                    fn(d) { c + d; }
+                }
+                let adder = newAdder(1, 2);
+                adder(8);
+                "#,
+            expected: Object::Integer(11),
+        },
+        VmTestCase {
+            input: r#"
+                let newAdderOuter = fn(a, b) {
+                    let c = a + b;
+                    fn(d) {
+                        let e = d + c;
+                        fn(f) { e + f; };
+                    };
+                };
+                let newAdderInner = newAdderOuter(1, 2);
+                let adder = newAdderInner(3);
+                adder(8);
+                "#,
+            expected: Object::Integer(14),
+        },
+        VmTestCase {
+            input: r#"
+                let a = 1;
+                let newAdderOuter = fn(b) {
+                    fn(c) {
+                        fn(d) { a + b + c + d };
+                    };
+                };
+                let newAdderInner = newAdderOuter(2);
+                let adder = newAdderInner(3);
+                adder(8);
+                "#,
+            expected: Object::Integer(14),
+        },
+        VmTestCase {
+            input: r#"
+                let newClosure = fn(a, b) {
+                    let one = fn() { a; };
+                    let two = fn() { b; };
+                    fn() { one() + two(); };
+                };
+                let closure = newClosure(9, 90);
+                closure();
+                "#,
+            expected: Object::Integer(99),
+        },
+    ];
+
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_closures_with_assignments() {
+    let tests = vec![
+        VmTestCase {
+            input: "
+            let counter = fn() { let c = 0; fn() { c = c + 1 } };
+            let next = counter();
+            next(); next(); next()
+            ",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "
+            let f = fn() { let c = 0; let inc = fn() { c = c + 1 }; inc(); inc(); c };
+            f()
+            ",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: "
+            let f = fn() { let x = 1; let g = fn() { x }; x = 5; g() };
+            f()
+            ",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "
+            let f = fn() { let c = 0; let g = fn() { fn() { c = c + 10 } }; g()(); c };
+            f()
+            ",
+            expected: Object::Integer(10),
+        },
+        VmTestCase {
+            input: "
+            let make = fn() { let c = 0; fn() { c = c + 1 } };
+            let a = make(); let b = make();
+            a(); a(); b()
+            ",
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: "
+            let f = fn(n) { let g = fn() { n = n - 1 }; while (n > 0) { g() }; n };
+            f(5)
+            ",
+            expected: Object::Integer(0),
+        },
+    ];
+
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_recursive_functions() {
+    let tests: Vec<VmTestCase> = vec![
+        VmTestCase {
+            input: r#"
+                let countDown = fn(x) {
+                    if (x == 0) {
+                        return 0;
+                    } else {
+                        countDown(x - 1);
+                    }
+                };
+                countDown(1);
+                "#,
+            expected: Object::Integer(0),
+        },
+        VmTestCase {
+            input: r#"
+                let countDown = fn(x) {
                     if (x == 0) {
                         return 0;
                     } else {
@@ -1046,29 +2104,626 @@ fn test_recursive_functions() {
                 };
                 wrapper();
             "#,
-            expected: Object::Number(0.),
+            expected: Object::Integer(0),
+        },
+        // define a recursive function inside another function and also
+        // call it inside this other function.
+        VmTestCase {
+            input: r#"
+                let wrapper = fn() {
+                    let countDown = fn(x) {
+                        if (x == 0) {
+                            return 0;
+                        } else {
+                            countDown(x - 1);
+                        }
+                    };
+                    countDown(1);
+                };
+                wrapper();
+            "#,
+            expected: Object::Integer(0),
+        },
+    ];
+
+    run_vm_tests(&tests);
+}
+
+#[test]
+fn test_slice_expressions() {
+    let tests = vec![
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][1:3]",
+            expected: integer_array(&[2, 3]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][-2:]",
+            expected: integer_array(&[4, 5]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][:-3]",
+            expected: integer_array(&[1, 2]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][::2]",
+            expected: integer_array(&[1, 3, 5]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][::-1]",
+            expected: integer_array(&[5, 4, 3, 2, 1]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3, 4, 5][3:0:-2]",
+            expected: integer_array(&[4, 2]),
+        },
+        VmTestCase {
+            // bounds out of range are clamped
+            input: "[1, 2, 3][-10:10]",
+            expected: integer_array(&[1, 2, 3]),
+        },
+        VmTestCase {
+            input: "[1, 2, 3][2:1]",
+            expected: integer_array(&[]),
+        },
+        VmTestCase {
+            // slicing copies the elements into a new array
+            input: "let a = [1, 2]; let b = a[:]; b[0] = 3; a",
+            expected: integer_array(&[1, 2]),
+        },
+        VmTestCase {
+            input: r#""héllo"[1:3]"#,
+            expected: Object::Str("él".into()),
+        },
+        VmTestCase {
+            input: r#""héllo"[::-1]"#,
+            expected: Object::Str("olléh".into()),
+        },
+        VmTestCase {
+            input: r#""héllo"[1]"#,
+            expected: Object::Str("é".into()),
+        },
+        VmTestCase {
+            input: r#""héllo"[-1]"#,
+            expected: Object::Str("o".into()),
+        },
+        VmTestCase {
+            input: r#""abc"[3]"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: "let a = [1, 2, 3]; a[-1] = 4; a",
+            expected: integer_array(&[1, 2, 4]),
+        },
+        // a step past the end of the i64 range
+        VmTestCase {
+            input: "[1, 2, 3][::9223372036854775807]",
+            expected: integer_array(&[1]),
+        },
+        VmTestCase {
+            input: r#""abc"[::-9223372036854775807]"#,
+            expected: Object::Str("c".into()),
+        },
+        // 'len' counts the characters of a string, as indexing does
+        VmTestCase {
+            input: r#"let s = "héllo"; s[len(s) - 1]"#,
+            expected: Object::Str("o".into()),
         },
-        // define a recursive function inside another function and also
-        // call it inside this other function.
+        VmTestCase {
+            input: "[1, 2][1.0]",
+            expected: Object::Integer(2),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: "[1, 2][::0]",
+            expected: "slice step cannot be zero",
+        },
+        VmTestCaseErr {
+            input: "[1, 2][0.5]",
+            expected: "invalid index to array object",
+        },
+        VmTestCaseErr {
+            input: r#""héllo"[1.7]"#,
+            expected: "invalid index to string object",
+        },
+        VmTestCaseErr {
+            input: "[1, 2][0.5:]",
+            expected: "slice indices must be integers",
+        },
+        VmTestCaseErr {
+            input: "{1: 2}[1:]",
+            expected: "slice operator not supported",
+        },
+        VmTestCaseErr {
+            input: r#""abc"["a"]"#,
+            expected: "invalid index to string object",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_property_and_method_calls() {
+    let tests = vec![
+        VmTestCase {
+            input: r#"let config = {"host": "localhost"}; config.host"#,
+            expected: Object::Str("localhost".into()),
+        },
+        VmTestCase {
+            input: r#"{"a": 1}.b"#,
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: r#"let m = {"a": {"b": [1, 2]}}; m.a.b[1]"#,
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: r#"let m = {}; m.x = 5; m["x"]"#,
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: r#""a,b,c".split(",")[2]"#,
+            expected: Object::Str("c".into()),
+        },
+        VmTestCase {
+            input: r#""héllo".split("").len()"#,
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: "[1, 2].push(3).len()",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            // functions stored in a map are called without the receiver
+            input: r#"let m = {"double": fn(x) { x * 2 }}; m.double(4)"#,
+            expected: Object::Integer(8),
+        },
+        VmTestCase {
+            // members of a map take precedence over builtins
+            input: r#"let m = {"len": fn() { 42 }}; m.len()"#,
+            expected: Object::Integer(42),
+        },
+        VmTestCase {
+            input: r#"let f = fn(s, sep) { s.split(sep).len() }; f("a-b", "-") + f("c", "-")"#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"let args = [","]; "x,y".split(...args).len()"#,
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            // functions defined by the program are called with the receiver
+            input: "let map = fn(arr, f) { let out = []; for (x in arr) { out = out.push(f(x)) } out };
+                    [1, 2, 3].map(fn(x) { x * 2 })[2]",
+            expected: Object::Integer(6),
+        },
+        VmTestCase {
+            input: "let f = fn() { let sum = fn(arr, n) { arr.len() + n }; fn() { [1, 2].sum(...[10]) } }; f()()",
+            expected: Object::Integer(12),
+        },
+        VmTestCase {
+            // and take precedence over builtins
+            input: "let len = fn(arr) { 42 }; [1].len()",
+            expected: Object::Integer(42),
+        },
+        VmTestCase {
+            // bindings that aren't functions are skipped
+            input: "let len = 5; [1, 2].len()",
+            expected: Object::Integer(2),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: "[1].foo",
+            expected: "property access not supported",
+        },
+        VmTestCaseErr {
+            input: "[1].foo()",
+            expected: "undefined method 'foo'",
+        },
+        VmTestCaseErr {
+            input: r#"let m = {"f": 1}; m.f()"#,
+            expected: "calling non-function",
+        },
+        VmTestCaseErr {
+            input: r#""a".split(1)"#,
+            expected: "split: unsupported argument",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_pipeline() {
+    let tests = vec![
+        VmTestCase {
+            input: "[1, 2, 3] |> len",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "let sub = fn(a, b) { a - b }; 10 |> sub(3)",
+            expected: Object::Integer(7),
+        },
+        VmTestCase {
+            input:
+                "let double = fn(x) { x * 2 }; let add = fn(a, b) { a + b }; 5 |> double |> add(1)",
+            expected: Object::Integer(11),
+        },
+        VmTestCase {
+            input: r#""a,b,c" |> split(",") |> len()"#,
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "3 |> fn(x) { x * x }",
+            expected: Object::Integer(9),
+        },
+        VmTestCase {
+            input: r#"let m = {"sub": fn(a, b) { a - b }}; 10 |> m.sub(4)"#,
+            expected: Object::Integer(6),
+        },
+        VmTestCase {
+            input: "let f = fn(a, ...rest) { a + len(rest) }; 1 + 1 |> f(...[0, 0])",
+            expected: Object::Integer(4),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![VmTestCaseErr {
+        input: "1 |> 2",
+        expected: "calling non-function",
+    }];
+    run_vm_negative_tests(&tests);
+}
+
+#[test]
+fn test_match_expression() {
+    let tests = vec![
         VmTestCase {
             input: r#"
-                let wrapper = fn() {
-                    let countDown = fn(x) {
-                        if (x == 0) {
-                            return 0;
-                        } else {
-                            countDown(x - 1);
-                        }
-                    };
-                    countDown(1);
-                };
-                wrapper();
-            "#,
-            expected: Object::Number(0.),
+        let f = fn(x) {
+            match (x) {
+                0 => "zero",
+                -1 => "minus one",
+                "s" => "string",
+                nil => "nil",
+                [] => "empty",
+                [a, [b, c], ...rest] if a > 0 => "nested " + str(a + b + c + len(rest)),
+                [_, _] => "pair",
+                {"k": 1, name} => "name " + name,
+                {"k": k} => "k " + str(k),
+                n if n == 10 => { let m = n * 2; "ten " + str(m) },
+                _ => "other"
+            }
+        };
+        let args = [0, -1, "s", nil, [], [1, [2, 3], 4, 5], [0, [2, 3]], {"k": 1, "name": "bob"}, {"k": 2}, 10, 5];
+        let result = "";
+        for (arg in args) {
+            result = result + f(arg) + ";";
+        }
+        result
+        "#,
+            expected: Object::Str("zero;minus one;string;nil;empty;nested 8;pair;name bob;k 2;ten 20;other;".into()),
+        },
+        VmTestCase {
+            input: "let x = match ([1, 2]) { [a, b] => a + b }; x",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: "let f = fn(p) { match (p) { [x, y] if x == y => x, [x, _] => -x } }; f([2, 2]) + f([3, 1])",
+            expected: Object::Integer(-1),
+        },
+        VmTestCase {
+            input: "match (1) { 1 => { } }",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            // only false and nil fail a guard
+            input: "match (5) { n if 0 => 1, _ => 2 }",
+            expected: Object::Integer(1),
+        },
+        VmTestCase {
+            input: "match (5) { n if nil => 1, _ => 2 }",
+            expected: Object::Integer(2),
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![VmTestCaseErr {
+        input: "let x = 3;\nmatch (x) { 1 => 1, [a] => a }",
+        expected: "no match arm for 3",
+    }];
+    run_vm_negative_tests(&tests);
+    let mut vm = VM::new(test_compile("let x = 3;\nmatch (x) { 1 => 1 }"));
+    assert_eq!(vm.run().expect_err("no error").line, 2);
+}
+
+#[test]
+fn test_try_and_throw() {
+    let tests = vec![
+        VmTestCase {
+            input: "let x = 0; try { throw 5; } catch (e) { x = e; } x",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            input: r#"let m = ""; try { 1(); } catch (e) { m = e.message; } m"#,
+            expected: Object::Str("calling non-function".into()),
+        },
+        VmTestCase {
+            input: "let m = 0;\ntry {\n  len(1);\n} catch (e) { m = [e.message, e.line]; }\nm",
+            expected: Object::Arr(Rc::new(Array::new(vec![
+                Rc::new(Object::Str("len: unsupported argument".into())),
+                Rc::new(Object::Integer(3)),
+            ]))),
+        },
+        VmTestCase {
+            // the frames of the calls are dropped
+            input: r#"let f = fn(n) { if (n == 0) { throw "deep"; } f(n - 1) };
+                let m = "";
+                try { f(20); } catch (e) { m = e; }
+                m"#,
+            expected: Object::Str("deep".into()),
+        },
+        VmTestCase {
+            // the stack is restored for each iteration of the loop
+            input: "let s = 0; for (i in [1, 2, 3]) { try { if (i == 2) { throw i; } s = s + i; } catch (e) { s = s + 10 * e; } } s",
+            expected: Object::Integer(24),
+        },
+        VmTestCase {
+            input: r#"let log = ""; let f = fn() { try { log = log + "t"; return "r"; } finally { log = log + "f"; } }; f() + log"#,
+            expected: Object::Str("rtf".into()),
+        },
+        VmTestCase {
+            input: r#"let log = ""; try { try { throw "x"; } finally { log = log + "f"; } } catch (e) { log = log + e; } log"#,
+            expected: Object::Str("fx".into()),
+        },
+        VmTestCase {
+            input: r#"let log = ""; try { try { throw "a"; } catch (e) { throw e + "b"; } finally { log = log + "f"; } } catch (e) { log = log + e; } log"#,
+            expected: Object::Str("fab".into()),
+        },
+        VmTestCase {
+            input: r#"let log = ""; let f = fn() { try { throw 1; } catch (e) { return e + 1; } finally { log = "f"; } }; str(f()) + log"#,
+            expected: Object::Str("2f".into()),
+        },
+        VmTestCase {
+            input: "let f = fn() { try { throw 1; } finally { return 2; } }; f()",
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            // an error in the finally block of a return is not caught by its own catch
+            input: r#"let f = fn() { try { return 1; } catch (e) { return 3; } finally { throw "f"; } };
+                let m = "";
+                try { f(); } catch (e) { m = e; }
+                m"#,
+            expected: Object::Str("f".into()),
+        },
+        VmTestCase {
+            input: "let f = fn() { try { 1 } catch (e) { 2 } }; f()",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            // a caught error that is thrown again keeps its line
+            input: "let l = 0;\ntry {\n  try { 1 / 0; }\n  catch (e) { throw e; }\n} catch (e) { l = e.line; }\nl",
+            expected: Object::Integer(3),
         },
     ];
+    run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: r#"throw "oops";"#,
+            expected: "oops",
+        },
+        VmTestCaseErr {
+            input: "try { 1 / 0; } catch (e) { throw e; }",
+            expected: "division by zero",
+        },
+        VmTestCaseErr {
+            input: "try { throw 1; } finally { 2 }",
+            expected: "1",
+        },
+    ];
+    run_vm_negative_tests(&tests);
+    let mut vm = VM::new(test_compile("let x = 1;\nthrow x;"));
+    assert_eq!(vm.run().expect_err("no error").line, 2);
+}
+
+// Write the files of a program to a directory of its own
+#[cfg(test)]
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("monkey-vm-{}-{}", name, process::id()));
+    for (file, source) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    dir.canonicalize().unwrap()
+}
+
+#[cfg(test)]
+fn compile_file(file: &Path) -> Result<Bytecode, CompileError> {
+    let source = fs::read_to_string(file).unwrap();
+    let scanner = Scanner::new(&source);
+    let mut parser = Parser::new(scanner);
+    let program = parser.parse_program();
+    check_parse_errors(&parser);
+    let mut compiler = Compiler::new();
+    compiler.set_file(file.to_path_buf());
+    compiler.compile(program)?;
+    Ok(compiler.bytecode())
+}
+
+#[test]
+fn test_import() {
+    let dir = write_files(
+        "import",
+        &[
+            (
+                "main.mky",
+                r#"import "lib/math.mky" as math;
+                import "lib/util.mky" as util;
+                let f = fn() { import "lib/math.mky" as m; m.counter() };
+                [math.square(4), math.pi, math._hidden, util.twice(3), math.counter(), util.count(), f(), math.n]"#,
+            ),
+            (
+                "lib/math.mky",
+                "let pi = 3; let _hidden = 1; let n = 0;
+                let square = fn(x) { x * x };
+                let counter = fn() { n = n + 1; n };",
+            ),
+            (
+                "lib/util.mky",
+                r#"import "math.mky" as math;
+                let twice = fn(x) { math.square(x) * 2 };
+                let count = fn() { math.counter() };"#,
+            ),
+        ],
+    );
+    let mut vm = VM::new(compile_file(&dir.join("main.mky")).unwrap());
+    vm.run().unwrap();
+    // the names starting with an underscore are not exported
+    let expected = [
+        Some(16),
+        Some(3),
+        None,
+        Some(18),
+        Some(1),
+        Some(2),
+        Some(3),
+        Some(0),
+    ]
+    .iter()
+    .map(|n| Rc::new(n.map_or(Object::Nil, Object::Integer)))
+    .collect();
+    test_expected_object(
+        vm.last_popped(),
+        &Object::Arr(Rc::new(Array::new(expected))),
+    );
+}
+
+#[test]
+fn test_import_errors() {
+    let dir = write_files(
+        "import-errors",
+        &[
+            ("main.mky", "let x = 1;\nimport \"a.mky\" as a;"),
+            ("a.mky", "import \"b.mky\" as b;"),
+            ("b.mky", "\nimport \"main.mky\" as m;"),
+            ("missing.mky", "import \"nope.mky\" as n;"),
+            ("bad.mky", "import \"lib.mky\" x;"),
+            ("defer.mky", "defer 1;"),
+            ("import-defer.mky", "import \"defer.mky\" as d;"),
+        ],
+    );
+    let tests = [
+        (
+            "main.mky",
+            "import cycle: main.mky -> a.mky -> b.mky -> main.mky",
+            2,
+        ),
+        ("missing.mky", "module 'nope.mky' not found", 1),
+        (
+            "import-defer.mky",
+            "defer is only allowed inside a function",
+            1,
+        ),
+    ];
+    for (file, expected, line) in tests {
+        match compile_file(&dir.join(file)) {
+            Ok(_) => panic!("expected a compile error for {}", file),
+            Err(err) => {
+                assert_eq!(err.msg, expected);
+                assert_eq!(err.line, line);
+            }
+        }
+    }
+    let err = compile_file(&dir.join("a.mky")).err().map(|e| e.msg);
+    assert_eq!(
+        err.as_deref(),
+        Some("import cycle: a.mky -> b.mky -> main.mky -> a.mky")
+    );
+}
 
+#[test]
+fn test_nil_operators() {
+    let tests = vec![
+        VmTestCase {
+            input: "nil",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: "let a = nil; a == nil",
+            expected: Object::Bool(true),
+        },
+        VmTestCase {
+            input: "nil ?? 5",
+            expected: Object::Integer(5),
+        },
+        VmTestCase {
+            // only nil is replaced, not every falsy value
+            input: "false ?? 5",
+            expected: Object::Bool(false),
+        },
+        VmTestCase {
+            input: "nil ?? nil ?? 3",
+            expected: Object::Integer(3),
+        },
+        VmTestCase {
+            input: r#"let cfg = {"db": {"port": 80}}; cfg?.db?.port"#,
+            expected: Object::Integer(80),
+        },
+        VmTestCase {
+            input: r#"let cfg = {}; cfg.db?.port ?? 8080"#,
+            expected: Object::Integer(8080),
+        },
+        VmTestCase {
+            input: r#"let cfg = {"ports": [1, 2]}; cfg.ports?.[-1]"#,
+            expected: Object::Integer(2),
+        },
+        VmTestCase {
+            input: "let a = nil; a?.[0:1]",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            // the right operand and the index are not evaluated
+            input: "let n = 0; let f = fn() { n = n + 1 }; 1 ?? f(); nil?.[f()]; nil?.x(f()); n",
+            expected: Object::Integer(0),
+        },
+        VmTestCase {
+            input: r#"let f = fn(m) { m?.a ?? "none" }; f(nil) + str(f({"a": 1}))"#,
+            expected: Object::Str("none1".into()),
+        },
+        // a '?.' that finds nil skips the rest of the chain
+        VmTestCase {
+            input: "nil?.a.b",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: "let n = 0; let f = fn() { n = n + 1 }; let a = nil; a?.b.c(f())[f()]; n",
+            expected: Object::Integer(0),
+        },
+        VmTestCase {
+            input: "let a = nil; len([a?.b.c, 1]) + (a?.b.c ?? 5)",
+            expected: Object::Integer(7),
+        },
+    ];
     run_vm_tests(&tests);
+
+    let tests = vec![
+        VmTestCaseErr {
+            input: "nil.a?.b",
+            expected: "property access not supported",
+        },
+        VmTestCaseErr {
+            // only the object of the '?.' is tested for nil
+            input: r#"{"a": nil}?.a.b"#,
+            expected: "property access not supported",
+        },
+    ];
+    run_vm_negative_tests(&tests);
 }
 
 #[test]
@@ -1088,8 +2743,38 @@ fn test_recursive_fibonacci() {
                 };
                 fibonacci(15);
                 "#,
-        expected: Object::Number(610.),
+        expected: Object::Integer(610),
     }];
 
     run_vm_tests(&tests);
 }
+
+#[test]
+fn test_help_builtin() {
+    let tests = vec![
+        VmTestCase {
+            input: "/// Add two numbers\n/// and return the sum\nlet add = fn(a, b) { a + b }; help(add)",
+            expected: Object::Str("Add two numbers\nand return the sum".into()),
+        },
+        VmTestCase {
+            // closures keep the doc comment of the function they wrap
+            input: "let f = fn(x) { /// Add x\n let g = fn(y) { x + y }; g }; help(f(1))",
+            expected: Object::Str("Add x".into()),
+        },
+        VmTestCase {
+            input: "/* not documented */ let f = fn() { 1 }; help(f)",
+            expected: Object::Nil,
+        },
+        VmTestCase {
+            input: "help(len)",
+            expected: Object::Nil,
+        },
+    ];
+    run_vm_tests(&tests);
+
+    let tests = vec![VmTestCaseErr {
+        input: "help(1)",
+        expected: "help: unsupported argument",
+    }];
+    run_vm_negative_tests(&tests);
+}